@@ -0,0 +1,355 @@
+use anyhow::{Context, Result};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{header, Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tracing::{debug, info};
+
+use crate::relay::{RelayJob, RelayOutcome, RelayRequest};
+
+/// Failed packets kept per relay; the oldest are dropped beyond this
+const DEAD_LETTER_CAPACITY: usize = 1000;
+
+/// Packet statuses kept per relay for `GET /packets`; the oldest are dropped beyond this
+const PACKET_LOG_CAPACITY: usize = 10_000;
+
+/// A packet whose relay attempt failed
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadLetter {
+    pub request: RelayRequest,
+    pub error: String,
+    /// Unix ms
+    pub failed_at_ms: u64,
+}
+
+/// Where a packet is in its relay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketState {
+    /// Handed to the relay worker
+    Detected,
+    Relayed,
+    /// Not relayed on purpose (filtered, already received, ...)
+    Skipped,
+    Failed,
+    /// Skipped because its timeout had passed
+    TimedOut,
+    /// Relayed, and the destination wrote its acknowledgement (`follow_acks`)
+    Acked,
+}
+
+/// Status of one packet as reported by `GET /packets/{channel}/{sequence}`
+#[derive(Debug, Clone, Serialize)]
+pub struct PacketStatus {
+    pub relay: String,
+    /// Source channel
+    pub channel: String,
+    pub sequence: u64,
+    pub status: PacketState,
+    /// Relay transaction, once broadcast
+    pub tx_hash: Option<String>,
+    /// Why the packet was skipped or failed
+    pub reason: Option<String>,
+    /// Unix ms
+    pub detected_at_ms: u64,
+    /// Unix ms of the last status change
+    pub updated_at_ms: u64,
+}
+
+/// Recent packet statuses by (source channel, sequence), oldest first in `order`
+#[derive(Default)]
+struct PacketLog {
+    packets: HashMap<(String, u64), PacketStatus>,
+    order: VecDeque<(String, u64)>,
+}
+
+impl PacketLog {
+    /// The job's status, added as `Detected` (evicting the oldest beyond capacity) if new
+    fn entry(&mut self, relay: &str, job: &RelayJob) -> &mut PacketStatus {
+        let key = (job.packet.src_channel.clone(), job.packet.sequence);
+        if !self.packets.contains_key(&key) {
+            self.order.push_back(key.clone());
+            if self.order.len() > PACKET_LOG_CAPACITY {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.packets.remove(&oldest);
+                }
+            }
+        }
+        self.packets.entry(key.clone()).or_insert_with(|| PacketStatus {
+            relay: relay.to_string(),
+            channel: key.0,
+            sequence: key.1,
+            status: PacketState::Detected,
+            tx_hash: None,
+            reason: None,
+            detected_at_ms: job
+                .detected_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            updated_at_ms: 0,
+        })
+    }
+}
+
+/// Snapshot of one relay as reported by `GET /relays`
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayStatus {
+    pub name: String,
+    pub channel_id: String,
+    pub last_height: u64,
+    pub paused: bool,
+    pub dead_letters: usize,
+    /// State of the watched source channel, e.g. `STATE_OPEN`; unset until it is queried
+    pub channel_state: Option<String>,
+}
+
+/// Runtime state of one relay, updated by its poller and worker and read (or nudged)
+/// through the admin API
+pub struct RelayControl {
+    name: String,
+    channel_id: String,
+    last_height: AtomicU64,
+    paused: AtomicBool,
+    scan: Notify,
+    dead_letters: Mutex<VecDeque<DeadLetter>>,
+    packets: Mutex<PacketLog>,
+    channel_state: Mutex<Option<String>>,
+}
+
+impl RelayControl {
+    pub fn new(name: &str, channel_id: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            channel_id: channel_id.to_string(),
+            last_height: AtomicU64::new(0),
+            paused: AtomicBool::new(false),
+            scan: Notify::new(),
+            dead_letters: Mutex::new(VecDeque::new()),
+            packets: Mutex::new(PacketLog::default()),
+            channel_state: Mutex::new(None),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_height(&self, height: u64) {
+        self.last_height.store(height, Ordering::Relaxed);
+    }
+
+    /// Stops block scanning at the next block boundary; queued packets are still relayed
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+        self.scan.notify_one();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Ends the poller's current wait so it looks for new blocks right away
+    pub fn request_scan(&self) {
+        self.scan.notify_one();
+    }
+
+    /// Resolves on the next `request_scan` (or one made since the last wait)
+    pub async fn scan_requested(&self) {
+        self.scan.notified().await;
+    }
+
+    /// Records the source channel state last seen by the channel cache
+    pub fn set_channel_state(&self, state: &str) {
+        *self.channel_state.lock().unwrap() = Some(state.to_string());
+    }
+
+    pub fn dead_letter(&self, request: RelayRequest, error: String) {
+        let failed_at_ms = now_ms();
+        let mut dead_letters = self.dead_letters.lock().unwrap();
+        if dead_letters.len() == DEAD_LETTER_CAPACITY {
+            dead_letters.pop_front();
+        }
+        dead_letters.push_back(DeadLetter { request, error, failed_at_ms });
+    }
+
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Records a packet handed to the worker
+    pub fn packet_detected(&self, job: &RelayJob) {
+        let mut log = self.packets.lock().unwrap();
+        let packet = log.entry(&self.name, job);
+        (packet.status, packet.tx_hash, packet.reason) = (PacketState::Detected, None, None);
+        packet.updated_at_ms = now_ms();
+    }
+
+    /// Records the outcome of a relay attempt; `timed_out` marks a skip caused by the
+    /// packet's timeout
+    pub fn packet_finished(&self, job: &RelayJob, outcome: &RelayOutcome, timed_out: bool) {
+        let mut log = self.packets.lock().unwrap();
+        let packet = log.entry(&self.name, job);
+        (packet.status, packet.tx_hash, packet.reason) = match outcome {
+            RelayOutcome::Relayed { tx_hash, .. } => (PacketState::Relayed, tx_hash.clone(), None),
+            RelayOutcome::Skipped { reason } if timed_out => (PacketState::TimedOut, None, Some(reason.clone())),
+            RelayOutcome::Skipped { reason } => (PacketState::Skipped, None, Some(reason.clone())),
+            RelayOutcome::Failed { error } => (PacketState::Failed, None, Some(error.clone())),
+        };
+        packet.updated_at_ms = now_ms();
+    }
+
+    /// Records the destination's acknowledgement of a relayed packet
+    pub fn packet_acked(&self, channel: &str, sequence: u64) {
+        let mut log = self.packets.lock().unwrap();
+        if let Some(packet) = log.packets.get_mut(&(channel.to_string(), sequence)) {
+            packet.status = PacketState::Acked;
+            packet.updated_at_ms = now_ms();
+        }
+    }
+
+    /// Status of packet `sequence` sent on source channel `channel`, if still tracked
+    pub fn packet(&self, channel: &str, sequence: u64) -> Option<PacketStatus> {
+        self.packets.lock().unwrap().packets.get(&(channel.to_string(), sequence)).cloned()
+    }
+
+    pub fn status(&self) -> RelayStatus {
+        RelayStatus {
+            name: self.name.clone(),
+            channel_id: self.channel_id.clone(),
+            last_height: self.last_height.load(Ordering::Relaxed),
+            paused: self.is_paused(),
+            dead_letters: self.dead_letters.lock().unwrap().len(),
+            channel_state: self.channel_state.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Relay controls by relay name
+pub type RelayControls = BTreeMap<String, Arc<RelayControl>>;
+
+/// Serves the control API on `listen_addr`. Every request needs `Authorization: Bearer <token>`.
+///
+/// - `GET /relays` – status of every relay
+/// - `POST /relays/{name}/pause`, `POST /relays/{name}/resume` – stop/restart block scanning
+/// - `POST /relays/{name}/scan` – look for new blocks now instead of after the poll interval
+/// - `GET /relays/{name}/dead-letters` – packets whose relay attempt failed
+///
+/// `GET /packets/{channel}/{sequence}` (status of a recently detected packet) is read-only
+/// and needs no token, so frontends can show transfer progress.
+pub async fn serve(listen_addr: &str, token: String, relays: Arc<RelayControls>) -> Result<()> {
+    let listener = TcpListener::bind(listen_addr)
+        .await
+        .context(format!("Failed to bind admin server to {}", listen_addr))?;
+
+    info!("Admin API listening on {}", listen_addr);
+
+    let token = Arc::new(token);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let token = token.clone();
+        let relays = relays.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| {
+                let response = respond(&req, &token, &relays);
+                async move { Ok::<_, Infallible>(response) }
+            });
+            if let Err(e) = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                debug!("Admin connection error: {}", e);
+            }
+        });
+    }
+}
+
+fn respond(req: &Request<Incoming>, token: &str, relays: &RelayControls) -> Response<Full<Bytes>> {
+    let path: Vec<&str> = req.uri().path().trim_matches('/').split('/').collect();
+    if let (&Method::GET, ["packets", channel, sequence]) = (req.method(), path.as_slice()) {
+        let Ok(sequence) = sequence.parse::<u64>() else {
+            return json(StatusCode::BAD_REQUEST, &serde_json::json!({ "error": "invalid sequence" }));
+        };
+        return match relays.values().find_map(|relay| relay.packet(channel, sequence)) {
+            Some(packet) => json(StatusCode::OK, &packet),
+            None => json(
+                StatusCode::NOT_FOUND,
+                &serde_json::json!({ "error": format!("packet {} on {} is not tracked", sequence, channel) }),
+            ),
+        };
+    }
+
+    if !authorized(req, token) {
+        return json(StatusCode::UNAUTHORIZED, &serde_json::json!({ "error": "unauthorized" }));
+    }
+
+    match (req.method(), path.as_slice()) {
+        (&Method::GET, ["relays"]) => {
+            let statuses: Vec<RelayStatus> = relays.values().map(|relay| relay.status()).collect();
+            json(StatusCode::OK, &statuses)
+        }
+        (method, ["relays", name, action]) => {
+            let Some(relay) = relays.get(*name) else {
+                return json(StatusCode::NOT_FOUND, &serde_json::json!({ "error": format!("unknown relay '{}'", name) }));
+            };
+            match (method, *action) {
+                (&Method::GET, "dead-letters") => json(StatusCode::OK, &relay.dead_letters()),
+                (&Method::POST, "pause") => {
+                    info!("[{}] Paused via admin API", relay.name());
+                    relay.pause();
+                    json(StatusCode::OK, &relay.status())
+                }
+                (&Method::POST, "resume") => {
+                    info!("[{}] Resumed via admin API", relay.name());
+                    relay.resume();
+                    json(StatusCode::OK, &relay.status())
+                }
+                (&Method::POST, "scan") => {
+                    relay.request_scan();
+                    json(StatusCode::ACCEPTED, &relay.status())
+                }
+                _ => not_found(),
+            }
+        }
+        _ => not_found(),
+    }
+}
+
+/// Compares hashes so the check takes the same time however much of the token matches
+fn authorized(req: &Request<Incoming>, token: &str) -> bool {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| blake3::hash(given.as_bytes()) == blake3::hash(token.as_bytes()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn not_found() -> Response<Full<Bytes>> {
+    json(StatusCode::NOT_FOUND, &serde_json::json!({ "error": "not found" }))
+}
+
+fn json<T: Serialize>(status: StatusCode, body: &T) -> Response<Full<Bytes>> {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/json"));
+    response
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::config::AlertsConfig;
+
+/// Category of an alert, used to select which events are sent
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    RelayFailures,
+    ChainHalted,
+    DeadLetterQueue,
+    UnrelayedPacket,
+}
+
+/// Condition an operator should be paged about
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertEvent {
+    /// A relay failed `consecutive` times in a row
+    RelayFailures { relay: String, consecutive: u32, last_error: String },
+    /// The source chain height has not advanced for `stalled_secs`
+    ChainHalted { chain: String, height: u64, stalled_secs: u64 },
+    /// Packets gave up retrying and were parked for manual inspection
+    DeadLetterQueue { relay: String, size: usize },
+    /// Monitor mode: a sent packet was not received on the destination in time
+    UnrelayedPacket { relay: String, channel: String, sequence: u64, waited_secs: u64 },
+}
+
+impl AlertEvent {
+    pub fn kind(&self) -> AlertKind {
+        match self {
+            AlertEvent::RelayFailures { .. } => AlertKind::RelayFailures,
+            AlertEvent::ChainHalted { .. } => AlertKind::ChainHalted,
+            AlertEvent::DeadLetterQueue { .. } => AlertKind::DeadLetterQueue,
+            AlertEvent::UnrelayedPacket { .. } => AlertKind::UnrelayedPacket,
+        }
+    }
+}
+
+/// Alert sink. `notify` must not block the caller (poll loop) –
+/// implementations deliver in the background.
+pub trait Notifier: Send + Sync {
+    fn notify(&self, event: AlertEvent);
+}
+
+/// POSTs each alert as JSON to a webhook URL
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String,
+    kinds: Vec<AlertKind>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str, kinds: Vec<AlertKind>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            kinds,
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&self, event: AlertEvent) {
+        if !self.kinds.contains(&event.kind()) {
+            debug!("Alert {:?} not enabled, skipping", event.kind());
+            return;
+        }
+
+        let client = self.client.clone();
+        let url = self.url.clone();
+        tokio::spawn(async move {
+            match client.post(&url).json(&event).send().await {
+                Ok(res) if res.status().is_success() => debug!("Alert delivered: {:?}", event.kind()),
+                Ok(res) => warn!("Alert webhook returned {}", res.status()),
+                Err(e) => warn!("Failed to deliver alert to webhook: {}", e),
+            }
+        });
+    }
+}
+
+/// Builds the configured notifier (None if alerting is not configured)
+pub fn from_config(config: &AlertsConfig) -> Option<Arc<dyn Notifier>> {
+    config
+        .webhook_url
+        .as_ref()
+        .map(|url| Arc::new(WebhookNotifier::new(url, config.events.clone())) as Arc<dyn Notifier>)
+}
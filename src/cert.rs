@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveTime, Utc};
+use rcgen::{date_time_ymd, CertificateParams, KeyPair};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls_pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::config::TransportConfig;
+use crate::metrics;
+
+/// How often the rotation task checks the certificate
+const CERT_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// The peer server's self-signed certificate. Serves as the TLS certificate resolver,
+/// so a regenerated certificate applies to new handshakes without rebinding the server;
+/// established connections keep the certificate they were opened with.
+#[derive(Debug)]
+pub struct ServerCert {
+    inner: RwLock<Issued>,
+}
+
+#[derive(Debug)]
+struct Issued {
+    key: Arc<CertifiedKey>,
+    not_after: DateTime<Utc>,
+}
+
+impl ServerCert {
+    /// Generates a certificate for `localhost`/`127.0.0.1` valid for `validity_days`
+    pub fn self_signed(validity_days: u32) -> Result<Self> {
+        Ok(Self {
+            inner: RwLock::new(issue(validity_days)?),
+        })
+    }
+
+    /// End of the current certificate's validity (`not_after`)
+    pub fn cert_expiry(&self) -> DateTime<Utc> {
+        self.inner.read().unwrap().not_after
+    }
+
+    /// Whole days until `cert_expiry`; negative once expired
+    pub fn days_until_expiry(&self) -> i64 {
+        (self.cert_expiry() - Utc::now()).num_days()
+    }
+
+    /// Replaces the certificate with a freshly generated one
+    pub fn regenerate(&self, validity_days: u32) -> Result<()> {
+        let issued = issue(validity_days)?;
+        info!("Regenerated the peer server certificate, valid until {}", issued.not_after);
+        *self.inner.write().unwrap() = issued;
+        Ok(())
+    }
+
+    /// Publishes days until expiry, warns within `cert_expiry_warn_days` and, with
+    /// `cert_auto_rotate`, regenerates the certificate there. Runs until the process exits.
+    pub async fn watch_expiry(self: Arc<Self>, config: TransportConfig) {
+        loop {
+            let mut days = self.days_until_expiry();
+            if days < i64::from(config.cert_expiry_warn_days) {
+                if config.cert_auto_rotate {
+                    match self.regenerate(config.cert_validity_days) {
+                        Ok(()) => days = self.days_until_expiry(),
+                        Err(e) => error!("Failed to regenerate the peer server certificate: {:#}", e),
+                    }
+                } else {
+                    warn!(
+                        "Peer server certificate expires in {} days ({}); restart to regenerate it",
+                        days,
+                        self.cert_expiry()
+                    );
+                }
+            }
+            metrics::set_gauge("transport_cert_expiry_days", &[], days as f64);
+
+            tokio::time::sleep(CERT_CHECK_INTERVAL).await;
+        }
+    }
+}
+
+impl ResolvesServerCert for ServerCert {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.inner.read().unwrap().key.clone())
+    }
+}
+
+fn issue(validity_days: u32) -> Result<Issued> {
+    let now = Utc::now();
+    // Backdated a day so peers with a slightly slow clock accept it
+    let not_before = now - ChronoDuration::days(1);
+    // Certificate dates have day precision: valid until midnight (UTC) at the end of the period
+    let not_after = (now + ChronoDuration::days(i64::from(validity_days)))
+        .date_naive()
+        .and_time(NaiveTime::MIN)
+        .and_utc();
+
+    let mut params = CertificateParams::new(vec!["localhost".to_string(), "127.0.0.1".to_string()])?;
+    params.not_before = date_time_ymd(not_before.year(), not_before.month() as u8, not_before.day() as u8);
+    params.not_after = date_time_ymd(not_after.year(), not_after.month() as u8, not_after.day() as u8);
+
+    let key_pair = KeyPair::generate()?;
+    let cert = params.self_signed(&key_pair)?;
+
+    let key_der = PrivateKeyDer::from(PrivatePkcs8KeyDer::from(key_pair.serialize_der()));
+    let signing_key = rustls::crypto::aws_lc_rs::sign::any_supported_type(&key_der)
+        .map_err(|e| anyhow!("Unsupported certificate key: {}", e))?;
+
+    Ok(Issued {
+        key: Arc::new(CertifiedKey::new(vec![cert.der().clone()], signing_key)),
+        not_after,
+    })
+}
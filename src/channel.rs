@@ -0,0 +1,158 @@
+use anyhow::{anyhow, Result};
+use prost::Message;
+use std::collections::HashMap;
+use std::time::Duration;
+use tendermint_rpc::Client;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use ibc_proto::ibc::core::channel::v1::{
+    Order, QueryChannelClientStateRequest, QueryChannelClientStateResponse, QueryChannelRequest,
+    QueryChannelResponse, State,
+};
+
+use crate::config::{RetryConfig, RetryPolicy, RetryStep};
+use crate::retry::retry;
+use crate::rpc::{with_timeout, RpcClient};
+
+/// Channel metadata needed when relaying (ordering, client, counterparty)
+#[derive(Debug, Clone)]
+pub struct ChannelInfo {
+    pub port_id: String,
+    pub channel_id: String,
+    pub ordering: Order,
+    /// Channel state name, e.g. `STATE_OPEN`
+    pub state: String,
+    pub version: String,
+    pub connection_id: String,
+    pub client_id: String,
+    pub counterparty_port_id: String,
+    pub counterparty_channel_id: String,
+}
+
+impl ChannelInfo {
+    pub fn is_ordered(&self) -> bool {
+        self.ordering == Order::Ordered
+    }
+
+    /// Closed channels accept no more packets; only timeouts can still be relayed
+    pub fn is_closed(&self) -> bool {
+        self.state == State::Closed.as_str_name()
+    }
+}
+
+/// Channel metadata fetched once per channel and refreshed after `refresh_interval`
+/// (channel upgrades can change ordering and version, and a channel can be closed)
+pub struct ChannelCache {
+    client: RpcClient,
+    rpc_timeout: Duration,
+    /// `None` never refreshes
+    refresh_interval: Option<Duration>,
+    channels: HashMap<String, (ChannelInfo, Instant)>,
+    /// `[retry.client_state]`
+    retry: RetryPolicy,
+}
+
+impl ChannelCache {
+    pub fn new(client: RpcClient, rpc_timeout: Duration, refresh_interval: Option<Duration>) -> Self {
+        Self {
+            client,
+            rpc_timeout,
+            refresh_interval,
+            channels: HashMap::new(),
+            retry: RetryConfig::default().policy(RetryStep::ClientState),
+        }
+    }
+
+    /// Retry policy of the channel and client state queries
+    pub fn set_retry(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// Cached metadata for `channel_id`, if it has been loaded
+    pub fn channel_info(&self, channel_id: &str) -> Option<&ChannelInfo> {
+        self.channels.get(channel_id).map(|(info, _)| info)
+    }
+
+    /// Fetches and caches the metadata of `port_id/channel_id`
+    pub async fn load(&mut self, port_id: &str, channel_id: &str) -> Result<&ChannelInfo> {
+        let info = retry(&self.retry, RetryStep::ClientState, || self.query(port_id, channel_id)).await?;
+        info!(
+            "Channel {}/{}: {:?}, {}, connection {}, client {}, counterparty {}/{}",
+            info.port_id, info.channel_id, info.ordering, info.state, info.connection_id,
+            info.client_id, info.counterparty_port_id, info.counterparty_channel_id
+        );
+
+        self.channels.insert(channel_id.to_string(), (info, Instant::now()));
+        Ok(&self.channels[channel_id].0)
+    }
+
+    /// Re-queries every entry older than the refresh interval; stale data is kept on failure
+    pub async fn refresh_stale(&mut self) {
+        let Some(interval) = self.refresh_interval else { return };
+
+        let stale: Vec<(String, String)> = self
+            .channels
+            .values()
+            .filter(|(_, fetched_at)| fetched_at.elapsed() >= interval)
+            .map(|(info, _)| (info.port_id.clone(), info.channel_id.clone()))
+            .collect();
+
+        for (port_id, channel_id) in stale {
+            if let Err(e) = self.load(&port_id, &channel_id).await {
+                warn!("Failed to refresh channel {}/{}: {}", port_id, channel_id, e);
+            }
+        }
+    }
+
+    async fn query(&self, port_id: &str, channel_id: &str) -> Result<ChannelInfo> {
+        let request = QueryChannelRequest {
+            port_id: port_id.to_string(),
+            channel_id: channel_id.to_string(),
+        };
+        let response: QueryChannelResponse =
+            self.abci_query("/ibc.core.channel.v1.Query/Channel", request.encode_to_vec()).await?;
+        let channel = response
+            .channel
+            .ok_or_else(|| anyhow!("Channel {}/{} not found", port_id, channel_id))?;
+
+        let request = QueryChannelClientStateRequest {
+            port_id: port_id.to_string(),
+            channel_id: channel_id.to_string(),
+        };
+        let response: QueryChannelClientStateResponse = self
+            .abci_query("/ibc.core.channel.v1.Query/ChannelClientState", request.encode_to_vec())
+            .await?;
+        let client_id = response
+            .identified_client_state
+            .map(|state| state.client_id)
+            .unwrap_or_default();
+
+        let counterparty = channel.counterparty.clone().unwrap_or_default();
+        Ok(ChannelInfo {
+            port_id: port_id.to_string(),
+            channel_id: channel_id.to_string(),
+            ordering: channel.ordering(),
+            state: channel.state().as_str_name().to_string(),
+            version: channel.version,
+            connection_id: channel.connection_hops.first().cloned().unwrap_or_default(),
+            client_id,
+            counterparty_port_id: counterparty.port_id,
+            counterparty_channel_id: counterparty.channel_id,
+        })
+    }
+
+    async fn abci_query<R: Message + Default>(&self, path: &str, data: Vec<u8>) -> Result<R> {
+        let res = with_timeout(
+            self.rpc_timeout,
+            "abci_query",
+            self.client.abci_query(Some(path.to_string()), data, None, false),
+        )
+        .await?;
+
+        if res.code.is_err() {
+            return Err(anyhow!("{} query failed: {}", path, res.log));
+        }
+        Ok(R::decode(res.value.as_slice())?)
+    }
+}
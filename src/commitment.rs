@@ -0,0 +1,138 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ibc_proto::ibc::core::commitment::v1::MerkleProof;
+use ics23::commitment_proof::Proof;
+use ics23::{CommitmentProof, ExistenceProof, HostFunctionsManager, ProofSpec};
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+use crate::relay::RelayRequest;
+
+/// Store the IBC module commits to; the second proof links it to the app hash
+const IBC_STORE_KEY: &[u8] = b"ibc";
+
+/// A packet as committed on its source chain
+#[derive(Debug, Clone)]
+pub struct CommittedPacket {
+    pub port_id: String,
+    pub channel_id: String,
+    pub sequence: u64,
+    /// `(revision_number, revision_height)`, `(0, 0)` without a height timeout
+    pub timeout_height: (u64, u64),
+    /// Unix ns, 0 without a timestamp timeout
+    pub timeout_timestamp: u64,
+    /// Packet data exactly as sent
+    pub data: Vec<u8>,
+}
+
+impl TryFrom<&RelayRequest> for CommittedPacket {
+    type Error = anyhow::Error;
+
+    fn try_from(request: &RelayRequest) -> Result<Self> {
+        let timeout_height = match request.timeout_height.trim() {
+            "" => (0, 0),
+            height => {
+                let (number, height) = height
+                    .split_once('-')
+                    .ok_or_else(|| anyhow!("timeout_height '{}' is not <revision>-<height>", height))?;
+                (number.parse()?, height.parse()?)
+            }
+        };
+        Ok(Self {
+            port_id: request.src_port.clone(),
+            channel_id: request.src_channel.clone(),
+            sequence: request.sequence,
+            timeout_height,
+            timeout_timestamp: request.timeout_timestamp.nanos(),
+            data: crate::ibc::decode_packet_data(&request.packet_data_hex)?,
+        })
+    }
+}
+
+impl CommittedPacket {
+    /// Key of the commitment in the IBC store
+    pub fn commitment_path(&self) -> String {
+        format!("commitments/ports/{}/channels/{}/sequences/{}", self.port_id, self.channel_id, self.sequence)
+    }
+
+    /// ICS-04 packet commitment: sha256 of the timeout timestamp, timeout height
+    /// and sha256 of the data
+    pub fn commitment(&self) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.timeout_timestamp.to_be_bytes());
+        hasher.update(self.timeout_height.0.to_be_bytes());
+        hasher.update(self.timeout_height.1.to_be_bytes());
+        hasher.update(Sha256::digest(&self.data));
+        hasher.finalize().to_vec()
+    }
+}
+
+/// Verifies an encoded IBC `MerkleProof` (as returned by `query_commitment_proof`) that
+/// `packet` is committed under `app_hash`, the app hash at the proof height (carried by the
+/// header of the next block). No chain connection is needed. The error names the step
+/// that failed.
+pub fn verify_packet_proof(packet: &CommittedPacket, proof: &[u8], app_hash: &[u8]) -> Result<()> {
+    let proof = MerkleProof::decode(proof).context("Proof is not an encoded MerkleProof")?;
+    let [store_proof, root_proof] = proof.proofs.as_slice() else {
+        bail!("Expected 2 proofs (IBC store, app hash), got {}", proof.proofs.len());
+    };
+
+    let path = packet.commitment_path();
+    let commitment = packet.commitment();
+    let store_exist = existence(store_proof).context("IBC store proof")?;
+    if store_exist.key != path.as_bytes() {
+        bail!(
+            "IBC store proof is for key '{}', not '{}'",
+            String::from_utf8_lossy(&store_exist.key),
+            path
+        );
+    }
+    if store_exist.value != commitment {
+        bail!(
+            "IBC store proof commits {}, but the packet's commitment is {} (packet data or timeouts differ)",
+            hex::encode(&store_exist.value),
+            hex::encode(&commitment)
+        );
+    }
+    let store_root = verify_exist(store_proof, store_exist, &ics23::iavl_spec(), &path).context("IBC store proof")?;
+
+    let root_exist = existence(root_proof).context("App hash proof")?;
+    if root_exist.key != IBC_STORE_KEY {
+        bail!("App hash proof is for store '{}', not 'ibc'", String::from_utf8_lossy(&root_exist.key));
+    }
+    if root_exist.value != store_root {
+        bail!(
+            "App hash proof commits IBC store root {}, but the store proof computes {}",
+            hex::encode(&root_exist.value),
+            hex::encode(&store_root)
+        );
+    }
+    let root = verify_exist(root_proof, root_exist, &ics23::tendermint_spec(), "ibc").context("App hash proof")?;
+
+    if root != app_hash {
+        bail!(
+            "Proof computes app hash {}, expected {} (wrong proof height or app hash?)",
+            hex::encode_upper(&root),
+            hex::encode_upper(app_hash)
+        );
+    }
+    Ok(())
+}
+
+fn existence(proof: &CommitmentProof) -> Result<&ExistenceProof> {
+    match &proof.proof {
+        Some(Proof::Exist(exist)) => Ok(exist),
+        Some(Proof::Nonexist(_)) => bail!("is a non-membership proof: the key is not in the store"),
+        Some(_) => bail!("batch proofs are not supported"),
+        None => bail!("is empty"),
+    }
+}
+
+/// Root the existence proof computes, after checking it against `spec`
+fn verify_exist(proof: &CommitmentProof, exist: &ExistenceProof, spec: &ProofSpec, key: &str) -> Result<Vec<u8>> {
+    let root = ics23::calculate_existence_root::<HostFunctionsManager>(exist)
+        .map_err(|e| anyhow!("cannot compute the root: {}", e))?;
+    if !ics23::verify_membership::<HostFunctionsManager>(proof, spec, &root, &exist.key, &exist.value) {
+        bail!("membership of '{}' does not verify (malformed proof path)", key);
+    }
+    Ok(root)
+}
@@ -1,8 +1,347 @@
+use regex::Regex;
 use serde::Deserialize;
-use std::collections::HashMap;
-use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{anyhow, bail, Context, Result};
+use notify::{RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tracing::{error, info};
 
-#[derive(Deserialize, Clone, Debug)]
+use crate::alert::AlertKind;
+use crate::proof;
+use crate::rpc::DEFAULT_RPC_TIMEOUT_MS;
+use crate::signer::{validate_address, KeySigner, KeyType, Signer, SigningKey, DEFAULT_HD_PATH};
+use crate::submit::{FeePayment, GasPrice};
+
+/// Quiet period after a config file change before it is reloaded (editors save in several steps)
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Wait for new blocks once caught up when `poll_interval_ms` is unset
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 6000;
+
+/// Pause between two scanned blocks when `block_step_delay_ms` is unset
+pub const DEFAULT_BLOCK_STEP_DELAY_MS: u64 = 200;
+
+/// Channel metadata refresh interval when `channel_refresh_secs` is unset
+pub const DEFAULT_CHANNEL_REFRESH_SECS: u64 = 3600;
+
+/// Packet data size limit when `max_packet_bytes` is unset (1 MiB)
+pub const DEFAULT_MAX_PACKET_BYTES: usize = 1024 * 1024;
+
+/// Entries kept per packet tracker set when `tracker_capacity` is unset
+pub const DEFAULT_TRACKER_CAPACITY: usize = 10_000;
+
+/// Largest accepted `relay_workers` per destination
+pub const MAX_RELAY_WORKERS: usize = 64;
+
+/// Startup connection retries when `connect_retries` is unset
+pub const DEFAULT_CONNECT_RETRIES: u32 = 5;
+
+/// Peer server address when `quic_listen` is unset and a relay forwards to peers
+pub const DEFAULT_QUIC_LISTEN: &str = "0.0.0.0:4433";
+
+/// Peer server address when `quic_listen` is unset and no relay forwards to peers
+pub const DEFAULT_QUIC_LISTEN_LOCAL: &str = "127.0.0.1:4433";
+
+/// Largest accepted `confirmations`; beyond this relaying lags by hours on most chains
+pub const MAX_CONFIRMATIONS: u64 = 1000;
+
+/// Gas assumed per MsgRecvPacket when `gas_estimate` is unset
+pub const DEFAULT_GAS_ESTIMATE: u64 = 200_000;
+
+/// Packet event kinds processed when `watched_events` is unset. Older SDK versions
+/// emit `recv_packet` where newer ones emit `write_acknowledgement` (or both).
+pub const DEFAULT_WATCHED_EVENTS: &[&str] = &["send_packet", "write_acknowledgement", "recv_packet"];
+
+/// Commented example configuration written by `aero-relay gen-config`.
+/// Must stay parseable by `Config::load`.
+pub const SAMPLE_CONFIG: &str = r#"# AeroRelay configuration file
+# Generated by `aero-relay gen-config` – adjust the relay pair below to your setup.
+# Any string value may reference environment variables as ${VAR} (e.g. src_rpc = "${HUB_RPC}");
+# an unset variable is an error. Write $$ for a literal $.
+
+# Address the peer server (QUIC, or TCP+TLS with [transport] protocol = "tcp") listens on;
+# change it to run several instances on one host, or to accept packets from other nodes
+# (default: "0.0.0.0:4433" if a relay sets forward_to, else "127.0.0.1:4433")
+# quic_listen = "0.0.0.0:4433"
+
+[[relays]]
+# Unique, human-readable name of this relay (used in logs)
+name = "hub-to-osmosis"
+
+# Source chain: packets are detected here (send_packet / write_acknowledgement / recv_packet events).
+# RPC URLs may be http(s):// or unix:///path/to/socket
+src_chain = "cosmoshub-4"
+src_rpc = "https://cosmos-rpc.publicnode.com:443"
+# Backup source RPCs, tried in order when src_rpc keeps failing (default: [])
+# src_rpc_fallbacks = ["https://cosmoshub.tendermintrpc.lava.build:443"]
+# How the source RPC is picked among src_rpc and its fallbacks: "failover" sticks to one
+# and moves on after repeated failures; "latency" sends each call to the healthy endpoint
+# with the lowest abci_info round trip (re-measured every 30 s) and quarantines endpoints
+# that keep failing (default: "failover")
+# src_rpc_selection = "failover"
+# Source WebSocket endpoint: send_packet txs of src_channel are then received by event
+# subscription as they are committed instead of by fetching every block. Blocks missed while
+# disconnected are scanned on reconnect. Only send_packet is subscribed to, so this needs
+# watched_events = ["send_packet"]; polling is used otherwise, if the endpoint is unreachable
+# at startup, with confirmations > 0 or with enable_channel_v2 (default: unset, poll)
+# src_ws = "wss://cosmos-rpc.publicnode.com:443/websocket"
+src_channel = "channel-141"
+src_port = "transfer"
+
+# Destination chain: MsgRecvPacket is formed for this side
+dst_chain = "osmosis-1"
+dst_rpc = "https://osmosis-rpc.publicnode.com:443"
+dst_channel = "channel-0"
+dst_port = "transfer"
+
+# Destination account relay messages are signed with, e.g. "${RELAYER_SIGNER}". Required
+# unless dst_signers or private_key_dst provide the signer.
+signer = "osmo1ufml4llk38hsakhh4czken98fj7fplc7qzwpn5"
+
+# Optional signing keys (default: unset).
+# Never commit real keys – reference environment variables instead.
+# Accepted encodings: 32 hex-encoded bytes, a bech32 "...priv1..." key, or a BIP39
+# mnemonic (secp256k1 only, derived along hd_path). Loading fails on a malformed key.
+# private_key_src = "${AERO_PRIVATE_KEY_SRC}"
+# private_key_dst = "${AERO_PRIVATE_KEY_DST}"
+# key_type = "secp256k1"   # or "ed25519" (default: "secp256k1")
+# hd_path = "m/44'/118'/0'/0/0"   # mnemonic derivation path (default: Cosmos coin type 118)
+# With private_key_dst, account_prefix is required to derive the signer address.
+
+# Destination signer accounts, used round-robin to parallelize broadcasts (default: [])
+# dst_signers = []
+
+# Timeout for each RPC call in milliseconds (default: 10000)
+# rpc_timeout_ms = 10000
+
+# Retries (with exponential backoff, 1 s up to 30 s) while no source RPC is reachable
+# at startup, e.g. a node still booting in the same compose stack (default: 5)
+# connect_retries = 5
+
+# Refuse to start unless src_rpc / dst_rpc report src_chain / dst_chain as their chain ID,
+# so a mistyped RPC URL cannot relay to the wrong network (default: true)
+# verify_chain_id = true
+
+# Packets with more decoded data than this are logged and skipped; bounds memory use
+# and ZK proof cost for adversarial packets (default: 1048576 = 1 MiB)
+# max_packet_bytes = 1048576
+
+# Packet data that is not JSON (e.g. protobuf-encoded app packets) is relayed verbatim;
+# set to true to skip such packets instead (default: false)
+# strict_json = false
+
+# Before relaying a packet, look for a MsgRecvPacket of it in the destination mempool
+# (unconfirmed_txs) and skip it if another relayer already submitted it (default: false)
+# mempool_check = false
+
+# After relaying a packet, watch the destination for its write_acknowledgement and form
+# the MsgAcknowledgement back to the source, with the proof of the acknowledgement and
+# signed by src_signer (a source-chain address, required then). Like MsgRecvPacket it is
+# only logged, not broadcast yet. The admin packet status moves from "relayed" to "acked"
+# (default: false)
+# follow_acks = false
+# src_signer = "cosmos1ufml4llk38hsakhh4czken98fj7fplc7gea39x"
+
+# Wait before asking for new blocks once caught up with the tip, and pause between two
+# scanned blocks while catching up; tune to the source's block time (default: 6000 / 200)
+# poll_interval_ms = 6000
+# block_step_delay_ms = 200
+
+# Channel metadata (ordering, client, counterparty) is cached at startup and
+# re-queried after this many seconds, e.g. to pick up channel upgrades or a closed channel
+# (receives on a closed channel are skipped); 0 never refreshes (default: 3600)
+# channel_refresh_secs = 3600
+
+# Packets in flight and sequences already relayed are remembered for deduplication,
+# each up to this many entries; the lowest sequences are evicted first. A packet at or
+# below an evicted sequence is only relayed after the destination shows no receipt
+# for it (default: 10000)
+# tracker_capacity = 10000
+
+# Relay workers per destination, to parallelize relaying under load. Worker i signs with
+# the i-th of dst_signers (round-robin past the end). Packets of an ORDERED channel always
+# go to the same worker; unordered packets wait in one queue shared by all workers and are
+# taken by whichever is free first. At most 64 (default: 1)
+# relay_workers = 1
+
+# Bech32 account prefix of the destination chain; enables signer address validation (default: unset)
+# account_prefix = "osmo"
+
+# Detect IBC v2 (Eureka) packets addressed by client ID – experimental, detection only (default: false)
+# enable_channel_v2 = false
+
+# How new blocks are searched for packets: "blocks" reads block_results of every block;
+# "tx_search" asks the node's tx index for the channel's packet txs only, far fewer calls
+# on low-traffic channels. Falls back to "blocks" if the node has tx indexing disabled (default: "blocks")
+# scan_strategy = "blocks"
+
+# Only blocks at least this many blocks below the source tip are scanned, a safety margin
+# against reorgs on chains without instant finality (CometBFT chains need none). Adds
+# about confirmations × block time of latency; at most 1000 (default: 0)
+# confirmations = 0
+
+# Destination gas price and gas per MsgRecvPacket, used for fee estimates and the
+# relay_fee_estimated metric (default: unset / 200000)
+# gas_price = "0.025uosmo"
+# gas_estimate = 200000
+
+# Let another account pay relay fees: fee_granter through a fee grant (x/feegrant) to
+# the signer, or fee_payer, which then has to sign as well. The granter account is
+# looked up on the destination at startup (default: the signer pays)
+# fee_granter = "osmo1sponsor..."
+# fee_payer = "osmo1payer..."
+
+# Forward detected packets (with proof) to a peer aero-relay node that submits them;
+# the peer must run a relay with the same name (default: submit locally)
+# forward_to = "10.0.0.2:4433"
+
+# Packet event kinds to process (default: all three)
+# watched_events = ["send_packet", "write_acknowledgement", "recv_packet"]
+
+# Log ibc/<hash> denoms with their base denom and path, looked up (and cached)
+# on the source chain; display only (default: false)
+# resolve_denoms = false
+
+# Only relay transfers whose sender / receiver address matches a regex, e.g. for a
+# relayer dedicated to one dApp; anchor with ^...$ for an exact match (default: all)
+# sender_filter = "^osmo1abc"
+# receiver_filter = "^osmo1contractaddress$"
+
+# Further destinations every packet is also relayed to (e.g. a backup or monitoring chain),
+# each by its own worker so a failing destination does not hold up the others. The chain
+# needs a client of the source and a channel matching dst_channel. Keep this after all
+# other relay keys (default: none)
+# [[relays.extra_destinations]]
+# chain = "osmo-backup-1"
+# rpc = "https://backup-rpc.example.com:443"
+
+# Values inherited by every relay that does not set them itself (default: none)
+# [defaults]
+# rpc_timeout_ms = 10000
+# connect_retries = 5
+# max_packet_bytes = 1048576
+# strict_json = false
+# mempool_check = false
+# follow_acks = false
+# poll_interval_ms = 6000
+# block_step_delay_ms = 200
+# tracker_capacity = 10000
+# relay_workers = 1
+# enable_channel_v2 = false
+# verify_chain_id = true
+# scan_strategy = "blocks"
+# src_rpc_selection = "failover"
+# confirmations = 0
+# channel_refresh_secs = 3600
+# gas_price = "0.025uosmo"
+# gas_estimate = 200000
+# fee_granter = "osmo1sponsor..."
+# sender_filter = "^osmo1abc"
+
+# Alerting (default: disabled)
+# [alerts]
+# webhook_url = "${AERO_ALERT_WEBHOOK}"
+# events = ["relay_failures", "chain_halted", "dead_letter_queue", "unrelayed_packet"]
+# failure_threshold = 5
+# stall_after_secs = 120
+
+# Publish every relay outcome (JSON) to a NATS subject for downstream consumers.
+# Fire-and-forget: events the broker cannot take are dropped and counted in
+# event_sink_dropped_total, relaying never waits for it (default: disabled)
+# [events]
+# nats_url = "nats://127.0.0.1:4222"
+# subject = "aero-relay.packets"
+
+# Retries of the network steps of a relay. The top-level values apply to every step,
+# [retry.<step>] overrides them for one step. Only transient failures (timeouts,
+# connection errors) are retried, never an answer from the chain.
+#   proof_query  – commitment proof queries: read-only, always safe to retry
+#   client_state – channel and client state queries: read-only, always safe to retry
+#   simulate     – tx simulation: executes nothing on chain, safe to retry
+# Txs are not broadcast yet, so there is no broadcast step.
+# [retry]
+# max_attempts = 3
+# initial_backoff_ms = 500
+# max_backoff_ms = 10000
+# [retry.proof_query]
+# max_attempts = 5
+
+# Packet proof generation
+# [zk]
+# backend = "halo2"   # "halo2" (encryption-proof feature) | "blake3" | "none"; default: halo2 if built with the feature
+# scheme = "kzg"   # halo2 commitment scheme: "kzg" (trusted setup) | "ipa" (transparent)
+# k = 11   # halo2 circuit size (2^k rows); startup fails with the minimum viable k if too small
+# proof_timeout_ms = 60000
+# on_proof_timeout = "proceed"   # or "skip" to drop packets whose proof timed out
+
+# Prometheus metrics (/metrics) and readiness probe (/ready) (default: disabled)
+# [metrics]
+# listen = "127.0.0.1:9090"
+# ready_max_lag_secs = 60
+
+# Control API (HTTP+JSON): relay status, pause/resume, immediate scan, failed packets.
+# Requests must send "Authorization: Bearer <token>", except the read-only
+# GET /packets/{channel}/{sequence} packet status (default: disabled)
+# [admin]
+# listen = "127.0.0.1:9091"
+# token = "change-me"
+
+# Transport between relay peers
+# [transport]
+# enabled = true   # false skips the peer server (no port bind) for single-node pollers
+# protocol = "quic"   # or "tcp" (TLS over TCP) where UDP is blocked
+# congestion = "cubic"   # QUIC only: "bbr" | "cubic" | "newreno"; bbr suits high-latency inter-continental links
+# connection_idle_timeout_secs = 300   # close peers that send no request for this long; 0 disables
+# max_concurrent_streams = 100   # QUIC only: requests a peer may have in flight at once per connection
+# verifier = "webpki"   # or "insecure" to accept any certificate (self-signed peers); development only,
+#                       # requires --insecure-skip-verify or AERO_INSECURE=1
+# server_name = "relay.example.com"   # SNI / hostname to verify (default: host of the peer address)
+# cert_validity_days = 365   # validity of the generated self-signed server certificate
+# cert_expiry_warn_days = 30   # warn (transport_cert_expiry_days metric) within this many days of expiry
+# cert_auto_rotate = true   # regenerate the certificate at that point; false only warns
+# peer_token = "${AERO_PEER_TOKEN}"   # shared secret of the nodes forwarding packets to each
+#                                     # other; without it the peer server accepts no forwarded packets
+
+# Log file directory; logging falls back to console-only if it is not writable
+# (e.g. read-only root filesystems) (default: "logs")
+# [logging]
+# dir = "logs"
+
+# Poller snapshots: after every processed block and on shutdown each relay saves its height,
+# in-flight packets and relayed sequences to <dir>/<relay>.json and resumes from it on
+# startup, so packets sent while the relayer was down are still relayed (default: enabled).
+# start_from = "genesis" instead scans from the earliest block the source node keeps,
+# ignoring the saved height (sequences already relayed are still skipped)
+# [state]
+# enabled = true
+# dir = "state"
+# start_from = "saved"   # or "genesis" (default: "saved" – the saved height, else the tip)
+
+# Tokio runtime sizing (default: one worker per CPU, up to 512 blocking threads).
+# In CPU-limited containers set worker_threads to the CPU limit; ZK proofs run on the
+# blocking pool, so max_blocking_threads bounds how many proofs are generated at once.
+# Small container (2 CPUs): worker_threads = 2, max_blocking_threads = 2
+# [runtime]
+# worker_threads = 2
+# max_blocking_threads = 2
+
+# Optional reusable relay templates (default: none). A relay with preset = "<name>" takes
+# every field it does not set itself from that preset; presets may be partial.
+# [presets.hub-osmosis]
+# src_chain = "cosmoshub-4"
+# src_rpc = "https://cosmos-rpc.publicnode.com:443"
+# ...any fields of a [[relays]] entry...
+#
+# [[relays]]
+# name = "hub-osmosis-backup"
+# preset = "hub-osmosis"
+# src_rpc = "https://cosmoshub.tendermintrpc.lava.build:443"
+"#;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
 pub struct RelayPair {
     pub name: String,
     pub src_chain: String,
@@ -13,24 +352,1300 @@ pub struct RelayPair {
     pub dst_rpc: String,
     pub dst_channel: String,
     pub dst_port: String,      // Matches TOML structure
+    /// Destination account address relay messages are signed with; required unless
+    /// `dst_signers` or `private_key_dst` is set
+    #[serde(default)]
+    pub signer: Option<String>,
+    /// Source-chain account address MsgAcknowledgement is signed with; required with `follow_acks`
+    #[serde(default)]
+    pub src_signer: Option<String>,
     #[serde(default)]
     pub private_key_src: Option<String>,
     #[serde(default)]
     pub private_key_dst: Option<String>,
+    /// Algorithm of `private_key_src`/`private_key_dst` (default: secp256k1)
+    #[serde(default)]
+    pub key_type: Option<KeyType>,
+    /// BIP32 path for mnemonic keys (default: m/44'/118'/0'/0/0)
+    #[serde(default)]
+    pub hd_path: Option<String>,
+    /// Source RPCs used, in order, when `src_rpc` keeps failing
+    #[serde(default)]
+    pub src_rpc_fallbacks: Vec<String>,
+    /// How the source RPC is chosen among `src_endpoints` (default: failover)
+    #[serde(default)]
+    pub src_rpc_selection: Option<RpcSelection>,
+    /// Source WebSocket endpoint (`ws(s)://host/websocket`); when set, `send_packet` txs are
+    /// followed by event subscription instead of fetching every block (only with
+    /// `watched_events = ["send_packet"]`)
+    #[serde(default)]
+    pub src_ws: Option<String>,
+    /// Destination signer addresses; broadcasts are spread across them round-robin
+    #[serde(default)]
+    pub dst_signers: Vec<String>,
+    /// Timeout for each RPC call in milliseconds (default: 10000)
+    #[serde(default)]
+    pub rpc_timeout_ms: Option<u64>,
+    /// Packets whose decoded data is larger are skipped (default: 1 MiB)
+    #[serde(default)]
+    pub max_packet_bytes: Option<usize>,
+    /// Skip packets whose data is not JSON instead of relaying it verbatim (default: false)
+    #[serde(default)]
+    pub strict_json: Option<bool>,
+    /// Skip packets already pending in the destination mempool (default: false)
+    #[serde(default)]
+    pub mempool_check: Option<bool>,
+    /// Follow relayed packets until the destination acknowledges them (default: false)
+    #[serde(default)]
+    pub follow_acks: Option<bool>,
+    /// Wait for new blocks once caught up, in ms (default: 6000)
+    #[serde(default)]
+    pub poll_interval_ms: Option<u64>,
+    /// Pause between two scanned blocks, in ms (default: 200)
+    #[serde(default)]
+    pub block_step_delay_ms: Option<u64>,
+    /// Entries kept in each packet tracker set before evicting the lowest sequences (default: 10000)
+    #[serde(default)]
+    pub tracker_capacity: Option<usize>,
+    /// Relay workers per destination (default: 1)
+    #[serde(default)]
+    pub relay_workers: Option<usize>,
+    /// Re-query cached channel metadata after this many seconds; 0 never refreshes (default: 3600)
+    #[serde(default)]
+    pub channel_refresh_secs: Option<u64>,
+    /// Startup retries while no source RPC is reachable (default: 5)
+    #[serde(default)]
+    pub connect_retries: Option<u32>,
+    /// Check at startup that src_rpc/dst_rpc serve `src_chain`/`dst_chain` (default: true)
+    #[serde(default)]
+    pub verify_chain_id: Option<bool>,
+    /// Bech32 account prefix of the destination chain (e.g. "osmo").
+    /// Address validation is skipped when unset.
+    #[serde(default)]
+    pub account_prefix: Option<String>,
+    /// Recognize IBC v2 (Eureka) packets; `src_channel` then holds the client ID (experimental)
+    #[serde(default)]
+    pub enable_channel_v2: Option<bool>,
+    /// How new blocks are searched for packets (default: blocks)
+    #[serde(default)]
+    pub scan_strategy: Option<ScanStrategy>,
+    /// Blocks below the source tip a block must be before it is scanned (default: 0)
+    #[serde(default)]
+    pub confirmations: Option<u64>,
+    /// Address of a peer aero-relay node that submits this relay's packets
+    /// (the peer needs a relay with the same name)
+    #[serde(default)]
+    pub forward_to: Option<String>,
+    /// Destination gas price used for fee estimates, e.g. "0.025uosmo"
+    #[serde(default)]
+    pub gas_price: Option<String>,
+    /// Gas assumed per MsgRecvPacket when estimating fees (default: 200000)
+    #[serde(default)]
+    pub gas_estimate: Option<u64>,
+    /// Account whose fee grant to the signer pays relay fees
+    #[serde(default)]
+    pub fee_granter: Option<String>,
+    /// Account paying relay fees (it must sign too)
+    #[serde(default)]
+    pub fee_payer: Option<String>,
+    /// Packet event kinds to process (default: send_packet, write_acknowledgement, recv_packet)
+    #[serde(default)]
+    pub watched_events: Option<Vec<String>>,
+    /// Resolve `ibc/<hash>` denoms via the source chain's DenomTrace query for logging
+    #[serde(default)]
+    pub resolve_denoms: bool,
+    /// Only relay packets whose sender matches this regex (default: all)
+    #[serde(default)]
+    pub sender_filter: Option<String>,
+    /// Only relay packets whose receiver matches this regex (default: all)
+    #[serde(default)]
+    pub receiver_filter: Option<String>,
+    /// `sender_filter`/`receiver_filter`, compiled by `Config::load`
+    #[serde(skip)]
+    pub packet_filter: PacketFilter,
+    /// Destinations packets are relayed to in addition to `dst_chain` (default: none)
+    #[serde(default)]
+    pub extra_destinations: Vec<Destination>,
+}
+
+/// A chain a relay's packets are delivered to
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Destination {
+    pub chain: String,
+    pub rpc: String,
+}
+
+impl RelayPair {
+    /// Every destination, `dst_chain`/`dst_rpc` first
+    pub fn destinations(&self) -> Vec<Destination> {
+        std::iter::once(Destination {
+            chain: self.dst_chain.clone(),
+            rpc: self.dst_rpc.clone(),
+        })
+        .chain(self.extra_destinations.iter().cloned())
+        .collect()
+    }
+
+    /// `src_rpc` followed by its fallbacks
+    pub fn src_endpoints(&self) -> Vec<String> {
+        std::iter::once(self.src_rpc.clone())
+            .chain(self.src_rpc_fallbacks.iter().cloned())
+            .collect()
+    }
+
+    /// Effective per-call RPC timeout
+    pub fn rpc_timeout(&self) -> Duration {
+        Duration::from_millis(self.rpc_timeout_ms.unwrap_or(DEFAULT_RPC_TIMEOUT_MS))
+    }
+
+    /// Compiles `sender_filter`/`receiver_filter` into `packet_filter`
+    fn compile_filters(&mut self) -> Result<()> {
+        let compile = |pattern: &Option<String>, field: &str| {
+            pattern
+                .as_deref()
+                .map(|p| Regex::new(p).context(format!("Relay '{}': invalid {}", self.name, field)))
+                .transpose()
+        };
+        self.packet_filter = PacketFilter {
+            sender: compile(&self.sender_filter, "sender_filter")?,
+            receiver: compile(&self.receiver_filter, "receiver_filter")?,
+        };
+        Ok(())
+    }
+
+    /// Packet data size limit, checked before parsing and proof generation
+    pub fn max_packet_bytes(&self) -> usize {
+        self.max_packet_bytes.unwrap_or(DEFAULT_MAX_PACKET_BYTES)
+    }
+
+    /// Whether non-JSON packet data is skipped rather than relayed raw (default: false)
+    pub fn strict_json(&self) -> bool {
+        self.strict_json.unwrap_or(false)
+    }
+
+    /// Whether the destination mempool is checked before relaying a packet (default: false)
+    pub fn mempool_check(&self) -> bool {
+        self.mempool_check.unwrap_or(false)
+    }
+
+    /// Whether relayed packets are followed until acknowledged (default: false)
+    pub fn follow_acks(&self) -> bool {
+        self.follow_acks.unwrap_or(false)
+    }
+
+    /// Wait for new blocks once the poller has caught up with the tip
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms.unwrap_or(DEFAULT_POLL_INTERVAL_MS))
+    }
+
+    /// Pause between two scanned blocks
+    pub fn block_step_delay(&self) -> Duration {
+        Duration::from_millis(self.block_step_delay_ms.unwrap_or(DEFAULT_BLOCK_STEP_DELAY_MS))
+    }
+
+    /// Capacity of the in-flight and relayed sets of the packet tracker
+    pub fn tracker_capacity(&self) -> usize {
+        self.tracker_capacity.unwrap_or(DEFAULT_TRACKER_CAPACITY)
+    }
+
+    /// Size of the relay worker pool of each destination
+    pub fn relay_workers(&self) -> usize {
+        self.relay_workers.unwrap_or(1)
+    }
+
+    /// How long cached channel metadata is trusted; `None` never refreshes
+    pub fn channel_refresh_interval(&self) -> Option<Duration> {
+        match self.channel_refresh_secs.unwrap_or(DEFAULT_CHANNEL_REFRESH_SECS) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Startup retries while no source RPC is reachable
+    pub fn connect_retries(&self) -> u32 {
+        self.connect_retries.unwrap_or(DEFAULT_CONNECT_RETRIES)
+    }
+
+    /// Fee granter and payer of relay transactions
+    pub fn fee_payment(&self) -> FeePayment {
+        FeePayment {
+            granter: self.fee_granter.clone(),
+            payer: self.fee_payer.clone(),
+        }
+    }
+
+    /// Parsed `gas_price`, if configured
+    pub fn gas_price(&self) -> Result<Option<GasPrice>> {
+        self.gas_price
+            .as_deref()
+            .map(|price| price.parse::<GasPrice>().context(format!("Relay '{}': invalid gas_price", self.name)))
+            .transpose()
+    }
+
+    /// Gas assumed per MsgRecvPacket for fee estimates
+    pub fn gas_estimate(&self) -> u64 {
+        self.gas_estimate.unwrap_or(DEFAULT_GAS_ESTIMATE)
+    }
+
+    /// Parses `private_key` (one of `private_key_src`/`private_key_dst`) with this relay's
+    /// `key_type` and `hd_path`
+    fn signing_key(&self, private_key: &Option<String>, field: &str) -> Result<Option<SigningKey>> {
+        private_key
+            .as_deref()
+            .map(|key| {
+                SigningKey::parse(
+                    key,
+                    self.key_type.unwrap_or_default(),
+                    self.hd_path.as_deref().unwrap_or(DEFAULT_HD_PATH),
+                )
+                .context(format!("Relay '{}': invalid {}", self.name, field))
+            })
+            .transpose()
+    }
+
+    /// Signer for `private_key_dst`, with its address under `account_prefix`
+    pub fn dst_key_signer(&self) -> Result<Option<KeySigner>> {
+        let Some(key) = self.signing_key(&self.private_key_dst, "private_key_dst")? else {
+            return Ok(None);
+        };
+        let prefix = self
+            .account_prefix
+            .as_deref()
+            .ok_or_else(|| anyhow!("Relay '{}': private_key_dst requires account_prefix", self.name))?;
+        Ok(Some(KeySigner::new(key, prefix)?))
+    }
+
+    /// Destination address relay messages are signed with when no signer pool is used:
+    /// `signer`, else the first `dst_signers` entry, else the address of `private_key_dst`
+    pub fn signer_address(&self) -> Result<String> {
+        if let Some(signer) = self.signer.as_ref().or(self.dst_signers.first()) {
+            return Ok(signer.clone());
+        }
+        match self.dst_key_signer()? {
+            Some(signer) => Ok(signer.address().to_string()),
+            None => bail!("Relay '{}': no signer configured", self.name),
+        }
+    }
+
+    /// Packet event kinds processed by the poller
+    pub fn watched_events(&self) -> Vec<String> {
+        self.watched_events
+            .clone()
+            .unwrap_or_else(|| DEFAULT_WATCHED_EVENTS.iter().map(|k| k.to_string()).collect())
+    }
+
+    /// Whether RPC chain IDs are checked at startup (default: true)
+    pub fn chain_id_verification(&self) -> bool {
+        self.verify_chain_id.unwrap_or(true)
+    }
+
+    /// Depth below the source tip at which blocks count as final (default: 0, the tip itself)
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations.unwrap_or(0)
+    }
+
+    /// Whether IBC v2 packet detection is enabled (default: false)
+    pub fn channel_v2_enabled(&self) -> bool {
+        self.enable_channel_v2.unwrap_or(false)
+    }
+
+    /// Fills every setting left unset on this relay from `[defaults]`
+    fn inherit(&mut self, defaults: &RelayDefaults) {
+        self.rpc_timeout_ms = self.rpc_timeout_ms.or(defaults.rpc_timeout_ms);
+        self.connect_retries = self.connect_retries.or(defaults.connect_retries);
+        self.max_packet_bytes = self.max_packet_bytes.or(defaults.max_packet_bytes);
+        self.strict_json = self.strict_json.or(defaults.strict_json);
+        self.mempool_check = self.mempool_check.or(defaults.mempool_check);
+        self.follow_acks = self.follow_acks.or(defaults.follow_acks);
+        self.poll_interval_ms = self.poll_interval_ms.or(defaults.poll_interval_ms);
+        self.block_step_delay_ms = self.block_step_delay_ms.or(defaults.block_step_delay_ms);
+        self.tracker_capacity = self.tracker_capacity.or(defaults.tracker_capacity);
+        self.relay_workers = self.relay_workers.or(defaults.relay_workers);
+        self.enable_channel_v2 = self.enable_channel_v2.or(defaults.enable_channel_v2);
+        self.verify_chain_id = self.verify_chain_id.or(defaults.verify_chain_id);
+        self.scan_strategy = self.scan_strategy.or(defaults.scan_strategy);
+        self.src_rpc_selection = self.src_rpc_selection.or(defaults.src_rpc_selection);
+        self.confirmations = self.confirmations.or(defaults.confirmations);
+        self.channel_refresh_secs = self.channel_refresh_secs.or(defaults.channel_refresh_secs);
+        self.gas_estimate = self.gas_estimate.or(defaults.gas_estimate);
+        if self.watched_events.is_none() {
+            self.watched_events = defaults.watched_events.clone();
+        }
+        if self.gas_price.is_none() {
+            self.gas_price = defaults.gas_price.clone();
+        }
+        if self.fee_granter.is_none() {
+            self.fee_granter = defaults.fee_granter.clone();
+        }
+        if self.fee_payer.is_none() {
+            self.fee_payer = defaults.fee_payer.clone();
+        }
+        if self.sender_filter.is_none() {
+            self.sender_filter = defaults.sender_filter.clone();
+        }
+        if self.receiver_filter.is_none() {
+            self.receiver_filter = defaults.receiver_filter.clone();
+        }
+    }
+}
+
+/// Syntax of a config file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// `.yaml`/`.yml` and `.json` files; anything else is read as TOML
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Toml,
+        }
+    }
+}
+
+/// Compiled sender/receiver address patterns; an unset pattern matches everything
+#[derive(Clone, Debug, Default)]
+pub struct PacketFilter {
+    pub sender: Option<Regex>,
+    pub receiver: Option<Regex>,
+}
+
+// Filters are equal when they were compiled from the same patterns
+impl PartialEq for PacketFilter {
+    fn eq(&self, other: &Self) -> bool {
+        let pattern = |re: &Option<Regex>| re.as_ref().map(|re| re.as_str().to_string());
+        pattern(&self.sender) == pattern(&other.sender) && pattern(&self.receiver) == pattern(&other.receiver)
+    }
+}
+
+impl PacketFilter {
+    pub fn matches(&self, sender: &str, receiver: &str) -> bool {
+        self.sender.iter().all(|re| re.is_match(sender))
+            && self.receiver.iter().all(|re| re.is_match(receiver))
+    }
+}
+
+/// `[defaults]` section – inherited by every relay that does not set the value itself
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RelayDefaults {
+    pub rpc_timeout_ms: Option<u64>,
+    pub connect_retries: Option<u32>,
+    pub max_packet_bytes: Option<usize>,
+    pub strict_json: Option<bool>,
+    pub mempool_check: Option<bool>,
+    pub follow_acks: Option<bool>,
+    pub poll_interval_ms: Option<u64>,
+    pub block_step_delay_ms: Option<u64>,
+    pub tracker_capacity: Option<usize>,
+    pub relay_workers: Option<usize>,
+    pub enable_channel_v2: Option<bool>,
+    pub verify_chain_id: Option<bool>,
+    pub scan_strategy: Option<ScanStrategy>,
+    pub src_rpc_selection: Option<RpcSelection>,
+    pub confirmations: Option<u64>,
+    pub watched_events: Option<Vec<String>>,
+    pub channel_refresh_secs: Option<u64>,
+    pub gas_price: Option<String>,
+    pub gas_estimate: Option<u64>,
+    pub fee_granter: Option<String>,
+    pub fee_payer: Option<String>,
+    pub sender_filter: Option<String>,
+    pub receiver_filter: Option<String>,
+}
+
+/// `[alerts]` section – paging on stuck relays
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AlertsConfig {
+    /// Webhook receiving alerts as JSON POSTs (alerting is off when unset)
+    pub webhook_url: Option<String>,
+    /// Alert types to send
+    pub events: Vec<AlertKind>,
+    /// Consecutive relay failures before a `relay_failures` alert
+    pub failure_threshold: u32,
+    /// Seconds without a new source block before a `chain_halted` alert
+    pub stall_after_secs: u64,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            events: vec![
+                AlertKind::RelayFailures,
+                AlertKind::ChainHalted,
+                AlertKind::DeadLetterQueue,
+                AlertKind::UnrelayedPacket,
+            ],
+            failure_threshold: 5,
+            stall_after_secs: 120,
+        }
+    }
+}
+
+/// What to do when proof generation exceeds `proof_timeout_ms`
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofTimeoutPolicy {
+    /// Relay the packet without a proof
+    #[default]
+    Proceed,
+    /// Do not relay the packet
+    Skip,
+}
+
+/// Proof system used for packet commitments
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofBackendKind {
+    /// halo2 circuit, KZG or IPA (requires the encryption-proof feature)
+    Halo2,
+    /// blake3 hash of the packet data
+    Blake3,
+    /// No proofs
+    None,
+}
+
+/// Polynomial commitment scheme of the halo2 backend
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitmentScheme {
+    /// KZG: short proofs, needs a trusted setup (params persisted to `zk_params*.bin`)
+    #[default]
+    Kzg,
+    /// IPA: transparent (no trusted setup), larger proofs and slower verification
+    Ipa,
+}
+
+/// Default circuit size for the halo2 backend (2^k rows)
+pub const DEFAULT_ZK_K: u32 = 11;
+
+/// Largest accepted `[zk] k`; params grow as 2^k
+pub const MAX_ZK_K: u32 = 24;
+
+/// `[zk]` section – proof generation
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ZkConfig {
+    /// Proof backend (default: halo2 with the encryption-proof feature, otherwise none)
+    pub backend: Option<ProofBackendKind>,
+    /// halo2 commitment scheme (default: kzg)
+    pub scheme: CommitmentScheme,
+    /// halo2 circuit size: 2^k rows (checked at startup)
+    pub k: u32,
+    /// Maximum time for one proof in milliseconds
+    pub proof_timeout_ms: u64,
+    pub on_proof_timeout: ProofTimeoutPolicy,
+}
+
+impl Default for ZkConfig {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            scheme: CommitmentScheme::Kzg,
+            k: DEFAULT_ZK_K,
+            proof_timeout_ms: 60_000,
+            on_proof_timeout: ProofTimeoutPolicy::Proceed,
+        }
+    }
+}
+
+impl ZkConfig {
+    pub fn proof_timeout(&self) -> Duration {
+        Duration::from_millis(self.proof_timeout_ms)
+    }
+}
+
+/// `[metrics]` section – Prometheus endpoint and readiness probe
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Address serving `/metrics` and `/ready` (disabled when unset)
+    pub listen: Option<String>,
+    /// Largest block lag, in seconds, at which `/ready` still reports ready
+    pub ready_max_lag_secs: u64,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            ready_max_lag_secs: 60,
+        }
+    }
+}
+
+impl MetricsConfig {
+    pub fn ready_max_lag(&self) -> Duration {
+        Duration::from_secs(self.ready_max_lag_secs)
+    }
+}
+
+/// QUIC congestion controller
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CongestionControl {
+    /// Bandwidth/RTT based; often better on long, high-latency links
+    Bbr,
+    /// quinn's default
+    #[default]
+    Cubic,
+    NewReno,
+}
+
+/// How the poller finds packet events in new blocks
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanStrategy {
+    /// `block_results` for every block
+    #[default]
+    Blocks,
+    /// `tx_search` for the channel's packet events over a height range (needs tx indexing)
+    TxSearch,
+}
+
+/// How a relay picks among its source RPC endpoints
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcSelection {
+    /// Stay on one endpoint, switch to the next after repeated failures
+    #[default]
+    Failover,
+    /// Route each call to the fastest healthy endpoint (`RpcPool`)
+    Latency,
+}
+
+/// Wire protocol used between relay peers
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportProtocol {
+    #[default]
+    Quic,
+    /// TLS over TCP, for networks that block UDP
+    Tcp,
+}
+
+/// How the client verifies the peer's certificate
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CertVerifierMode {
+    /// Accept any certificate (self-signed peers); offers no protection against MITM.
+    /// Only honored with the explicit opt-in (`TransportConfig::allow_insecure`).
+    Insecure,
+    /// Standard chain and hostname verification against the platform's root certificates
+    #[default]
+    WebPki,
+}
+
+/// `[transport]` section – peer transport (QUIC, or TCP+TLS)
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TransportConfig {
+    /// Run the peer server (disable for single-node pollers)
+    pub enabled: bool,
+    pub protocol: TransportProtocol,
+    /// QUIC only
+    pub congestion: CongestionControl,
+    pub verifier: CertVerifierMode,
+    /// Server name (SNI) checked against the peer certificate (default: host part of the peer address)
+    pub server_name: Option<String>,
+    /// Server closes connections that send no new request for this long (0 disables)
+    pub connection_idle_timeout_secs: u64,
+    /// QUIC only: bidirectional streams (requests) a peer may have open at once on a connection
+    pub max_concurrent_streams: u32,
+    /// Validity of the generated server certificate
+    pub cert_validity_days: u32,
+    /// Warn (or rotate) once the certificate expires within this many days
+    pub cert_expiry_warn_days: u32,
+    /// Regenerate the certificate when it nears expiry instead of only warning
+    pub cert_auto_rotate: bool,
+    /// Shared secret forwarded packets must carry; this node sends it to `forward_to` peers
+    /// and its peer server rejects every forwarded packet without it (default: unset)
+    pub peer_token: Option<String>,
+    /// Opt-in for `verifier = "insecure"`, from `--insecure-skip-verify`; never read from the file
+    #[serde(skip)]
+    pub allow_insecure: bool,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            protocol: TransportProtocol::default(),
+            congestion: CongestionControl::default(),
+            verifier: CertVerifierMode::default(),
+            server_name: None,
+            connection_idle_timeout_secs: 300,
+            max_concurrent_streams: 100,
+            cert_validity_days: 365,
+            cert_expiry_warn_days: 30,
+            cert_auto_rotate: true,
+            peer_token: None,
+            allow_insecure: false,
+        }
+    }
+}
+
+impl TransportConfig {
+    pub fn connection_idle_timeout(&self) -> Option<Duration> {
+        (self.connection_idle_timeout_secs > 0).then_some(Duration::from_secs(self.connection_idle_timeout_secs))
+    }
+}
+
+/// `[logging]` section
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Directory for the daily rotating log file; console-only if it is not writable
+    pub dir: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self { dir: "logs".to_string() }
+    }
+}
+
+/// `[state]` section – poller snapshots (height, in-flight packets, relayed sequences)
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct StateConfig {
+    /// Save snapshots after every block and on shutdown, and resume from them on startup
+    pub enabled: bool,
+    /// One `<relay>.json` per relay
+    pub dir: String,
+    /// Height pollers start scanning from
+    pub start_from: StartFrom,
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self { enabled: true, dir: "state".to_string(), start_from: StartFrom::default() }
+    }
+}
+
+/// Where a poller starts scanning (`[state] start_from`)
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StartFrom {
+    /// The saved snapshot's height; the tip without a snapshot
+    #[default]
+    Saved,
+    /// The earliest block the source node keeps, regardless of any snapshot
+    Genesis,
+}
+
+impl StateConfig {
+    /// Snapshot file of `relay`, if persistence is enabled
+    pub fn path_for(&self, relay: &str) -> Option<PathBuf> {
+        self.enabled.then(|| Path::new(&self.dir).join(format!("{}.json", relay)))
+    }
+}
+
+/// `[admin]` section – control API
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// Address of the control API (disabled when unset)
+    pub listen: Option<String>,
+    /// Bearer token every request must present; required with `listen`
+    pub token: Option<String>,
+}
+
+/// Subject relay events are published to when `subject` is unset
+pub const DEFAULT_EVENTS_SUBJECT: &str = "aero-relay.packets";
+
+/// `[events]` section – relay outcomes published to a message broker
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EventsConfig {
+    /// NATS server (publishing is off when unset)
+    pub nats_url: Option<String>,
+    pub subject: String,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            nats_url: None,
+            subject: DEFAULT_EVENTS_SUBJECT.to_string(),
+        }
+    }
+}
+
+/// Attempts per network step when `[retry] max_attempts` is unset
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+
+/// Network steps of a relay with their own retry policy (`[retry.<step>]`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryStep {
+    /// Packet commitment proof query (read-only)
+    ProofQuery,
+    /// Channel and client state queries (read-only)
+    ClientState,
+    /// Tx simulation (executes nothing on chain)
+    Simulate,
+}
+
+impl RetryStep {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RetryStep::ProofQuery => "proof_query",
+            RetryStep::ClientState => "client_state",
+            RetryStep::Simulate => "simulate",
+        }
+    }
+}
+
+/// Effective retry policy of one step
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first; 1 disables retries
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before attempt `attempt + 1`: doubles from `initial_backoff` up to `max_backoff`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// `[retry.<step>]` – values that differ from the `[retry]` defaults
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct RetryOverride {
+    pub max_attempts: Option<u32>,
+    pub initial_backoff_ms: Option<u64>,
+    pub max_backoff_ms: Option<u64>,
+}
+
+/// `[retry]` section – retry policies of the relay's network steps
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub proof_query: RetryOverride,
+    pub client_state: RetryOverride,
+    pub simulate: RetryOverride,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_RETRY_ATTEMPTS,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+            proof_query: RetryOverride::default(),
+            client_state: RetryOverride::default(),
+            simulate: RetryOverride::default(),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Policy of `step`: its overrides on top of the `[retry]` values
+    pub fn policy(&self, step: RetryStep) -> RetryPolicy {
+        let overrides = match step {
+            RetryStep::ProofQuery => self.proof_query,
+            RetryStep::ClientState => self.client_state,
+            RetryStep::Simulate => self.simulate,
+        };
+        RetryPolicy {
+            max_attempts: overrides.max_attempts.unwrap_or(self.max_attempts),
+            initial_backoff: Duration::from_millis(overrides.initial_backoff_ms.unwrap_or(self.initial_backoff_ms)),
+            max_backoff: Duration::from_millis(overrides.max_backoff_ms.unwrap_or(self.max_backoff_ms)),
+        }
+    }
+}
+
+/// `[runtime]` section – tokio runtime sizing
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    /// Async worker threads (default: number of CPUs)
+    pub worker_threads: Option<usize>,
+    /// Blocking pool size, shared by ZK proof generation (default: tokio's 512)
+    pub max_blocking_threads: Option<usize>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Config {
+    /// Peer server listen address (default: 0.0.0.0:4433 with `forward_to` peers, else 127.0.0.1:4433)
+    #[serde(default)]
+    pub quic_listen: Option<String>,
     pub relays: Vec<RelayPair>,
     #[serde(default)]
-    pub presets: HashMap<String, RelayPair>,
+    pub defaults: RelayDefaults,
+    /// Relay templates by name, as written (`preset = "<name>"` in a relay); already merged
+    /// into `relays` by the time the config is loaded
+    #[serde(default)]
+    pub presets: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub events: EventsConfig,
+    #[serde(default)]
+    pub zk: ZkConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
+    #[serde(default)]
+    pub transport: TransportConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub state: StateConfig,
 }
 
 impl Config {
-    /// Loads configuration from a TOML file.
+    /// Loads configuration from a file; the format follows the extension (`ConfigFormat::from_path`).
+    /// Relays are returned fully resolved, with `[defaults]` already applied.
+    /// `${VAR}` in string values is replaced by the environment variable (an unset one is an
+    /// error); `$$` stands for a literal `$`.
     pub fn load(path: &str) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&content)?;
-        Ok(config)
+        Self::load_from_str(&content, ConfigFormat::from_path(Path::new(path)))
+    }
+
+    /// Like `load`, for config content that does not come from a file (e.g. embedded)
+    pub fn load_from_str(content: &str, format: ConfigFormat) -> Result<Self> {
+        let value = match format {
+            ConfigFormat::Toml => serde_json::to_value(toml::from_str::<toml::Table>(content)?)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(content)?,
+            ConfigFormat::Json => serde_json::from_str(content)?,
+        };
+        Self::from_value(value)?.resolve()
+    }
+
+    /// Watches the config file at `path` and sends every new version that loads successfully.
+    /// A version that fails to load (e.g. a half-written or broken file) is logged and skipped,
+    /// so the previous config stays in effect. Watching stops when the receiver is dropped.
+    pub fn watch(path: &str) -> Result<mpsc::Receiver<Config>> {
+        let file = PathBuf::from(path);
+        let file_name = file
+            .file_name()
+            .ok_or_else(|| anyhow!("Config path {} is not a file", path))?
+            .to_os_string();
+        // Watch the directory: editors often replace the file instead of writing to it
+        let dir = match file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let (events_tx, events) = std::sync::mpsc::channel::<notify::Result<notify::Event>>();
+        let mut watcher = notify::recommended_watcher(events_tx).context("Failed to create config file watcher")?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .context(format!("Failed to watch {}", dir.display()))?;
+
+        let (configs, rx) = mpsc::channel(1);
+        let path = path.to_string();
+        std::thread::spawn(move || {
+            let _watcher = watcher;
+            while let Ok(event) = events.recv() {
+                let changed = event.is_ok_and(|event| {
+                    !event.kind.is_access()
+                        && event.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str()))
+                });
+                if !changed {
+                    continue;
+                }
+                while events.recv_timeout(RELOAD_DEBOUNCE).is_ok() {}
+
+                match Config::load(&path) {
+                    Ok(config) => {
+                        info!("Config {} changed, reloading", path);
+                        if configs.blocking_send(config).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => error!("Config {} changed but failed to load, keeping the previous config: {:#}", path, e),
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    /// Loads and merges every `*.toml` file in `dir`, in file name order – e.g. one file per
+    /// network. `relays` lists are concatenated and `presets` unioned; a relay or preset name
+    /// defined twice is an error, as is any other section appearing in more than one file.
+    /// Environment variables are expanded as in `load`.
+    pub fn load_dir(dir: &str) -> Result<Self> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .context(format!("Failed to read config directory {}", dir))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        files.sort();
+        if files.is_empty() {
+            bail!("No *.toml files in config directory {}", dir);
+        }
+
+        let mut merged = toml::Table::new();
+        // Defining file of each relay, preset and section, for error messages
+        let mut origins: HashMap<String, PathBuf> = HashMap::new();
+        for file in &files {
+            let content = std::fs::read_to_string(file).context(format!("Failed to read {}", file.display()))?;
+            let table: toml::Table = toml::from_str(&content).context(format!("Invalid TOML in {}", file.display()))?;
+
+            for (key, value) in table {
+                match (key.as_str(), value) {
+                    ("relays", toml::Value::Array(relays)) => {
+                        for relay in &relays {
+                            let name = relay.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+                            if let Some(first) = origins.insert(format!("relay {}", name), file.clone()) {
+                                bail!("Relay '{}' is defined in both {} and {}", name, first.display(), file.display());
+                            }
+                        }
+                        let entry = merged.entry("relays").or_insert_with(|| toml::Value::Array(Vec::new()));
+                        if let toml::Value::Array(all) = entry {
+                            all.extend(relays);
+                        }
+                    }
+                    ("presets", toml::Value::Table(presets)) => {
+                        let entry = merged.entry("presets").or_insert_with(|| toml::Value::Table(toml::Table::new()));
+                        for (name, preset) in presets {
+                            if let Some(first) = origins.insert(format!("preset {}", name), file.clone()) {
+                                bail!("Preset '{}' is defined in both {} and {}", name, first.display(), file.display());
+                            }
+                            if let toml::Value::Table(all) = entry {
+                                all.insert(name, preset);
+                            }
+                        }
+                    }
+                    (_, value) => {
+                        if let Some(first) = origins.insert(key.clone(), file.clone()) {
+                            bail!("[{}] is defined in both {} and {}", key, first.display(), file.display());
+                        }
+                        merged.insert(key, value);
+                    }
+                }
+            }
+        }
+
+        let config = Self::from_value(serde_json::to_value(merged)?)
+            .context(format!("Invalid merged config from {}", dir))?;
+        config.resolve()
+    }
+
+    /// Expands environment variables in a parsed config document, merges presets into the
+    /// relays referencing them and deserializes it; every format goes through the same JSON
+    /// value, so the structure is identical
+    fn from_value(mut value: serde_json::Value) -> Result<Self> {
+        expand_env_vars(&mut value, "")?;
+        resolve_presets(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Address the peer server listens on; only loopback by default unless relays forward to peers
+    pub fn quic_listen(&self) -> &str {
+        match &self.quic_listen {
+            Some(listen) => listen,
+            None if self.relays.iter().any(|relay| relay.forward_to.is_some()) => DEFAULT_QUIC_LISTEN,
+            None => DEFAULT_QUIC_LISTEN_LOCAL,
+        }
     }
-}
\ No newline at end of file
+
+    /// Applies `[defaults]`, compiles filters and validates a freshly parsed config
+    fn resolve(mut self) -> Result<Self> {
+        self.apply_defaults();
+        for relay in &mut self.relays {
+            relay.compile_filters()?;
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Merges `[defaults]` into every relay; values set on a relay take precedence
+    pub fn apply_defaults(&mut self) {
+        for relay in &mut self.relays {
+            relay.inherit(&self.defaults);
+        }
+    }
+
+    /// Checks relay settings that serde cannot express. Relay errors name the relay by its
+    /// position in `relays` and its name.
+    pub fn validate(&self) -> Result<()> {
+        for (index, relay) in self.relays.iter().enumerate() {
+            let at = format!("Relay #{} '{}'", index, relay.name);
+
+            for (field, url) in [("src_rpc", &relay.src_rpc), ("dst_rpc", &relay.dst_rpc)]
+                .into_iter()
+                .chain(relay.src_rpc_fallbacks.iter().map(|url| ("src_rpc_fallbacks entry", url)))
+            {
+                check_rpc_url(url).context(format!("{}: invalid {}", at, field))?;
+            }
+            if let Some(url) = &relay.src_ws {
+                if !url.starts_with("ws://") && !url.starts_with("wss://") {
+                    bail!("{}: src_ws '{}' must be a ws:// or wss:// URL", at, url);
+                }
+                check_rpc_url(url).context(format!("{}: invalid src_ws", at))?;
+            }
+            for destination in &relay.extra_destinations {
+                if !destination.rpc.trim().is_empty() {
+                    check_rpc_url(&destination.rpc)
+                        .context(format!("{}: invalid rpc of destination {}", at, destination.chain))?;
+                }
+            }
+            for (field, channel) in [("src_channel", &relay.src_channel), ("dst_channel", &relay.dst_channel)] {
+                if !is_channel_id(channel, relay.channel_v2_enabled()) {
+                    bail!("{}: {} '{}' is not a channel ID like channel-0", at, field, channel);
+                }
+            }
+            for (field, port) in [("src_port", &relay.src_port), ("dst_port", &relay.dst_port)] {
+                if port.trim().is_empty() {
+                    bail!("{}: {} must not be empty", at, field);
+                }
+            }
+
+            relay.gas_price().context(at.clone())?;
+
+            if let Some(prefix) = &relay.account_prefix {
+                if prefix.is_empty()
+                    || !prefix.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                {
+                    bail!("{}: account_prefix '{}' must be lowercase alphanumeric", at, prefix);
+                }
+
+                for address in &relay.dst_signers {
+                    validate_address(address, prefix).context(format!("{}: invalid dst_signers entry", at))?;
+                }
+                for (field, address) in [
+                    ("signer", &relay.signer),
+                    ("fee_granter", &relay.fee_granter),
+                    ("fee_payer", &relay.fee_payer),
+                ] {
+                    if let Some(address) = address {
+                        validate_address(address, prefix).context(format!("{}: invalid {}", at, field))?;
+                    }
+                }
+            }
+
+            relay.signing_key(&relay.private_key_src, "private_key_src").context(at.clone())?;
+            relay.dst_key_signer().context(at.clone())?;
+            if relay.signer.is_none() && relay.dst_signers.is_empty() && relay.private_key_dst.is_none() {
+                bail!(
+                    "{}: no signer configured; set signer to the destination account address \
+                     (e.g. signer = \"${{RELAYER_SIGNER}}\"), or dst_signers or private_key_dst",
+                    at
+                );
+            }
+            if let (Some(signer), None) = (&relay.signer, &relay.account_prefix) {
+                bech32::decode(signer).map_err(|e| anyhow!("{}: invalid signer {}: {}", at, signer, e))?;
+            }
+            match &relay.src_signer {
+                Some(src_signer) => {
+                    bech32::decode(src_signer)
+                        .map_err(|e| anyhow!("{}: invalid src_signer {}: {}", at, src_signer, e))?;
+                }
+                None if relay.follow_acks() => {
+                    bail!("{}: follow_acks needs src_signer, the source-chain account acknowledgements are signed with", at)
+                }
+                None => {}
+            }
+
+            if relay.poll_interval_ms == Some(0) {
+                bail!("{}: poll_interval_ms must be at least 1", at);
+            }
+            if relay.tracker_capacity() == 0 {
+                bail!("{}: tracker_capacity must be at least 1", at);
+            }
+            if !(1..=MAX_RELAY_WORKERS).contains(&relay.relay_workers()) {
+                bail!("{}: relay_workers must be between 1 and {}", at, MAX_RELAY_WORKERS);
+            }
+            if relay.confirmations() > MAX_CONFIRMATIONS {
+                bail!("{}: confirmations must be at most {}", at, MAX_CONFIRMATIONS);
+            }
+            let mut chains = HashSet::new();
+            for destination in relay.destinations() {
+                if destination.rpc.trim().is_empty() {
+                    bail!("{}: destination {} has no rpc", at, destination.chain);
+                }
+                if !chains.insert(destination.chain.clone()) {
+                    bail!("{}: destination {} is listed more than once", at, destination.chain);
+                }
+            }
+        }
+
+        for step in [RetryStep::ProofQuery, RetryStep::ClientState, RetryStep::Simulate] {
+            if self.retry.policy(step).max_attempts == 0 {
+                bail!("[retry] max_attempts of {} must be at least 1", step.as_str());
+            }
+        }
+
+        if self.transport.enabled {
+            self.quic_listen()
+                .parse::<SocketAddr>()
+                .map_err(|e| anyhow!("quic_listen '{}' is not an address like 0.0.0.0:4433: {}", self.quic_listen(), e))?;
+        }
+        if self.transport.max_concurrent_streams == 0 {
+            bail!("[transport] max_concurrent_streams must be at least 1");
+        }
+        if self.transport.cert_expiry_warn_days >= self.transport.cert_validity_days {
+            bail!("[transport] cert_expiry_warn_days must be below cert_validity_days");
+        }
+
+        proof::from_config(&self.zk)?;
+        if !(1..=MAX_ZK_K).contains(&self.zk.k) {
+            bail!("[zk] k must be between 1 and {}", MAX_ZK_K);
+        }
+
+        if self.admin.listen.is_some() && self.admin.token.as_deref().unwrap_or_default().is_empty() {
+            bail!("[admin] token is required when listen is set");
+        }
+
+        if self.runtime.worker_threads == Some(0) {
+            bail!("[runtime] worker_threads must be at least 1");
+        }
+        if self.runtime.max_blocking_threads == Some(0) {
+            bail!("[runtime] max_blocking_threads must be at least 1");
+        }
+        Ok(())
+    }
+
+    /// Keeps only the named relays (all of them if `names` is empty).
+    /// Fails if a name does not match any configured relay.
+    pub fn retain_relays(&mut self, names: &[String]) -> Result<()> {
+        if names.is_empty() {
+            return Ok(());
+        }
+
+        for name in names {
+            if !self.relays.iter().any(|r| &r.name == name) {
+                bail!("Relay '{}' not found in config", name);
+            }
+        }
+
+        self.relays.retain(|r| names.contains(&r.name));
+        Ok(())
+    }
+
+    /// Writes the example configuration to `path`.
+    /// Refuses to overwrite an existing file unless `force` is set.
+    pub fn write_sample(path: &str, force: bool) -> Result<()> {
+        if Path::new(path).exists() && !force {
+            bail!("{} already exists (use --force to overwrite)", path);
+        }
+        std::fs::write(path, SAMPLE_CONFIG)
+            .context(format!("Failed to write sample config to {}", path))?;
+        Ok(())
+    }
+}
+
+/// Checks that `url` is an RPC endpoint `RpcClient::new` accepts (http(s), ws(s) or unix://)
+fn check_rpc_url(url: &str) -> Result<()> {
+    if let Some(path) = url.strip_prefix("unix://") {
+        if path.is_empty() {
+            bail!("'{}' has no socket path", url);
+        }
+        return Ok(());
+    }
+    url.parse::<tendermint_rpc::Url>()
+        .map(|_| ())
+        .map_err(|e| anyhow!("'{}' is not a valid RPC URL: {}", url, e))
+}
+
+/// `channel-N`; with IBC v2 any identifier (a client ID) is accepted
+fn is_channel_id(id: &str, channel_v2: bool) -> bool {
+    if channel_v2 {
+        return !id.is_empty() && !id.contains('/') && !id.contains(char::is_whitespace);
+    }
+    id.strip_prefix("channel-")
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Expands environment variables in every string of `value`; `path` is its dotted key, for errors
+fn expand_env_vars(value: &mut serde_json::Value, path: &str) -> Result<()> {
+    match value {
+        serde_json::Value::String(text) => {
+            *text = expand_env(text).context(format!("Config value {}", path))?;
+        }
+        serde_json::Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                expand_env_vars(item, &format!("{}[{}]", path, index))?;
+            }
+        }
+        serde_json::Value::Object(table) => {
+            for (key, item) in table.iter_mut() {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                expand_env_vars(item, &path)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Replaces `${VAR}` with the variable's value and `$$` with `$`; any other `$` is kept
+fn expand_env(text: &str) -> Result<String> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('$') {
+        expanded.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+        } else if let Some(after) = after.strip_prefix('{') {
+            let end = after.find('}').ok_or_else(|| anyhow!("unterminated ${{ (write $$ for a literal $)"))?;
+            let name = &after[..end];
+            if name.is_empty() {
+                bail!("empty ${{}}");
+            }
+            let value = std::env::var(name).map_err(|_| anyhow!("environment variable {} is not set", name))?;
+            expanded.push_str(&value);
+            rest = &after[end + 1..];
+        } else {
+            expanded.push('$');
+            rest = after;
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Replaces `preset = "<name>"` in each relay with the fields of `presets.<name>` the relay
+/// does not set itself
+fn resolve_presets(config: &mut serde_json::Value) -> Result<()> {
+    let presets = config.get("presets").cloned().unwrap_or_default();
+    let Some(relays) = config.get_mut("relays").and_then(|relays| relays.as_array_mut()) else {
+        return Ok(());
+    };
+
+    for (index, relay) in relays.iter_mut().enumerate() {
+        let Some(fields) = relay.as_object_mut() else { continue };
+        let Some(preset) = fields.remove("preset") else { continue };
+        let name = fields.get("name").and_then(|name| name.as_str()).unwrap_or_default().to_string();
+        let at = format!("Relay #{} '{}'", index, name);
+
+        let preset = preset.as_str().ok_or_else(|| anyhow!("{}: preset must be a preset name", at))?;
+        let template = presets
+            .get(preset)
+            .and_then(|template| template.as_object())
+            .ok_or_else(|| anyhow!("{}: preset '{}' is not defined in [presets]", at, preset))?;
+        for (key, value) in template {
+            if key != "preset" && !fields.contains_key(key) {
+                fields.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tendermint_rpc::Client;
+use tracing::debug;
+
+use ibc_proto::ibc::applications::transfer::v1::{DenomTrace, QueryDenomTraceRequest, QueryDenomTraceResponse};
+
+use crate::rpc::{with_timeout, RpcClient};
+
+/// Resolves `ibc/<hash>` denoms to their trace (path + base denom) for display.
+/// Lookups are cached; results never influence what is relayed.
+pub struct DenomResolver {
+    client: RpcClient,
+    rpc_timeout: Duration,
+    cache: Mutex<HashMap<String, DenomTrace>>,
+}
+
+impl DenomResolver {
+    pub fn new(client: RpcClient, rpc_timeout: Duration) -> Self {
+        Self {
+            client,
+            rpc_timeout,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Queries the transfer module's DenomTrace for `hash` (with or without the `ibc/` prefix)
+    pub async fn resolve_denom_trace(&self, hash: &str) -> Result<DenomTrace> {
+        let hash = hash.strip_prefix("ibc/").unwrap_or(hash);
+        if let Some(trace) = self.cache.lock().unwrap().get(hash) {
+            return Ok(trace.clone());
+        }
+
+        let request = QueryDenomTraceRequest { hash: hash.to_string() };
+        let res = with_timeout(
+            self.rpc_timeout,
+            "abci_query",
+            self.client.abci_query(
+                Some("/ibc.applications.transfer.v1.Query/DenomTrace".to_string()),
+                prost::Message::encode_to_vec(&request),
+                None,
+                false,
+            ),
+        )
+        .await?;
+
+        if res.code.is_err() {
+            return Err(anyhow!("DenomTrace query failed: {}", res.log));
+        }
+
+        let response = <QueryDenomTraceResponse as prost::Message>::decode(res.value.as_slice())?;
+        let trace = response
+            .denom_trace
+            .ok_or_else(|| anyhow!("No denom trace for ibc/{}", hash))?;
+
+        self.cache.lock().unwrap().insert(hash.to_string(), trace.clone());
+        Ok(trace)
+    }
+
+    /// Readable form of `denom`: `base (path)` for resolvable `ibc/` denoms, otherwise unchanged
+    pub async fn display(&self, denom: &str) -> String {
+        if !denom.starts_with("ibc/") {
+            return denom.to_string();
+        }
+
+        match self.resolve_denom_trace(denom).await {
+            Ok(trace) if trace.path.is_empty() => trace.base_denom,
+            Ok(trace) => format!("{} ({})", trace.base_denom, trace.path),
+            Err(e) => {
+                debug!("Could not resolve {}: {}", denom, e);
+                denom.to_string()
+            }
+        }
+    }
+}
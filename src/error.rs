@@ -0,0 +1,83 @@
+use std::fmt;
+
+/// Non-zero result code returned by the chain for a broadcast transaction
+/// (CheckTx or DeliverTx), as opposed to a failure to reach the node at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxError {
+    pub code: u32,
+    pub codespace: String,
+    pub raw_log: String,
+}
+
+impl TxError {
+    /// Returns true if resubmitting may succeed (SDK: mempool full, account sequence mismatch).
+    /// Anything else (invalid proof, bad signer, ...) should be dead-lettered.
+    pub fn is_retryable(&self) -> bool {
+        self.codespace == "sdk" && matches!(self.code, 20 | 32)
+    }
+}
+
+impl fmt::Display for TxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tx failed with code {} (codespace '{}'): {}", self.code, self.codespace, self.raw_log)
+    }
+}
+
+impl std::error::Error for TxError {}
+
+/// Error taxonomy for relay attempts – lets the retry logic decide between retrying and dead-lettering.
+#[derive(Debug)]
+pub enum RelayError {
+    /// The transaction never reached the chain (connection, RPC or encoding failure)
+    Submission(String),
+    /// The chain accepted the request but rejected the transaction
+    Tx(TxError),
+    /// An RPC call did not answer within the configured timeout
+    Timeout(String),
+}
+
+impl RelayError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            RelayError::Submission(_) | RelayError::Timeout(_) => true,
+            RelayError::Tx(e) => e.is_retryable(),
+        }
+    }
+}
+
+impl fmt::Display for RelayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayError::Submission(msg) => write!(f, "submission failed: {}", msg),
+            RelayError::Tx(e) => write!(f, "{}", e),
+            RelayError::Timeout(call) => write!(f, "RPC timed out: {}", call),
+        }
+    }
+}
+
+impl std::error::Error for RelayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RelayError::Submission(_) | RelayError::Timeout(_) => None,
+            RelayError::Tx(e) => Some(e),
+        }
+    }
+}
+
+impl From<TxError> for RelayError {
+    fn from(e: TxError) -> Self {
+        RelayError::Tx(e)
+    }
+}
+
+/// The operation was abandoned because shutdown was requested
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
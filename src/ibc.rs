@@ -1,262 +1,2253 @@
-use anyhow::{Context, Result};
-use tendermint::block::Height;
-use tendermint_rpc::{Client, HttpClient};
+use anyhow::{anyhow, bail, Context, Result};
+use futures::stream::{self, Stream, StreamExt};
+use tendermint::abci::{Event, EventAttribute};
+use tendermint::block::{self, Height};
+use tendermint::{Hash, Time};
+use tendermint_rpc::event::EventData;
+use tendermint_rpc::query::{EventType, Query};
+use tendermint_rpc::{Client, Order, Subscription, SubscriptionClient, WebSocketClient};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
+use base64::Engine;
 use hex;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, VecDeque};
+use std::hash::{Hash as _, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use ibc_proto::ibc::applications::transfer::v2::FungibleTokenPacketData as ProtoFungibleTokenPacketData;
-use ibc_proto::ibc::core::channel::v1::{MsgRecvPacket, Packet};
-use ibc_proto::ibc::core::client::v1::Height as IbcHeight;
+use ibc_proto::ibc::core::channel::v1::{
+    acknowledgement, Acknowledgement, QueryNextSequenceReceiveRequest,
+    QueryNextSequenceReceiveResponse, QueryPacketReceiptRequest, QueryPacketReceiptResponse,
+};
 
-#[derive(Debug)]
+use crate::admin::RelayControl;
+use crate::alert::{AlertEvent, Notifier};
+use crate::channel::{ChannelCache, ChannelInfo};
+use crate::config::{AlertsConfig, PacketFilter, RelayPair, RetryConfig, RetryStep, RpcSelection, ScanStrategy, ZkConfig};
+use crate::denom::DenomResolver;
+use crate::metrics;
+use crate::proof::ProofBackend;
+use crate::retry::retry;
+use crate::rpc::{verify_chain_id, with_timeout, ChainIdMismatch, RpcClient, RpcPool, RPC_PROBE_INTERVAL};
+use crate::config::TransportConfig;
+use crate::relay::{
+    ibc_packet, seconds_since, Direction, MsgType, PacketCallback, RecvPacketBuilder, RelayJob, RelayOutcome, RelayRequest, RelayRouter, RelayStats,
+    RelayWorker,
+};
+use crate::signer::{self, SignerPool};
+use crate::simulate::{query_commitment_proof, simulate_recv_packet, SimResult};
+use crate::sink::EventSink;
+use crate::submit::{FeePayment, GasPrice};
+use crate::state::{load_state, save_state, PacketTracker, PollerState, TrackStatus};
+
+/// ICS-20 packet data encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketVersion {
+    /// Flat `amount`/`denom` fields (`FungibleTokenPacketData`)
+    #[default]
+    V1,
+    /// `tokens` array with structured denoms (`FungibleTokenPacketDataV2`)
+    V2,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FungibleTokenPacketData {
     pub amount: String,
     pub denom: String,
     pub sender: String,
     pub receiver: String,
+    /// ICS-20 memo (e.g. a PFM or IBC-hooks instruction); empty when absent
+    #[serde(default)]
+    pub memo: String,
+    #[serde(default)]
+    pub version: PacketVersion,
+}
+
+/// Decodes `packet_data` as emitted in events: hex (`packet_data_hex`) or base64
+pub fn decode_packet_data(input: &str) -> Result<Vec<u8>> {
+    let input = input.trim();
+    if let Ok(bytes) = hex::decode(input) {
+        return Ok(bytes);
+    }
+    base64::engine::general_purpose::STANDARD
+        .decode(input)
+        .context("Packet data is neither valid hex nor base64")
+}
+
+/// Parses decoded packet bytes as UTF-8 JSON, reporting which step failed
+pub fn parse_packet_json(bytes: &[u8]) -> Result<Value> {
+    let packet_str = std::str::from_utf8(bytes).context("Packet data is not valid UTF-8")?;
+    serde_json::from_str(packet_str).context("Packet data is not valid JSON")
+}
+
+/// True if the JSON looks like ICS-20 transfer data (v1 or v2)
+pub fn is_transfer_packet(v: &Value) -> bool {
+    v.get("sender").is_some() && (v.get("amount").is_some() || v.get("tokens").is_some())
+}
+
+/// Detects the ICS-20 version from the packet JSON (v2 carries a `tokens` array)
+pub fn detect_packet_version(v: &Value) -> PacketVersion {
+    if v.get("tokens").map_or(false, Value::is_array) {
+        PacketVersion::V2
+    } else {
+        PacketVersion::V1
+    }
+}
+
+/// Reads an amount encoded as a JSON string (`"100"`) or number (`100`) in string form,
+/// keeping the digits as sent. A missing amount yields `"0"`; a number that is not a
+/// non-negative integer, or any other JSON type, is an error rather than rewritten.
+pub fn json_amount(v: &Value) -> Result<String> {
+    match v {
+        Value::Null => Ok("0".to_string()),
+        Value::String(s) => Ok(s.clone()),
+        // The number's original text (`arbitrary_precision`), so no digits are lost beyond u64
+        Value::Number(n) => {
+            let amount = n.to_string();
+            if amount.bytes().all(|b| b.is_ascii_digit()) {
+                Ok(amount)
+            } else {
+                bail!("amount {} is not a non-negative integer", amount)
+            }
+        }
+        other => bail!("amount {} is not a string or number", other),
+    }
+}
+
+/// Extracts transfer data from packet JSON of either ICS-20 version.
+/// For v2 only the first token is used for now.
+pub fn parse_transfer_data(v: &Value) -> Result<FungibleTokenPacketData> {
+    let version = detect_packet_version(v);
+
+    let (amount, denom) = match version {
+        PacketVersion::V1 => (
+            json_amount(&v["amount"])?,
+            v["denom"].as_str().unwrap_or("").to_string(),
+        ),
+        PacketVersion::V2 => {
+            let token = &v["tokens"][0];
+            // v2 denom: {"base": "uatom", "trace": [{"port_id": "...", "channel_id": "..."}]}
+            let mut denom = String::new();
+            if let Some(trace) = token["denom"]["trace"].as_array() {
+                for hop in trace {
+                    denom.push_str(&format!(
+                        "{}/{}/",
+                        hop["port_id"].as_str().unwrap_or(""),
+                        hop["channel_id"].as_str().unwrap_or("")
+                    ));
+                }
+            }
+            denom.push_str(token["denom"]["base"].as_str().unwrap_or(""));
+            (json_amount(&token["amount"])?, denom)
+        }
+    };
+
+    Ok(FungibleTokenPacketData {
+        amount,
+        denom,
+        sender: v["sender"].as_str().unwrap_or("").to_string(),
+        receiver: v["receiver"].as_str().unwrap_or("").to_string(),
+        memo: v["memo"].as_str().unwrap_or("").to_string(),
+        version,
+    })
+}
+
+/// IBC core protocol version a packet was sent with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelVersion {
+    /// Classic connection-scoped channels (`packet_src_channel`, ...)
+    #[default]
+    V1,
+    /// IBC v2 / Eureka: client IDs instead of channels (`packet_source_client`, ...)
+    V2,
+}
+
+/// Packet timeout as carried on the wire: nanoseconds since the unix epoch, 0 meaning
+/// the packet has no timestamp timeout (only `timeout_height` applies)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TimeoutTimestamp(u64);
+
+impl TimeoutTimestamp {
+    pub const NONE: TimeoutTimestamp = TimeoutTimestamp(0);
+
+    pub fn from_nanos(nanos: u64) -> Self {
+        Self(nanos)
+    }
+
+    /// Raw value for `Packet::timeout_timestamp`
+    pub fn nanos(self) -> u64 {
+        self.0
+    }
+
+    pub fn is_set(self) -> bool {
+        self.0 != 0
+    }
+
+    /// Deadline as wall-clock time; `None` without a timestamp timeout
+    pub fn as_system_time(self) -> Option<SystemTime> {
+        self.is_set().then(|| UNIX_EPOCH + Duration::from_nanos(self.0))
+    }
+
+    /// Whether a destination block at `block_time` can no longer receive the packet
+    /// (IBC times out once the block time reaches the timeout). Never true when unset.
+    pub fn has_expired(self, block_time: SystemTime) -> bool {
+        self.as_system_time().is_some_and(|deadline| block_time >= deadline)
+    }
+}
+
+impl std::fmt::Display for TimeoutTimestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.is_set() {
+            return write!(f, "none");
+        }
+        let secs = (self.0 / 1_000_000_000) as i64;
+        let nanos = (self.0 % 1_000_000_000) as u32;
+        match chrono::DateTime::from_timestamp(secs, nanos) {
+            Some(time) => write!(f, "{}", time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+            None => write!(f, "{} ns", self.0),
+        }
+    }
+}
+
+/// How the packet data was understood. Serialized as `{"kind": "json"}` or
+/// `{"kind": "raw", "hex": "..."}`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "hex", rename_all = "snake_case")]
+pub enum PacketPayload {
+    /// JSON data; ICS-20 transfer fields are in `ParsedPacket::data`
+    #[default]
+    Json,
+    /// Data that is not JSON (e.g. protobuf), relayed verbatim; `ParsedPacket::data` is empty
+    Raw(#[serde(with = "hex_bytes")] Vec<u8>),
 }
 
-#[derive(Debug)]
+/// Bytes as a lowercase hex string
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        hex::decode(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A detected packet. Its JSON form (serde) has stable field names, shared by the
+/// event sink, admin API and library consumers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ParsedPacket {
+    #[serde(default)]
+    pub channel_version: ChannelVersion,
     pub sequence: u64,
     pub src_port: String,
     pub src_channel: String,
     pub dst_port: String,
     pub dst_channel: String,
     pub timeout_height: String,
-    pub timeout_timestamp: u64,
+    pub timeout_timestamp: TimeoutTimestamp,
     pub data: FungibleTokenPacketData,
+    #[serde(default)]
+    pub payload: PacketPayload,
+}
+
+/// Why a packet event could not be parsed – the packet is skipped instead of relayed with made-up values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketParseError {
+    /// A mandatory attribute is absent or empty
+    MissingField(&'static str),
+    /// An attribute is present but malformed
+    InvalidField { field: &'static str, value: String },
+    /// `packet_data_hex` could not be decoded/parsed
+    InvalidData(String),
+    /// Decoded packet data is larger than `max_packet_bytes`
+    Oversized { size: usize, limit: usize },
+}
+
+impl std::fmt::Display for PacketParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PacketParseError::MissingField(field) => write!(f, "missing attribute '{}'", field),
+            PacketParseError::InvalidField { field, value } => write!(f, "malformed attribute '{}' = '{}'", field, value),
+            PacketParseError::InvalidData(reason) => write!(f, "invalid packet data: {}", reason),
+            PacketParseError::Oversized { size, limit } => {
+                write!(f, "packet data is {} bytes, above the {} byte limit", size, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PacketParseError {}
+
+/// Key and value of an event attribute as text. Nodes on CometBFT 0.34 emit both
+/// base64-encoded; a key that base64-decodes to an attribute name (lowercase letters,
+/// digits, `_`, `.`) marks such a pair, and both are decoded. Plain keys never qualify:
+/// attribute names contain `_` or are not padded to a multiple of 4.
+pub fn decode_attr(attr: &EventAttribute) -> (String, String) {
+    let key = attr.key_str().unwrap_or("");
+    let value = attr.value_str().unwrap_or("");
+    match decode_base64_text(key).filter(|key| is_attr_name(key)) {
+        Some(key) => (key, decode_base64_text(value).unwrap_or_else(|| value.to_string())),
+        None => (key.to_string(), value.to_string()),
+    }
+}
+
+fn decode_base64_text(input: &str) -> Option<String> {
+    let bytes = base64::engine::general_purpose::STANDARD.decode(input).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+fn is_attr_name(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '.')
+}
+
+/// Returns the value of the first attribute with the given key (see `decode_attr`)
+pub fn event_attr(event: &Event, key: &str) -> Option<String> {
+    event
+        .attributes
+        .iter()
+        .map(decode_attr)
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value)
+}
+
+/// Builds a `ParsedPacket` from a `send_packet`/`write_acknowledgement`/`recv_packet` event.
+/// Sequence, ports, channels and packet data are mandatory; packet data above
+/// `max_data_bytes` is rejected before it is parsed. Packet data that is not JSON becomes
+/// a `PacketPayload::Raw` payload, or an error with `strict_json`.
+pub fn try_parse_event(
+    event: &Event,
+    max_data_bytes: usize,
+    strict_json: bool,
+) -> std::result::Result<ParsedPacket, PacketParseError> {
+    let required = |field: &'static str| -> std::result::Result<String, PacketParseError> {
+        match event_attr(event, field) {
+            Some(value) if !value.is_empty() => Ok(value),
+            _ => Err(PacketParseError::MissingField(field)),
+        }
+    };
+
+    let sequence_str = required("packet_sequence")?;
+    let sequence = sequence_str.parse::<u64>().map_err(|_| PacketParseError::InvalidField {
+        field: "packet_sequence",
+        value: sequence_str.clone(),
+    })?;
+
+    let src_port = required("packet_src_port")?;
+    let src_channel = required("packet_src_channel")?;
+    let dst_port = required("packet_dst_port")?;
+    let dst_channel = required("packet_dst_channel")?;
+
+    let timeout_height = event_attr(event, "packet_timeout_height").unwrap_or_default();
+    let timeout_timestamp = match event_attr(event, "packet_timeout_timestamp") {
+        Some(value) if !value.is_empty() => {
+            TimeoutTimestamp::from_nanos(value.parse::<u64>().map_err(|_| PacketParseError::InvalidField {
+                field: "packet_timeout_timestamp",
+                value: value.clone(),
+            })?)
+        }
+        _ => TimeoutTimestamp::NONE,
+    };
+
+    let packet_data_hex = required("packet_data_hex")?;
+    // Hex and base64 both decode to at least half the input length – skip hopeless decodes
+    if packet_data_hex.len() / 2 > max_data_bytes {
+        return Err(PacketParseError::Oversized { size: packet_data_hex.len() / 2, limit: max_data_bytes });
+    }
+    let bytes = decode_packet_data(&packet_data_hex).map_err(|e| PacketParseError::InvalidData(format!("{:#}", e)))?;
+    if bytes.len() > max_data_bytes {
+        return Err(PacketParseError::Oversized { size: bytes.len(), limit: max_data_bytes });
+    }
+    let (data, payload) = match parse_packet_json(&bytes) {
+        Ok(v) => (
+            parse_transfer_data(&v).map_err(|e| PacketParseError::InvalidData(format!("{:#}", e)))?,
+            PacketPayload::Json,
+        ),
+        Err(e) if strict_json => return Err(PacketParseError::InvalidData(format!("{:#}", e))),
+        Err(_) => (FungibleTokenPacketData::default(), PacketPayload::Raw(bytes)),
+    };
+
+    Ok(ParsedPacket {
+        channel_version: ChannelVersion::V1,
+        sequence,
+        src_port,
+        src_channel,
+        dst_port,
+        dst_channel,
+        timeout_height,
+        timeout_timestamp,
+        data,
+        payload,
+    })
+}
+
+/// Result of the receive on the destination, as carried by an acknowledgement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AckOutcome {
+    /// Receive succeeded (app-specific result bytes)
+    Success(Vec<u8>),
+    /// Receive failed – the source chain refunds once this ack is relayed back
+    Error(String),
+    /// Bytes are neither a JSON nor a protobuf `Acknowledgement`
+    Unknown,
+}
+
+/// Decodes acknowledgement bytes (`result`/`error` oneof).
+/// Most apps (ICS-20) write the JSON form, so it is tried before protobuf.
+pub fn parse_acknowledgement(bytes: &[u8]) -> AckOutcome {
+    if let Ok(v) = serde_json::from_slice::<Value>(bytes) {
+        if let Some(result) = v.get("result") {
+            let data = result
+                .as_str()
+                .and_then(|r| base64::engine::general_purpose::STANDARD.decode(r).ok())
+                .unwrap_or_default();
+            return AckOutcome::Success(data);
+        }
+        if let Some(err) = v.get("error") {
+            return AckOutcome::Error(err.as_str().unwrap_or_default().to_string());
+        }
+        return AckOutcome::Unknown;
+    }
+
+    match <Acknowledgement as prost::Message>::decode(bytes) {
+        Ok(Acknowledgement { response: Some(acknowledgement::Response::Result(data)) }) => AckOutcome::Success(data),
+        Ok(Acknowledgement { response: Some(acknowledgement::Response::Error(err)) }) => AckOutcome::Error(err),
+        _ => AckOutcome::Unknown,
+    }
+}
+
+/// Counters collected during a catch-up pass
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScanReport {
+    pub blocks: u64,
+    pub packets: u64,
+    pub failed: u64,
+}
+
+/// Progress of a detection stream between yielded packets
+struct DetectState<'a> {
+    poller: &'a mut IbcPoller,
+    /// Packets found in the last scanned block, not yet yielded
+    pending: VecDeque<RelayJob>,
+    /// Tip scanning is catching up to; `None` until the first successful query
+    tip: Option<u64>,
+    report: ScanReport,
+}
+
+/// Tracks source-chain progress for halt alerts
+struct Watchdog {
+    notifier: Arc<dyn Notifier>,
+    stall_after: Duration,
+    last_tip: u64,
+    last_tip_at: Instant,
+    halt_reported: bool,
+}
+
+/// How often a paused poller checks whether it was resumed
+const PAUSED_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the destination is checked for a packet receipt in monitor mode
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a packet may stay unreceived before it is reported as unrelayed
+const RECEIPT_WAIT_LIMIT: Duration = Duration::from_secs(600);
+
+/// Watches the destination chain for receipts of detected packets (monitor mode)
+#[derive(Clone)]
+struct Monitor {
+    client: RpcClient,
+    rpc_timeout: Duration,
+    notifier: Option<Arc<dyn Notifier>>,
+}
+
+impl Monitor {
+    /// Spawns a background task that reports the relay latency once the packet is received
+    fn watch(&self, relay_name: &str, parsed: &ParsedPacket) {
+        let monitor = self.clone();
+        let relay_name = relay_name.to_string();
+        let port = parsed.dst_port.clone();
+        let channel = parsed.dst_channel.clone();
+        let sequence = parsed.sequence;
+        let detected_at = Instant::now();
+        let detected_wall = SystemTime::now();
+
+        tokio::spawn(async move {
+            while detected_at.elapsed() < RECEIPT_WAIT_LIMIT {
+                let query = query_packet_receipt(&monitor.client, &port, &channel, sequence);
+                match with_timeout(monitor.rpc_timeout, "packet_receipt", query).await {
+                    Ok(true) => {
+                        metrics::observe(
+                            "relay_e2e_seconds",
+                            &[
+                                ("relay", &relay_name),
+                                ("mode", "monitor"),
+                                ("direction", Direction::SrcToDst.as_str()),
+                                ("msg_type", MsgType::Recv.as_str()),
+                            ],
+                            seconds_since(detected_wall),
+                        );
+                        info!(
+                            "[{}] Packet {}/{} #{} received on destination – relay latency {:.1} sec",
+                            relay_name, port, channel, sequence, detected_at.elapsed().as_secs_f64()
+                        );
+                        return;
+                    }
+                    Ok(false) => {}
+                    Err(e) => debug!("Receipt query for sequence {} failed: {}", sequence, e),
+                }
+                sleep(RECEIPT_POLL_INTERVAL).await;
+            }
+
+            warn!(
+                "[{}] Packet {}/{} #{} still unrelayed after {} sec",
+                relay_name, port, channel, sequence, RECEIPT_WAIT_LIMIT.as_secs()
+            );
+            if let Some(notifier) = &monitor.notifier {
+                notifier.notify(AlertEvent::UnrelayedPacket {
+                    relay: relay_name,
+                    channel,
+                    sequence,
+                    waited_secs: RECEIPT_WAIT_LIMIT.as_secs(),
+                });
+            }
+        });
+    }
+}
+
+/// Queries whether the destination has a receipt for the packet (unordered channels)
+pub async fn query_packet_receipt<C: Client + Sync>(client: &C, port_id: &str, channel_id: &str, sequence: u64) -> Result<bool> {
+    let request = QueryPacketReceiptRequest {
+        port_id: port_id.to_string(),
+        channel_id: channel_id.to_string(),
+        sequence,
+    };
+
+    let res = client
+        .abci_query(
+            Some("/ibc.core.channel.v1.Query/PacketReceipt".to_string()),
+            prost::Message::encode_to_vec(&request),
+            None,
+            false,
+        )
+        .await?;
+
+    if res.code.is_err() {
+        return Err(anyhow!("PacketReceipt query failed: {}", res.log));
+    }
+
+    let response = <QueryPacketReceiptResponse as prost::Message>::decode(res.value.as_slice())?;
+    Ok(response.received)
+}
+
+/// Queries the next sequence the destination expects on an ordered channel
+pub async fn query_next_sequence_receive<C: Client + Sync>(client: &C, port_id: &str, channel_id: &str) -> Result<u64> {
+    let request = QueryNextSequenceReceiveRequest {
+        port_id: port_id.to_string(),
+        channel_id: channel_id.to_string(),
+    };
+
+    let res = client
+        .abci_query(
+            Some("/ibc.core.channel.v1.Query/NextSequenceReceive".to_string()),
+            prost::Message::encode_to_vec(&request),
+            None,
+            false,
+        )
+        .await?;
+
+    if res.code.is_err() {
+        return Err(anyhow!("NextSequenceReceive query failed: {}", res.log));
+    }
+
+    let response = <QueryNextSequenceReceiveResponse as prost::Message>::decode(res.value.as_slice())?;
+    Ok(response.next_sequence_receive)
+}
+
+/// Whether the destination already received the packet: its receipt on unordered
+/// channels, the next expected sequence on ordered ones
+pub async fn packet_received<C: Client + Sync>(client: &C, packet: &ParsedPacket, ordered: bool) -> Result<bool> {
+    if ordered {
+        let next = query_next_sequence_receive(client, &packet.dst_port, &packet.dst_channel).await?;
+        Ok(packet.sequence < next)
+    } else {
+        query_packet_receipt(client, &packet.dst_port, &packet.dst_channel, packet.sequence).await
+    }
+}
+
+/// Detected packets buffered between the poller and its relay worker
+const RELAY_QUEUE_CAPACITY: usize = 100;
+
+/// Number of recent block hashes kept for reorg detection
+const REORG_WINDOW: usize = 64;
+
+/// Failed `block_results` attempts after which the scan counts as stalled at the block and
+/// `scan_to` gives up
+const BLOCK_RESULTS_MAX_RETRIES: u32 = 10;
+
+/// First delay before retrying a failed `block_results`; doubled per failure
+const BLOCK_RETRY_INITIAL_DELAY: Duration = Duration::from_millis(500);
+
+/// Upper bound for the `block_results` retry delay
+const BLOCK_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Largest height range covered by one round of `tx_search` queries
+const TX_SEARCH_MAX_BLOCKS: u64 = 1000;
+
+/// Results per `tx_search` page (the RPC caps this at 100)
+const TX_SEARCH_PAGE_SIZE: u8 = 100;
+
+/// Consecutive failed tip queries before switching to the next source RPC
+const RPC_FAILOVER_THRESHOLD: u32 = 3;
+
+/// First delay between startup connection attempts; doubled after each failure
+const CONNECT_RETRY_INITIAL_DELAY: Duration = Duration::from_secs(1);
+/// Upper bound for the startup retry delay
+const CONNECT_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// First delay before resubscribing after the WebSocket dropped; doubled after each failure
+const WS_RECONNECT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+/// Upper bound for the resubscribe delay
+const WS_RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// An open event subscription on a WebSocket endpoint
+struct WsSubscription {
+    client: WebSocketClient,
+    driver: JoinHandle<std::result::Result<(), tendermint_rpc::Error>>,
+    events: Subscription,
+}
+
+impl WsSubscription {
+    async fn open(url: &str, query: Query, rpc_timeout: Duration) -> Result<Self> {
+        let url: tendermint_rpc::Url = url.parse()?;
+        let (client, driver) = with_timeout(rpc_timeout, "websocket connect", WebSocketClient::new(url)).await?;
+        let driver = tokio::spawn(driver.run());
+        match with_timeout(rpc_timeout, "subscribe", client.subscribe(query)).await {
+            Ok(events) => Ok(Self { client, driver, events }),
+            Err(e) => {
+                let _ = client.close();
+                Err(e)
+            }
+        }
+    }
+
+    async fn close(self) {
+        let _ = self.client.close();
+        let _ = self.driver.await;
+    }
+}
+
+/// Connects to `url` and returns the client with the node's latest height
+async fn probe_endpoint(url: &str, rpc_timeout: Duration) -> Result<(RpcClient, u64)> {
+    let client = RpcClient::new(url)
+        .context(format!("Failed to connect to RPC: {}", url))?;
+    let info = with_timeout(rpc_timeout, "abci_info", client.abci_info()).await?;
+    Ok((client, info.last_block_height.value()))
+}
+
+/// Startup check that the fee granter has an account on the destination; logged only,
+/// the grant itself is checked by the chain when a transaction uses it
+async fn check_fee_granter(relay: &RelayPair, granter: &str, rpc_timeout: Duration) {
+    let lookup = async {
+        let dst_client = RpcClient::new(&relay.dst_rpc)?;
+        signer::query_account(&dst_client, granter).await
+    };
+    match with_timeout(rpc_timeout, "fee granter lookup", lookup).await {
+        Ok(_) => info!("[{}] Relay fees are paid by fee granter {}", relay.name, granter),
+        Err(e) => warn!("[{}] Fee granter {} not usable on {}: {:#}", relay.name, granter, relay.dst_chain, e),
+    }
+}
+
+/// Startup guard against RPCs of the wrong network: the source must serve `src_chain`.
+/// The destination must serve `dst_chain`; if it cannot be reached it is only logged.
+async fn verify_chains(relay: &RelayPair, src_client: &RpcClient) -> Result<()> {
+    verify_chain_id(src_client, &relay.src_chain)
+        .await
+        .context(format!("[{}] Source chain ID check failed", relay.name))?;
+
+    let dst_client = RpcClient::new(&relay.dst_rpc)?;
+    match verify_chain_id(&dst_client, &relay.dst_chain).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.is::<ChainIdMismatch>() => {
+            Err(e.context(format!("[{}] Destination chain ID check failed", relay.name)))
+        }
+        Err(e) => {
+            warn!("[{}] Could not verify the destination chain ID: {:#}", relay.name, e);
+            Ok(())
+        }
+    }
+}
+
+/// Connects to the first reachable endpoint, in order: `(index, client, height)`
+async fn connect_first(relay_name: &str, endpoints: &[String], rpc_timeout: Duration) -> Result<(usize, RpcClient, u64)> {
+    let mut last_error = None;
+    for (index, url) in endpoints.iter().enumerate() {
+        match probe_endpoint(url, rpc_timeout).await {
+            Ok((client, height)) => return Ok((index, client, height)),
+            Err(e) => {
+                if index + 1 < endpoints.len() {
+                    warn!("[{}] Source RPC {} unavailable, trying next: {}", relay_name, url, e);
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow!("No source RPC configured for {}", relay_name)))
 }
 
 pub struct IbcPoller {
-    client: HttpClient,
+    client: RpcClient,
+    /// `src_rpc` followed by its fallbacks
+    src_endpoints: Vec<String>,
+    /// Index of the endpoint `client` is connected to
+    active_endpoint: usize,
+    /// Consecutive failed tip queries on the active endpoint
+    rpc_failures: u32,
+    /// Consecutive failed `block_results` queries for the next block
+    block_failures: u32,
+    relay_name: String,
+    src_chain: String,
     channel_id: String,
     last_height: u64,
+    rpc_timeout: Duration,
+    /// Recognize IBC v2 (Eureka) packet events
+    channel_v2: bool,
+    /// Event kinds considered by `handle_event`
+    watched_events: Vec<String>,
+    /// Block walking or tx index search
+    scan_strategy: ScanStrategy,
+    /// WebSocket endpoint `subscribe` listens on; it polls without one
+    src_ws: Option<String>,
+    /// Blocks are scanned only once they are this far below the tip
+    confirmations: u64,
+    /// Wait for new blocks once caught up with the tip
+    poll_interval: Duration,
+    /// Pause between two scanned blocks
+    block_step_delay: Duration,
+    /// Sender/receiver patterns a packet must match to be relayed
+    packet_filter: PacketFilter,
+    /// Metadata of the watched source channel
+    channels: ChannelCache,
+    /// Whether the closed source channel was already reported
+    closed_warned: bool,
+    /// Larger packet data is skipped
+    max_packet_bytes: usize,
+    /// Skip non-JSON packet data instead of relaying it raw
+    strict_json: bool,
+    /// Destination RPC and account used by `simulate_relay`
+    dst_client: Option<RpcClient>,
+    dst_signer: String,
+    /// Fee estimates in `simulate_relay`
+    gas_price: Option<GasPrice>,
+    /// Fee granter/payer of relay transactions
+    fee_payment: FeePayment,
+    /// Retry policies of the proof query and simulation in `simulate_relay`
+    retry: RetryConfig,
+    /// Packets in flight and sequences already relayed, shared with the worker
+    tracker: Arc<PacketTracker>,
+    /// Snapshot file written on shutdown (persistence is off when unset)
+    state_path: Option<PathBuf>,
+    /// In-flight packets from the restored snapshot, re-queued when polling starts
+    restored: Vec<RelayRequest>,
+    /// `last_height` comes from a snapshot or the genesis start rather than the tip
+    resumed: bool,
+    /// Polling stops at the next block boundary once this turns true
+    shutdown: Option<watch::Receiver<bool>>,
+    /// Pause/scan requests from the admin API; receives the height and failed packets
+    control: Option<Arc<RelayControl>>,
+    /// Relay worker pool of each destination (`RelayPair::destinations`, primary first),
+    /// each worker moved into its own task when polling starts
+    workers: Vec<Vec<RelayWorker>>,
+    /// Detected packets waiting for the worker pool of each destination; bounded for backpressure
+    queues: Vec<PoolQueues>,
+    watchdog: Option<Watchdog>,
+    /// Hashes of recently processed blocks (oldest first) for reorg detection
+    recent_hashes: VecDeque<(u64, Hash)>,
+    monitor: Option<Monitor>,
+    /// Receives packets forwarded by peers for this relay
+    router: Option<Arc<RelayRouter>>,
 }
 
 impl IbcPoller {
-    /// Initialize poller for a specific channel
-    pub async fn new(rpc_url: &str, channel_id: &str) -> Result<Self> {
-        let client = HttpClient::new(rpc_url)
-            .context(format!("Failed to connect to RPC: {}", rpc_url))?;
+    /// Initialize poller for a specific channel, connecting to the first reachable source RPC.
+    /// While none is reachable, retries with exponential backoff up to `connect_retries` times.
+    pub async fn new(relay: &RelayPair) -> Result<Self> {
+        let channel_id = &relay.src_channel;
+        let rpc_timeout = relay.rpc_timeout();
+        let src_endpoints = relay.src_endpoints();
+
+        let max_retries = relay.connect_retries();
+        let mut attempt = 0;
+        let mut delay = CONNECT_RETRY_INITIAL_DELAY;
+        let (active_endpoint, client, last_height) = loop {
+            match connect_first(&relay.name, &src_endpoints, rpc_timeout).await {
+                Ok(connected) => break connected,
+                Err(e) if attempt < max_retries => {
+                    attempt += 1;
+                    warn!(
+                        "[{}] Source RPC unreachable: {}. Retry {}/{} in {} s...",
+                        relay.name, e, attempt, max_retries, delay.as_secs()
+                    );
+                    sleep(delay).await;
+                    delay = (delay * 2).min(CONNECT_RETRY_MAX_DELAY);
+                }
+                Err(e) => return Err(e.context("Failed to get ABCI info during initialization")),
+            }
+        };
 
-        let info = client.abci_info().await
-            .context("Failed to get ABCI info during initialization")?;
-        let last_height = info.last_block_height.value();
+        if relay.chain_id_verification() {
+            verify_chains(relay, &client).await?;
+        }
+        let client = if relay.src_rpc_selection.unwrap_or_default() == RpcSelection::Latency && src_endpoints.len() > 1 {
+            let pool = Arc::new(RpcPool::new(&relay.name, &src_endpoints)?);
+            pool.probe(rpc_timeout).await;
+            pool.spawn_probes(RPC_PROBE_INTERVAL, rpc_timeout);
+            info!("[{}] Source RPC chosen by latency, currently {}", relay.name, pool.active_endpoint());
+            RpcClient::Pool(pool)
+        } else {
+            client
+        };
+        if let Some(granter) = &relay.fee_granter {
+            check_fee_granter(relay, granter, rpc_timeout).await;
+        }
 
         info!("Poller initialized: channel {}, starting height {}", channel_id, last_height);
 
+        let mut channels = ChannelCache::new(client.clone(), rpc_timeout, relay.channel_refresh_interval());
+        if let Err(e) = channels.load(&relay.src_port, channel_id).await {
+            warn!("[{}] Channel metadata unavailable, assuming an unordered channel: {}", relay.name, e);
+        }
+
+        let gas_price = relay.gas_price()?;
+        let tracker = Arc::new(PacketTracker::new(&relay.name, relay.tracker_capacity()));
+        let pool_size = relay.relay_workers();
+        let dst_signer = relay.signer_address()?;
+        let denom_resolver = relay
+            .resolve_denoms
+            .then(|| Arc::new(DenomResolver::new(client.clone(), rpc_timeout)));
+        let mut workers = Vec::new();
+        let mut dst_client = None;
+        // The single-destination relay is the case of a primary without extra destinations.
+        // Only the primary workers report to the tracker, so deduplication and saved state
+        // follow the primary destination.
+        for (index, destination) in relay.destinations().into_iter().enumerate() {
+            let primary = index == 0;
+            let name = if primary {
+                relay.name.clone()
+            } else {
+                format!("{}@{}", relay.name, destination.chain)
+            };
+            let mut pool = Vec::with_capacity(pool_size);
+            for slot in 0..pool_size {
+                let mut worker = RelayWorker::new(&name);
+                worker.set_signer(&dst_signer);
+                if pool_size > 1 {
+                    worker.set_pool_slot(slot);
+                }
+                if let Some(gas_price) = &gas_price {
+                    worker.set_gas_price(gas_price.clone(), relay.gas_estimate());
+                }
+                if relay.mempool_check() {
+                    worker.set_mempool_check(&destination.rpc, rpc_timeout);
+                }
+                if let Some(resolver) = &denom_resolver {
+                    worker.set_denom_resolver(resolver.clone());
+                }
+                if primary {
+                    worker.set_tracker(tracker.clone());
+                }
+                match RpcClient::new(&destination.rpc) {
+                    Ok(client) => {
+                        worker.set_receipt_client(client.clone(), rpc_timeout);
+                        if let (true, Some(src_signer)) = (relay.follow_acks(), &relay.src_signer) {
+                            worker.set_follow_acks(src_signer.clone());
+                        }
+                        if primary {
+                            dst_client = Some(client);
+                        }
+                    }
+                    Err(e) => warn!(
+                        "[{}] Destination RPC unusable, packets evicted from the tracker cannot be checked: {}",
+                        name, e
+                    ),
+                }
+                pool.push(worker);
+            }
+            if !primary {
+                info!("[{}] Also relaying to {} ({})", relay.name, destination.chain, destination.rpc);
+            }
+            metrics::set_gauge("relay_worker_pool_size", &[("relay", &name)], pool_size as f64);
+            workers.push(pool);
+        }
+
         Ok(Self {
             client,
+            src_endpoints,
+            active_endpoint,
+            rpc_failures: 0,
+            block_failures: 0,
+            relay_name: relay.name.clone(),
+            src_chain: relay.src_chain.clone(),
             channel_id: channel_id.to_string(),
             last_height,
+            rpc_timeout,
+            channel_v2: relay.channel_v2_enabled(),
+            watched_events: relay.watched_events(),
+            scan_strategy: relay.scan_strategy.unwrap_or_default(),
+            src_ws: relay.src_ws.clone(),
+            confirmations: relay.confirmations(),
+            poll_interval: relay.poll_interval(),
+            block_step_delay: relay.block_step_delay(),
+            packet_filter: relay.packet_filter.clone(),
+            channels,
+            closed_warned: false,
+            max_packet_bytes: relay.max_packet_bytes(),
+            strict_json: relay.strict_json(),
+            dst_client,
+            dst_signer,
+            gas_price,
+            fee_payment: relay.fee_payment(),
+            retry: RetryConfig::default(),
+            tracker,
+            state_path: None,
+            restored: Vec::new(),
+            resumed: false,
+            shutdown: None,
+            control: None,
+            workers,
+            queues: Vec::new(),
+            watchdog: None,
+            recent_hashes: VecDeque::with_capacity(REORG_WINDOW),
+            monitor: None,
+            router: None,
         })
     }
 
-    /// Process a detected IBC packet (forms MsgRecvPacket and optional ZK proof)
-    async fn relay_packet(&self, parsed: &ParsedPacket, packet_data_hex: &str) -> Result<()> {
-        let packet_start = Instant::now();
+    /// Switches to the next source RPC that is reachable and not behind `last_height`,
+    /// so heights stay consistent. Keeps the current endpoint if none qualifies.
+    async fn failover(&mut self) -> bool {
+        // A latency pool routes around failing endpoints by itself
+        if matches!(self.client, RpcClient::Pool(_)) {
+            return false;
+        }
+        let count = self.src_endpoints.len();
+        for offset in 1..count {
+            let index = (self.active_endpoint + offset) % count;
+            let url = &self.src_endpoints[index];
 
-        info!("Forming MsgRecvPacket for sequence {}", parsed.sequence);
+            match probe_endpoint(url, self.rpc_timeout).await {
+                Ok((client, height)) if height >= self.last_height => {
+                    warn!(
+                        "[{}] Source RPC failover: {} -> {} (height {})",
+                        self.relay_name, self.src_endpoints[self.active_endpoint], url, height
+                    );
+                    self.client = client;
+                    self.active_endpoint = index;
+                    return true;
+                }
+                Ok((_, height)) => warn!(
+                    "[{}] Skipping source RPC {}: height {} is behind last processed height {}",
+                    self.relay_name, url, height, self.last_height
+                ),
+                Err(e) => warn!("[{}] Source RPC {} unavailable: {}", self.relay_name, url, e),
+            }
+        }
+        false
+    }
 
-        let fungible_data = ProtoFungibleTokenPacketData {
-            denom: parsed.data.denom.clone(),
-            amount: parsed.data.amount.clone(),
-            sender: parsed.data.sender.clone(),
-            receiver: parsed.data.receiver.clone(),
-            memo: "".to_string(),
-        };
+    /// Persist progress to `path`: restores the snapshot saved there (height, in-flight packets,
+    /// relayed sequences) and writes a new one after every processed block and when polling stops
+    pub fn with_state_file(mut self, path: PathBuf) -> Result<Self> {
+        match load_state(&path)? {
+            Some(state) if state.channel_id != self.channel_id => warn!(
+                "[{}] Ignoring {}: saved for channel {}, not {}",
+                self.relay_name, path.display(), state.channel_id, self.channel_id
+            ),
+            Some(state) => {
+                info!(
+                    "[{}] Resuming from height {} ({} in-flight packets, {} relayed sequences)",
+                    self.relay_name, state.last_height, state.in_flight.len(), state.relayed.len()
+                );
+                self.last_height = state.last_height;
+                self.tracker.restore(&state);
+                self.restored = state.in_flight;
+                self.resumed = true;
+            }
+            None => {}
+        }
+        self.state_path = Some(path);
+        Ok(self)
+    }
 
-        let mut data_bytes = Vec::new();
-        prost::Message::encode(&fungible_data, &mut data_bytes)
-            .context("Failed to encode FungibleTokenPacketData")?;
-
-        let revision_height = parsed.timeout_height
-            .split('-')
-            .nth(1)
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(0);
-
-        let packet = Packet {
-            sequence: parsed.sequence,
-            source_port: parsed.src_port.clone(),
-            source_channel: parsed.src_channel.clone(),
-            destination_port: parsed.dst_port.clone(),
-            destination_channel: parsed.dst_channel.clone(),
-            data: data_bytes,
-            timeout_height: if revision_height > 0 {
-                Some(IbcHeight {
-                    revision_number: 1,
-                    revision_height,
-                })
-            } else {
-                None
-            },
-            timeout_timestamp: parsed.timeout_timestamp,
+    /// Stop polling (at a block boundary, after saving state) once `shutdown` turns true
+    pub fn with_shutdown(mut self, shutdown: watch::Receiver<bool>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Abort running proofs and stop the relay worker once `cancel` fires (shutdown)
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        for worker in self.workers.iter_mut().flatten() {
+            worker.set_cancel(cancel.clone());
+        }
+        self
+    }
+
+    /// Scan from the earliest block the source node keeps instead of the tip or the saved
+    /// height (`[state] start_from = "genesis"`); relayed sequences restored from a snapshot
+    /// are still skipped
+    pub async fn with_genesis_start(mut self) -> Result<Self> {
+        let status = with_timeout(self.rpc_timeout, "status", self.client.status()).await?;
+        let earliest = status.sync_info.earliest_block_height.value().max(1);
+        info!("[{}] Catching up from the earliest available block {}", self.relay_name, earliest);
+        self.last_height = earliest - 1;
+        self.recent_hashes.clear();
+        self.resumed = true;
+        Ok(self)
+    }
+
+    /// Writes the current snapshot to the state file, if one is configured
+    pub fn save_state(&self) -> Result<()> {
+        if let (Some(state), Some(path)) = (self.write_state()?, &self.state_path) {
+            info!(
+                "[{}] Saved state at height {} ({} in flight) to {}",
+                self.relay_name, state.last_height, state.in_flight.len(), path.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Saves the snapshot after a processed block, so a crash loses no more than that block
+    fn checkpoint(&self) {
+        if let Err(e) = self.write_state() {
+            warn!("[{}] Failed to save state at height {}: {:#}", self.relay_name, self.last_height, e);
+        }
+    }
+
+    /// Writes the snapshot; `None` without a state file
+    fn write_state(&self) -> Result<Option<PollerState>> {
+        let Some(path) = &self.state_path else { return Ok(None) };
+
+        let mut state = PollerState {
+            relay: self.relay_name.clone(),
+            channel_id: self.channel_id.clone(),
+            last_height: self.last_height,
+            ..Default::default()
         };
+        self.tracker.fill(&mut state);
+        save_state(path, &state)?;
+        debug!("[{}] Saved state at height {} to {}", self.relay_name, state.last_height, path.display());
+        Ok(Some(state))
+    }
+
+    fn shutdown_requested(&self) -> bool {
+        self.shutdown.as_ref().is_some_and(|shutdown| *shutdown.borrow())
+    }
+
+    /// Report status to and take pause/scan requests from the admin API. Packet statuses
+    /// are those of the primary destination.
+    pub fn with_control(mut self, control: Arc<RelayControl>) -> Self {
+        if let Some(pool) = self.workers.first_mut() {
+            for worker in pool {
+                worker.set_control(control.clone());
+            }
+        }
+        self.control = Some(control);
+        self.publish_channel_state();
+        self
+    }
+
+    /// Copies the cached source channel state to the admin API
+    fn publish_channel_state(&self) {
+        if let (Some(control), Some(channel)) = (&self.control, self.channel_info()) {
+            control.set_channel_state(&channel.state);
+        }
+    }
 
-        let msg = MsgRecvPacket {
-            packet: Some(packet),
-            proof_commitment: vec![],
-            proof_height: Some(IbcHeight {
-                revision_number: 0,
-                revision_height: self.last_height,
-            }),
-            signer: std::env::var("RELAYER_SIGNER")
-                .unwrap_or_else(|_| "replace_with_your_address".to_string()),
+    /// Sleeps for `duration`, returning early when shutdown or a scan is requested
+    async fn pause(&mut self, duration: Duration) {
+        let control = self.control.as_ref();
+        let shutdown = self.shutdown.as_mut();
+        let scan = async {
+            match control {
+                Some(control) => control.scan_requested().await,
+                None => std::future::pending().await,
+            }
+        };
+        let wait = async {
+            match shutdown {
+                Some(shutdown) => {
+                    let _ = tokio::time::timeout(duration, shutdown.wait_for(|stop| *stop)).await;
+                }
+                None => sleep(duration).await,
+            }
         };
+        tokio::select! {
+            _ = scan => {}
+            _ = wait => {}
+        }
+    }
 
-        info!("MsgRecvPacket formed successfully!");
-        info!("  Sequence: {}", msg.packet.as_ref().unwrap().sequence);
-        info!("  Src: {} / {}", msg.packet.as_ref().unwrap().source_port, msg.packet.as_ref().unwrap().source_channel);
-        info!("  Dst: {} / {}", msg.packet.as_ref().unwrap().destination_port, msg.packet.as_ref().unwrap().destination_channel);
-        info!("  Amount: {} {}", parsed.data.amount, parsed.data.denom);
-        info!("  Signer: {}", msg.signer);
+    /// Holds scanning at a block boundary while the relay is paused via the admin API
+    async fn wait_while_paused(&mut self) {
+        let Some(control) = self.control.clone() else { return };
+        if !control.is_paused() {
+            return;
+        }
 
-        #[cfg(feature = "encryption-proof")]
-        {
-            info!("Launching ZK proof generation...");
-            let zk_start = Instant::now();
+        info!("[{}] Paused at height {}", self.relay_name, self.last_height);
+        while control.is_paused() && !self.shutdown_requested() {
+            self.pause(PAUSED_CHECK_INTERVAL).await;
+        }
+        info!("[{}] Resumed at height {}", self.relay_name, self.last_height);
+    }
 
-            match crate::generate_packet_proof(packet_data_hex) {
-                Ok(proof) => {
-                    let zk_time = zk_start.elapsed().as_millis() as f64 / 1000.0;
-                    info!("ZK proof generated successfully (size: {} bytes, time: {:.3} sec)", proof.len(), zk_time);
+    /// Hands the in-flight packets of a restored snapshot back to the worker
+    async fn requeue_restored(&mut self) {
+        for request in std::mem::take(&mut self.restored) {
+            let sequence = request.sequence;
+            let status = self.tracker.begin(request.clone());
+            match request.into_job() {
+                Ok(mut job) => {
+                    job.check_receipt = status == TrackStatus::Unknown;
+                    send_job(&self.queues, job).await;
                 }
                 Err(e) => {
-                    error!("ZK proof generation failed: {:?}", e);
+                    warn!("[{}] Dropping restored packet {}: {}", self.relay_name, sequence, e);
+                    self.tracker.finish(sequence, &RelayOutcome::Failed { error: format!("{:#}", e) });
                 }
             }
         }
+    }
 
-        let packet_duration = packet_start.elapsed();
-        let packet_secs = packet_duration.as_secs_f64();
+    /// Cached metadata of the watched source channel (`None` if it could not be queried)
+    pub fn channel_info(&self) -> Option<&ChannelInfo> {
+        self.channels.channel_info(&self.channel_id)
+    }
 
-        info!("Packet processing metrics (sequence {}):", parsed.sequence);
-        info!("   Total time: {:.3} sec", packet_secs);
+    /// Use a pool of destination signers instead of the relay's single `signer` address
+    pub fn with_signer_pool(mut self, pool: Arc<SignerPool>) -> Self {
+        for worker in self.workers.iter_mut().flatten() {
+            worker.set_signer_pool(pool.clone());
+        }
+        self
+    }
 
-        Ok(())
+    /// Retry policies of the network steps (`[retry]`)
+    pub fn with_retry(mut self, retry: &RetryConfig) -> Self {
+        self.channels.set_retry(retry.policy(RetryStep::ClientState));
+        self.retry = *retry;
+        self
     }
 
-    /// Main polling loop – monitors new blocks and processes relevant IBC packets
-    pub async fn poll(&mut self) -> Result<()> {
-        info!("Polling started for channel {}", self.channel_id);
+    /// Proof generation settings for the relay worker
+    pub fn with_zk_config(mut self, zk: &ZkConfig) -> Self {
+        for worker in self.workers.iter_mut().flatten() {
+            worker.set_zk_config(zk.clone());
+        }
+        self
+    }
 
-        loop {
-            let current_height = match self.client.abci_info().await {
-                Ok(info) => info.last_block_height.value(),
-                Err(e) => {
-                    warn!("Failed to get current block height: {}. Retrying in 10 sec...", e);
-                    sleep(Duration::from_secs(10)).await;
-                    continue;
-                }
-            };
+    /// Use a custom proof backend instead of the one selected in `[zk]` (`None` disables proofs)
+    pub fn with_proof_backend(mut self, backend: Option<Arc<dyn ProofBackend>>) -> Self {
+        for worker in self.workers.iter_mut().flatten() {
+            worker.set_proof_backend(backend.clone());
+        }
+        self
+    }
 
-            while self.last_height < current_height {
-                self.last_height += 1;
-                let height = Height::try_from(self.last_height)
-                    .context("Failed to convert height to tendermint::Height")?;
-
-                debug!("Processing block {}", self.last_height);
-
-                match self.client.block_results(height).await {
-                    Ok(results) => {
-                        if let Some(txs_results) = results.txs_results {
-                            for tx_res in txs_results {
-                                for event in tx_res.events {
-                                    let is_relevant = (event.kind == "send_packet" || event.kind == "write_acknowledgement")
-                                        && event.attributes.iter().any(|a| {
-                                            let key = a.key_str().unwrap_or("");
-                                            let value = a.value_str().unwrap_or("");
-                                            (key == "packet_src_channel" || key == "packet_dst_channel")
-                                                && value == self.channel_id
-                                        });
-
-                                    if is_relevant {
-                                        info!("[Block {}] IBC PACKET DETECTED!", self.last_height);
-
-                                        let mut sequence = 0u64;
-                                        let mut src_port = String::new();
-                                        let mut src_channel = String::new();
-                                        let mut dst_port = String::new();
-                                        let mut dst_channel = String::new();
-                                        let mut timeout_height = String::new();
-                                        let mut timeout_timestamp = 0u64;
-                                        let mut packet_data_hex = String::new();
-
-                                        for attr in &event.attributes {
-                                            let key = attr.key_str().unwrap_or("");
-                                            let value = attr.value_str().unwrap_or("");
-
-                                            match key {
-                                                "packet_sequence" => sequence = value.parse().unwrap_or(0),
-                                                "packet_src_port" => src_port = value.to_string(),
-                                                "packet_src_channel" => src_channel = value.to_string(),
-                                                "packet_dst_port" => dst_port = value.to_string(),
-                                                "packet_dst_channel" => dst_channel = value.to_string(),
-                                                "packet_timeout_height" => timeout_height = value.to_string(),
-                                                "packet_timeout_timestamp" => timeout_timestamp = value.parse().unwrap_or(0),
-                                                "packet_data_hex" => packet_data_hex = value.to_string(),
-                                                _ => {}
-                                            }
-
-                                            info!("   {} = {}", key, value);
-                                        }
-
-                                        if !packet_data_hex.is_empty() {
-                                            match hex::decode(&packet_data_hex) {
-                                                Ok(bytes) => {
-                                                    let packet_str = String::from_utf8_lossy(&bytes);
-                                                    match serde_json::from_str::<Value>(&packet_str) {
-                                                        Ok(v) => {
-                                                            info!("   Packet parsing (human-readable):");
-                                                            info!("     Amount: {}", v["amount"].as_str().unwrap_or("0"));
-                                                            info!("     Denom: {}", v["denom"].as_str().unwrap_or(""));
-                                                            info!("     Sender: {}", v["sender"].as_str().unwrap_or(""));
-                                                            info!("     Receiver: {}", v["receiver"].as_str().unwrap_or(""));
-
-                                                            let parsed = ParsedPacket {
-                                                                sequence,
-                                                                src_port,
-                                                                src_channel,
-                                                                dst_port,
-                                                                dst_channel,
-                                                                timeout_height,
-                                                                timeout_timestamp,
-                                                                data: FungibleTokenPacketData {
-                                                                    amount: v["amount"].as_str().unwrap_or("0").to_string(),
-                                                                    denom: v["denom"].as_str().unwrap_or("").to_string(),
-                                                                    sender: v["sender"].as_str().unwrap_or("").to_string(),
-                                                                    receiver: v["receiver"].as_str().unwrap_or("").to_string(),
-                                                                },
-                                                            };
-
-                                                            info!("   Full packet structure: {:?}", parsed);
-
-                                                            if let Err(e) = self.relay_packet(&parsed, &packet_data_hex).await {
-                                                                error!("Failed to relay packet: {:?}", e);
-                                                            }
-                                                        }
-                                                        Err(e) => warn!("Failed to parse packet JSON: {}", e),
-                                                    }
-                                                }
-                                                Err(e) => warn!("Failed to decode packet hex: {}", e),
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => debug!("Failed to get block results for height {}: {}", self.last_height, e),
-                }
+    /// Observe every relay attempt (packet plus skipped/relayed/failed outcome) without
+    /// parsing logs. The callback runs on the relay worker task and must not block:
+    /// hand slow work off to a channel or spawned task, otherwise relaying stalls and,
+    /// once the queue is full, so does the poll loop.
+    pub fn with_on_packet(mut self, on_packet: PacketCallback) -> Self {
+        for worker in self.workers.iter_mut().flatten() {
+            worker.set_on_packet(on_packet.clone());
+        }
+        self
+    }
 
-                sleep(Duration::from_millis(200)).await;
-            }
+    /// Publish every relay outcome to `sink` (e.g. a NATS subject)
+    pub fn with_event_sink(mut self, sink: Arc<dyn EventSink>) -> Self {
+        for worker in self.workers.iter_mut().flatten() {
+            worker.set_event_sink(sink.clone());
+        }
+        self
+    }
 
-            sleep(Duration::from_secs(6)).await;
+    /// Spawns the relay workers and connects each to its detection queue.
+    /// Returns the worker handles; each finishes once its queue sender is dropped.
+    fn start_workers(&mut self) -> Vec<JoinHandle<RelayStats>> {
+        let mut handles = Vec::new();
+        for pool in std::mem::take(&mut self.workers) {
+            let (shared, shared_rx) = async_channel::bounded(RELAY_QUEUE_CAPACITY);
+            let mut pinned = Vec::with_capacity(pool.len());
+            for worker in pool {
+                let (tx, rx) = mpsc::channel(RELAY_QUEUE_CAPACITY);
+                pinned.push(tx);
+                handles.push(tokio::spawn(worker.run(rx, shared_rx.clone())));
+            }
+            self.queues.push(PoolQueues { pinned, shared });
         }
+        handles
     }
-}
\ No newline at end of file
+
+    /// Read-only monitor mode: nothing is submitted (no keys needed); instead each
+    /// sent packet is watched on the destination until its receipt appears
+    pub fn with_monitor(mut self, relay: &RelayPair) -> Result<Self> {
+        let dst_client = RpcClient::new(&relay.dst_rpc)
+            .context(format!("Failed to connect to destination RPC: {}", relay.dst_rpc))?;
+
+        let notifier = self.watchdog.as_ref().map(|w| w.notifier.clone());
+        self.monitor = Some(Monitor {
+            client: dst_client,
+            rpc_timeout: self.rpc_timeout,
+            notifier,
+        });
+
+        info!("Monitor mode enabled for {} – packets will not be relayed", relay.name);
+        Ok(self)
+    }
+
+    /// Send alerts for repeated relay failures and a halted source chain
+    pub fn with_notifier(mut self, notifier: Arc<dyn Notifier>, alerts: &AlertsConfig) -> Self {
+        for worker in self.workers.iter_mut().flatten() {
+            worker.set_notifier(notifier.clone(), alerts.failure_threshold);
+        }
+        self.watchdog = Some(Watchdog {
+            notifier,
+            stall_after: Duration::from_secs(alerts.stall_after_secs),
+            last_tip: self.last_height,
+            last_tip_at: Instant::now(),
+            halt_reported: false,
+        });
+        self
+    }
+
+    /// Alerts once if the source chain tip has not advanced for `stall_after`
+    fn record_tip(&mut self, tip: u64) {
+        let Some(watchdog) = self.watchdog.as_mut() else { return };
+
+        if tip > watchdog.last_tip {
+            watchdog.last_tip = tip;
+            watchdog.last_tip_at = Instant::now();
+            watchdog.halt_reported = false;
+        } else if !watchdog.halt_reported && watchdog.last_tip_at.elapsed() >= watchdog.stall_after {
+            warn!("Chain {} stalled at height {}", self.src_chain, tip);
+            watchdog.notifier.notify(AlertEvent::ChainHalted {
+                chain: self.src_chain.clone(),
+                height: tip,
+                stalled_secs: watchdog.last_tip_at.elapsed().as_secs(),
+            });
+            watchdog.halt_reported = true;
+        }
+    }
+
+    /// Main polling loop – monitors new blocks and relays relevant IBC packets
+    pub async fn poll(&mut self) -> Result<()> {
+        info!("Polling started for channel {}", self.channel_id);
+
+        if self.monitor.is_none() {
+            self.start_workers();
+            self.requeue_restored().await;
+        }
+
+        let queues = self.queues.clone();
+        let mut result = Ok(());
+        {
+            let jobs = self.jobs_stream();
+            tokio::pin!(jobs);
+            while let Some(job) = jobs.next().await {
+                match job {
+                    Ok(job) => {
+                        send_job(&queues, job).await;
+                    }
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = self.save_state() {
+            error!("[{}] Failed to save state: {:#}", self.relay_name, e);
+        }
+        result
+    }
+
+    /// Event-driven alternative to `poll`: subscribes to the source's `send_packet` txs of the
+    /// channel on `src_ws` and relays each packet as its tx is committed, instead of fetching
+    /// every block. Blocks committed before (re)subscribing are caught up by scanning, and a
+    /// dropped connection is resubscribed with backoff. The subscription only covers
+    /// `send_packet`, so it polls unless `watched_events = ["send_packet"]`; it also polls when
+    /// `src_ws` is unset or unreachable, with `confirmations` (events arrive at the tip) and
+    /// with IBC v2 (its packets are not addressed by channel).
+    pub async fn subscribe(&mut self) -> Result<()> {
+        let Some(url) = self.src_ws.clone() else { return self.poll().await };
+        if self.watched_events != ["send_packet"] {
+            info!(
+                "[{}] watched_events {:?} need block polling (the subscription only covers send_packet), not subscribing",
+                self.relay_name, self.watched_events
+            );
+            return self.poll().await;
+        }
+        if self.confirmations > 0 || self.channel_v2 {
+            info!("[{}] confirmations or IBC v2 need block polling, not subscribing", self.relay_name);
+            return self.poll().await;
+        }
+        let mut subscription = match WsSubscription::open(&url, self.subscription_query(), self.rpc_timeout).await {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                warn!("[{}] WebSocket {} unavailable, polling instead: {:#}", self.relay_name, url, e);
+                return self.poll().await;
+            }
+        };
+        info!("Subscribed to send_packet events of channel {} on {}", self.channel_id, url);
+
+        if self.monitor.is_none() {
+            self.start_workers();
+            self.requeue_restored().await;
+        }
+
+        loop {
+            self.wait_while_paused().await;
+            if self.shutdown_requested() {
+                break;
+            }
+            // Packets found by both the scan and the subscription are dropped by the tracker
+            if let Err(e) = self.catch_up().await {
+                warn!(
+                    "[{}] Catch-up failed, retrying in {} ms: {:#}",
+                    self.relay_name,
+                    self.poll_interval.as_millis(),
+                    e
+                );
+                self.pause(self.poll_interval).await;
+                continue;
+            }
+            if self.follow(&mut subscription).await {
+                continue;
+            }
+            let Some(next) = self.resubscribe(&url).await else { break };
+            std::mem::replace(&mut subscription, next).close().await;
+        }
+        subscription.close().await;
+
+        if let Err(e) = self.save_state() {
+            error!("[{}] Failed to save state: {:#}", self.relay_name, e);
+        }
+        Ok(())
+    }
+
+    /// Txs that sent a packet on the watched channel
+    fn subscription_query(&self) -> Query {
+        Query::from(EventType::Tx).and_eq("send_packet.packet_src_channel", self.channel_id.clone())
+    }
+
+    /// Scans the blocks between `last_height` and the tip
+    async fn catch_up(&mut self) -> Result<()> {
+        let Some(tip) = self.fetch_tip().await else { return Ok(()) };
+        self.record_tip(tip);
+        if self.last_height < tip {
+            let report = self.scan_to(tip).await?;
+            info!(
+                "[{}] Caught up to height {}: {} blocks, {} packets",
+                self.relay_name, tip, report.blocks, report.packets
+            );
+        }
+        self.channels.refresh_stale().await;
+        self.publish_channel_state();
+        Ok(())
+    }
+
+    /// Relays the packets of subscription events until the relay is paused or shut down, a
+    /// reorg needs a rescan (true: the subscription is still usable), or the subscription
+    /// fails or ends (false). Every `poll_interval` it does what the poll loop does between
+    /// blocks (`follow_tick`).
+    async fn follow(&mut self, subscription: &mut WsSubscription) -> bool {
+        let mut shutdown = self.shutdown.clone();
+        let mut ticks = tokio::time::interval_at(Instant::now() + self.poll_interval, self.poll_interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            let stop = async {
+                match &mut shutdown {
+                    Some(shutdown) => {
+                        let _ = shutdown.wait_for(|stop| *stop).await;
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+            // Pending events go first, so a tick only runs once the subscription is drained
+            let event = tokio::select! {
+                biased;
+                event = subscription.events.next() => event,
+                _ = ticks.tick() => {
+                    if !self.follow_tick().await {
+                        return true;
+                    }
+                    continue;
+                }
+                _ = stop => return true,
+            };
+            let event = match event {
+                Some(Ok(event)) => event,
+                Some(Err(e)) => {
+                    warn!("[{}] WebSocket subscription failed: {}", self.relay_name, e);
+                    return false;
+                }
+                None => {
+                    warn!("[{}] WebSocket subscription closed", self.relay_name);
+                    return false;
+                }
+            };
+            let EventData::Tx { tx_result } = event.data else { continue };
+
+            let height = tx_result.height.max(1) as u64;
+            // Other txs of this block may still arrive, so progress stops short of it: a
+            // catch-up after a reconnect rescans the block. Only send_packet is subscribed to
+            // (see `subscribe`), so no other watched event kind is skipped.
+            let resume = self.last_height.max(height - 1);
+            self.last_height = height;
+            let mut report = ScanReport::default();
+            let jobs: Vec<RelayJob> = tx_result
+                .result
+                .events
+                .iter()
+                .filter_map(|event| self.handle_event(event, &mut report))
+                .collect();
+            self.last_height = resume;
+
+            for job in jobs {
+                send_job(&self.queues, job).await;
+            }
+            if let Some(control) = &self.control {
+                control.set_height(self.last_height);
+            }
+            self.checkpoint();
+
+            if self.control.as_ref().is_some_and(|control| control.is_paused()) {
+                return true;
+            }
+        }
+    }
+
+    /// Between subscription events: chain halt watchdog, reorg check, progress and channel
+    /// state refresh, as the poll loop does once caught up. False if a reorg rewound
+    /// `last_height`.
+    async fn follow_tick(&mut self) -> bool {
+        let tip = self.fetch_tip().await;
+        if let Some(tip) = tip {
+            self.record_tip(tip);
+        }
+
+        let height = self.last_height;
+        self.check_reorg().await;
+        if self.last_height < height {
+            return false;
+        }
+
+        // No events are pending (`follow` drains them first), so every block short of the tip
+        // has been delivered: a quiet channel still checkpoints close to the tip
+        if let Some(tip) = tip {
+            self.last_height = self.last_height.max(tip.saturating_sub(1));
+        }
+        let height = self.last_height;
+        if height > 0 {
+            // Events do not record block hashes; the next check compares this one
+            let block_time = match self.block_meta(height).await {
+                Ok(meta) => {
+                    if !self.recent_hashes.iter().any(|(h, _)| *h == height) {
+                        self.record_block_hash(height, meta.block_id.hash);
+                    }
+                    Some(meta.header.time)
+                }
+                Err(e) => {
+                    debug!("Failed to get block hash for height {}: {}", height, e);
+                    None
+                }
+            };
+            self.record_block_metrics(block_time);
+            if let Some(control) = &self.control {
+                control.set_height(height);
+            }
+            self.checkpoint();
+        }
+
+        self.channels.refresh_stale().await;
+        self.publish_channel_state();
+        true
+    }
+
+    /// Reopens the subscription, backing off between failed attempts; `None` on shutdown
+    async fn resubscribe(&mut self, url: &str) -> Option<WsSubscription> {
+        let mut delay = WS_RECONNECT_INITIAL_DELAY;
+        loop {
+            self.wait_while_paused().await;
+            if self.shutdown_requested() {
+                return None;
+            }
+            self.pause(delay).await;
+            metrics::inc_counter("relay_ws_reconnects_total", &[("relay", &self.relay_name)]);
+            match WsSubscription::open(url, self.subscription_query(), self.rpc_timeout).await {
+                Ok(subscription) => {
+                    info!("[{}] Resubscribed on {}", self.relay_name, url);
+                    return Some(subscription);
+                }
+                Err(e) => {
+                    warn!("[{}] Resubscribing on {} failed, retrying in {} s: {:#}", self.relay_name, url, delay.as_secs(), e);
+                    delay = (delay * 2).min(WS_RECONNECT_MAX_DELAY);
+                }
+            }
+        }
+    }
+
+    /// Detected packets as a stream, for library consumers with their own relay logic.
+    /// Tails the source chain like `poll` but yields packets instead of relaying them. Blocks are
+    /// only scanned while the consumer keeps pulling, so a slow consumer throttles scanning.
+    /// An `Err` item is fatal to the scan; monitor mode yields nothing. Ends only on shutdown.
+    pub fn packets_stream(&mut self) -> impl Stream<Item = Result<ParsedPacket>> + '_ {
+        self.jobs_stream().map(|job| job.map(|job| job.packet))
+    }
+
+    /// Detection stream behind `poll` and `packets_stream`
+    fn jobs_stream(&mut self) -> impl Stream<Item = Result<RelayJob>> + '_ {
+        let state = DetectState {
+            poller: self,
+            pending: VecDeque::new(),
+            tip: None,
+            report: ScanReport::default(),
+        };
+
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(job) = state.pending.pop_front() {
+                    return Some((Ok(job), state));
+                }
+                // Between blocks nothing is pending, so `last_height` is safe to persist
+                if state.poller.shutdown_requested() {
+                    return None;
+                }
+                state.poller.wait_while_paused().await;
+                if state.poller.shutdown_requested() {
+                    return None;
+                }
+
+                let poller = &mut *state.poller;
+                match state.tip {
+                    Some(tip) if poller.last_height < tip => match poller.scan_next(tip, &mut state.report).await {
+                        Ok(jobs) => {
+                            state.pending.extend(jobs);
+                            poller.checkpoint();
+                        }
+                        Err(e) => return Some((Err(e), state)),
+                    },
+                    last_tip => {
+                        // Caught up: wait for new blocks before asking for the tip again
+                        if last_tip.is_some() {
+                            poller.pause(poller.poll_interval).await;
+                        }
+                        let tip = poller.fetch_tip().await;
+                        if let Some(tip) = tip {
+                            poller.record_tip(tip);
+                            poller.check_reorg().await;
+                            poller.channels.refresh_stale().await;
+                            poller.publish_channel_state();
+                        }
+                        state.tip = tip.map(|tip| poller.final_height(tip));
+                    }
+                }
+            }
+        })
+    }
+
+    /// Queries the source tip, failing over after repeated errors; `None` means retry
+    async fn fetch_tip(&mut self) -> Option<u64> {
+        match with_timeout(self.rpc_timeout, "abci_info", self.client.abci_info()).await {
+            Ok(info) => {
+                self.rpc_failures = 0;
+                Some(info.last_block_height.value())
+            }
+            Err(e) => {
+                self.rpc_failures += 1;
+                if self.rpc_failures >= RPC_FAILOVER_THRESHOLD && self.failover().await {
+                    self.rpc_failures = 0;
+                    return None;
+                }
+                warn!("Failed to get current block height: {}. Retrying in 10 sec...", e);
+                self.pause(Duration::from_secs(10)).await;
+                None
+            }
+        }
+    }
+
+    /// Highest block treated as final at source tip `tip`: `confirmations` below it
+    fn final_height(&self, tip: u64) -> u64 {
+        tip.saturating_sub(self.confirmations)
+    }
+
+    /// Single catch-up pass for cron-style operation: processes every block up to
+    /// the current (final) tip and returns instead of tailing new blocks. Continues from the
+    /// restored snapshot (or the genesis start) and fails without one.
+    pub async fn run_once(&mut self) -> Result<ScanReport> {
+        // Starting at the tip would scan nothing and still report success
+        if !self.resumed {
+            bail!(
+                "No saved state to resume channel {} from: run the daemon once to create a checkpoint \
+                 (needs [state] enabled), set [state] start_from = \"genesis\", or backfill with --from/--to",
+                self.channel_id
+            );
+        }
+        let tip = with_timeout(self.rpc_timeout, "abci_info", self.client.abci_info()).await
+            .context("Failed to get current block height")?
+            .last_block_height
+            .value();
+        let current_height = self.final_height(tip);
+
+        info!("One-shot scan for channel {}: blocks {}..={}", self.channel_id, self.last_height + 1, current_height);
+
+        let workers = self.start_workers();
+        self.requeue_restored().await;
+        let mut report = self.scan_to(current_height).await?;
+
+        // Close the queues and wait until every detected packet has been relayed
+        self.queues.clear();
+        for worker in workers {
+            report.failed += worker.await?.failed;
+        }
+        self.save_state()?;
+
+        Ok(report)
+    }
+
+    /// Backfill: relays every packet from blocks `from..=to` and returns without tailing new
+    /// blocks, e.g. to replay a window missed while a counterparty relayer was down.
+    /// Fails unless `1 <= from <= to <= tip`. Restored state is neither used nor saved.
+    pub async fn run_range(&mut self, from: u64, to: u64) -> Result<ScanReport> {
+        let tip = with_timeout(self.rpc_timeout, "abci_info", self.client.abci_info()).await
+            .context("Failed to get current block height")?
+            .last_block_height
+            .value();
+        if from == 0 || from > to {
+            bail!("Invalid range {}..={}: need 1 <= from <= to", from, to);
+        }
+        if to > tip {
+            bail!("Range end {} is above the current tip {}", to, tip);
+        }
+
+        info!("Backfill for channel {}: blocks {}..={}", self.channel_id, from, to);
+
+        self.last_height = from - 1;
+        self.recent_hashes.clear();
+        // The window is unrelated to the tailing position, which must not move
+        self.state_path = None;
+        let workers = self.start_workers();
+        let mut report = self.scan_to(to).await?;
+
+        self.queues.clear();
+        for worker in workers {
+            report.failed += worker.await?.failed;
+        }
+
+        Ok(report)
+    }
+
+    /// What-if relay of packet `sequence` sent on the watched channel: finds its `send_packet`
+    /// tx (needs the source node's tx index), checks receipt and timeout on the destination,
+    /// fetches the commitment proof and simulates MsgRecvPacket on the destination.
+    /// Nothing is broadcast; the first problem found is reported in `SimResult::error`.
+    pub async fn simulate_relay(&self, sequence: u64) -> Result<SimResult> {
+        let query: Query = format!(
+            "send_packet.packet_src_channel='{}' AND send_packet.packet_sequence='{}'",
+            self.channel_id, sequence
+        )
+        .parse()?;
+        let res = with_timeout(
+            self.rpc_timeout,
+            "tx_search",
+            self.client.tx_search(query, false, 1, 1, Order::Ascending),
+        )
+        .await?;
+        let tx = res
+            .txs
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No send_packet tx for sequence {} on {}", sequence, self.channel_id))?;
+        let parsed = tx
+            .tx_result
+            .events
+            .iter()
+            .filter(|event| event.kind == "send_packet")
+            .filter_map(|event| try_parse_event(event, self.max_packet_bytes, self.strict_json).ok())
+            .find(|packet| packet.sequence == sequence && packet.src_channel == self.channel_id)
+            .ok_or_else(|| anyhow!("Packet {} not found in tx {}", sequence, tx.hash))?;
+
+        let mut result = SimResult {
+            sequence,
+            send_height: tx.height.value(),
+            ..Default::default()
+        };
+        let dst = self.dst_client.as_ref().ok_or_else(|| anyhow!("Destination RPC is unusable"))?;
+
+        let ordered = self.channel_info().is_some_and(|channel| channel.is_ordered());
+        result.already_received = packet_received(dst, &parsed, ordered).await.context("Receipt check failed")?;
+        if result.already_received {
+            result.error = Some("already received on the destination".to_string());
+            return Ok(result);
+        }
+
+        let status = with_timeout(self.rpc_timeout, "status", dst.status()).await?;
+        let nanos = status.sync_info.latest_block_time.unix_timestamp_nanos();
+        result.timed_out = parsed
+            .timeout_timestamp
+            .has_expired(UNIX_EPOCH + Duration::from_nanos(nanos.max(0) as u64));
+        if result.timed_out {
+            result.error = Some(format!("timed out at {}", parsed.timeout_timestamp));
+            return Ok(result);
+        }
+
+        let commitment = retry(&self.retry.policy(RetryStep::ProofQuery), RetryStep::ProofQuery, || {
+            query_commitment_proof(&self.client, &parsed.src_port, &parsed.src_channel, sequence, self.rpc_timeout)
+        })
+        .await?;
+        let Some((proof, proof_height)) = commitment else {
+            result.error = Some("no packet commitment on the source (acknowledged or timed out)".to_string());
+            return Ok(result);
+        };
+        result.proof_height = Some(proof_height);
+        result.proof_bytes = proof.len();
+
+        let msg = RecvPacketBuilder::new()
+            .packet(ibc_packet(&parsed)?)
+            .proof(proof)
+            // The proof verifies against the app hash committed in the next header
+            .proof_height(proof_height + 1)
+            .signer(self.dst_signer.clone())
+            .build()?;
+        let simulation = retry(&self.retry.policy(RetryStep::Simulate), RetryStep::Simulate, || {
+            simulate_recv_packet(dst, &msg, &self.fee_payment, self.rpc_timeout)
+        });
+        match simulation.await {
+            Ok((gas_wanted, gas_used)) => {
+                result.gas_wanted = Some(gas_wanted);
+                result.gas_used = Some(gas_used);
+                result.fee = self.gas_price.as_ref().map(|price| price.fee_for(gas_used).to_string());
+            }
+            Err(e) => result.error = Some(format!("{:#}", e)),
+        }
+        Ok(result)
+    }
+
+    /// Fetches the block meta (hash and header) at `height`
+    async fn block_meta(&self, height: u64) -> Result<block::Meta> {
+        let height = Height::try_from(height)
+            .context("Failed to convert height to tendermint::Height")?;
+        let res = with_timeout(self.rpc_timeout, "blockchain", self.client.blockchain(height, height)).await?;
+        res.block_metas
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No block meta returned for height {}", height))
+    }
+
+    /// Fetches the block hash at `height`
+    async fn block_hash(&self, height: u64) -> Result<Hash> {
+        Ok(self.block_meta(height).await?.block_id.hash)
+    }
+
+    /// Publishes the last processed height and how far its block time trails the wall clock
+    fn record_block_metrics(&self, block_time: Option<Time>) {
+        let labels = [("relay", self.relay_name.as_str()), ("channel", self.channel_id.as_str())];
+        metrics::set_gauge("relay_last_block_height", &labels, self.last_height as f64);
+
+        if let Some(time) = block_time {
+            let block_secs = time.unix_timestamp_nanos() as f64 / 1e9;
+            let now_secs = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |d| d.as_secs_f64());
+            metrics::set_gauge("relay_last_block_timestamp_seconds", &labels, block_secs);
+            metrics::set_gauge("relay_block_lag_seconds", &labels, (now_secs - block_secs).max(0.0));
+        }
+    }
+
+    /// Compares the recorded hash at `height` with the chain. On mismatch walks back
+    /// through the recorded hashes and returns the fork point (last height that still matches).
+    pub async fn detect_reorg(&self, height: u64) -> Result<Option<u64>> {
+        let Some(&(_, recorded)) = self.recent_hashes.iter().find(|(h, _)| *h == height) else {
+            return Ok(None);
+        };
+
+        if self.block_hash(height).await? == recorded {
+            return Ok(None);
+        }
+
+        for &(h, hash) in self.recent_hashes.iter().rev().skip_while(|(h, _)| *h >= height) {
+            if self.block_hash(h).await? == hash {
+                return Ok(Some(h));
+            }
+        }
+
+        // Fork is older than the recorded window – reprocess all of it
+        let oldest = self.recent_hashes.front().map_or(height, |(h, _)| *h);
+        Ok(Some(oldest.saturating_sub(1)))
+    }
+
+    /// Records the hash of a processed block, keeping the last `REORG_WINDOW` entries
+    fn record_block_hash(&mut self, height: u64, hash: Hash) {
+        if self.recent_hashes.len() == REORG_WINDOW {
+            self.recent_hashes.pop_front();
+        }
+        self.recent_hashes.push_back((height, hash));
+    }
+
+    /// Rewinds `last_height` to the fork point if the last processed block was reorged away
+    async fn check_reorg(&mut self) {
+        match self.detect_reorg(self.last_height).await {
+            Ok(Some(fork_point)) => {
+                error!(
+                    "REORG DETECTED on {} (channel {}): block {} changed, rewinding to fork point {}. \
+                     Packets relayed from orphaned blocks may be invalid!",
+                    self.src_chain, self.channel_id, self.last_height, fork_point
+                );
+                self.recent_hashes.retain(|(h, _)| *h <= fork_point);
+                self.last_height = fork_point;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Reorg check failed at height {}: {}", self.last_height, e),
+        }
+    }
+
+    /// Processes all blocks after `last_height` up to and including `target`. Unlike the poll
+    /// loop it does not keep retrying a block whose results stay unavailable: after
+    /// `BLOCK_RESULTS_MAX_RETRIES` attempts it fails, with `last_height` left before the block.
+    async fn scan_to(&mut self, target: u64) -> Result<ScanReport> {
+        let mut report = ScanReport::default();
+        self.check_reorg().await;
+
+        while self.last_height < target {
+            for job in self.scan_next(target, &mut report).await? {
+                if !send_job(&self.queues, job).await {
+                    report.failed += 1;
+                }
+            }
+            self.checkpoint();
+            if self.block_failures >= BLOCK_RESULTS_MAX_RETRIES {
+                bail!(
+                    "Block results for height {} unavailable after {} attempts",
+                    self.last_height + 1,
+                    self.block_failures
+                );
+            }
+            if self.shutdown_requested() {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Advances towards `tip` with the configured scan strategy and returns the packets found.
+    /// A failed `tx_search` falls back to block walking for this step; a node without a
+    /// tx index switches the poller to block walking for good.
+    async fn scan_next(&mut self, tip: u64, report: &mut ScanReport) -> Result<Vec<RelayJob>> {
+        if self.scan_strategy == ScanStrategy::TxSearch {
+            match self.scan_tx_range(tip, report).await {
+                Ok(jobs) => return Ok(jobs),
+                Err(e) if format!("{:#}", e).contains("indexing is disabled") => {
+                    warn!("[{}] Source RPC has tx indexing disabled, switching to block scanning", self.relay_name);
+                    self.scan_strategy = ScanStrategy::Blocks;
+                }
+                Err(e) => warn!(
+                    "[{}] tx_search after height {} failed, scanning the next block directly: {:#}",
+                    self.relay_name, self.last_height, e
+                ),
+            }
+        }
+        self.scan_next_block(report).await
+    }
+
+    /// Finds the channel's packet txs after `last_height` (up to `TX_SEARCH_MAX_BLOCKS`, at most
+    /// `tip`) through the tx index and processes their events in height order. IBC v2 packets
+    /// are addressed by client ID and are only found by block scanning.
+    async fn scan_tx_range(&mut self, tip: u64, report: &mut ScanReport) -> Result<Vec<RelayJob>> {
+        let from = self.last_height + 1;
+        let to = tip.min(self.last_height + TX_SEARCH_MAX_BLOCKS);
+
+        // A packet tx can match several queries; keyed by (height, index) to process each once, in order
+        let mut txs: BTreeMap<(u64, u32), Vec<Event>> = BTreeMap::new();
+        for kind in &self.watched_events {
+            for attribute in ["packet_src_channel", "packet_dst_channel"] {
+                let query: Query = format!(
+                    "{}.{}='{}' AND tx.height>={} AND tx.height<={}",
+                    kind, attribute, self.channel_id, from, to
+                )
+                .parse()?;
+
+                let mut page = 1;
+                loop {
+                    let res = with_timeout(
+                        self.rpc_timeout,
+                        "tx_search",
+                        self.client.tx_search(query.clone(), false, page, TX_SEARCH_PAGE_SIZE, Order::Ascending),
+                    )
+                    .await?;
+                    let fetched = res.txs.len();
+                    for tx in res.txs {
+                        txs.entry((tx.height.value(), tx.index)).or_insert(tx.tx_result.events);
+                    }
+                    if fetched == 0 || page as usize * TX_SEARCH_PAGE_SIZE as usize >= res.total_count as usize {
+                        break;
+                    }
+                    page += 1;
+                }
+            }
+        }
+
+        debug!("tx_search found {} packet txs in blocks {}..={}", txs.len(), from, to);
+
+        let mut jobs = Vec::new();
+        for ((height, _), events) in txs {
+            // Packets carry the height they were found at (proof height)
+            self.last_height = height;
+            for event in &events {
+                jobs.extend(self.handle_event(event, report));
+            }
+        }
+        self.last_height = to;
+
+        // Reorg detection and lag metrics only look at the end of the range
+        let block_time = match self.block_meta(to).await {
+            Ok(meta) => {
+                self.record_block_hash(to, meta.block_id.hash);
+                Some(meta.header.time)
+            }
+            Err(e) => {
+                debug!("Failed to get block hash for height {}: {}", to, e);
+                None
+            }
+        };
+        self.record_block_metrics(block_time);
+        if let Some(control) = &self.control {
+            control.set_height(self.last_height);
+        }
+        report.blocks += to - from + 1;
+
+        Ok(jobs)
+    }
+
+    /// Processes the block after `last_height` and returns the packets to relay from it.
+    /// If its `block_results` cannot be fetched, `last_height` stays put and nothing is
+    /// returned after a capped backoff, so the next call retries the same block – a block is
+    /// never skipped. After `BLOCK_RESULTS_MAX_RETRIES` failures the stall is logged as an
+    /// error and reported in the `relay_scan_stalled_height` gauge; the poll loop keeps
+    /// retrying, while `scan_to` gives up at that point.
+    async fn scan_next_block(&mut self, report: &mut ScanReport) -> Result<Vec<RelayJob>> {
+        let next = self.last_height + 1;
+        let height = Height::try_from(next).context("Failed to convert height to tendermint::Height")?;
+        let labels = [("relay", self.relay_name.as_str()), ("channel", self.channel_id.as_str())];
+
+        let results = match with_timeout(self.rpc_timeout, "block_results", self.client.block_results(height)).await {
+            Ok(results) => {
+                if self.block_failures >= BLOCK_RESULTS_MAX_RETRIES {
+                    info!("[{}] Block results for height {} available again, scan resumed", self.relay_name, next);
+                    metrics::set_gauge("relay_scan_stalled_height", &labels, 0.0);
+                }
+                self.block_failures = 0;
+                results
+            }
+            Err(e) => {
+                self.block_failures += 1;
+                let delay = BLOCK_RETRY_INITIAL_DELAY
+                    .saturating_mul(2u32.saturating_pow(self.block_failures - 1))
+                    .min(BLOCK_RETRY_MAX_DELAY);
+                metrics::inc_counter("relay_blocks_retried_total", &labels);
+                if self.block_failures == BLOCK_RESULTS_MAX_RETRIES {
+                    metrics::set_gauge("relay_scan_stalled_height", &labels, next as f64);
+                    error!(
+                        "[{}] Scan stalled: block results for height {} failed {} times: {}",
+                        self.relay_name, next, self.block_failures, e
+                    );
+                } else {
+                    warn!(
+                        "[{}] Failed to get block results for height {} (attempt {}), retrying in {} ms: {}",
+                        self.relay_name, next, self.block_failures, delay.as_millis(), e
+                    );
+                }
+                self.pause(delay).await;
+                return Ok(Vec::new());
+            }
+        };
+
+        self.last_height = next;
+        debug!("Processing block {}", self.last_height);
+
+        let block_time = match self.block_meta(self.last_height).await {
+            Ok(meta) => {
+                self.record_block_hash(self.last_height, meta.block_id.hash);
+                Some(meta.header.time)
+            }
+            Err(e) => {
+                debug!("Failed to get block hash for height {}: {}", self.last_height, e);
+                None
+            }
+        };
+
+        let mut jobs = Vec::new();
+        if let Some(txs_results) = results.txs_results {
+            for tx_res in txs_results {
+                for event in &tx_res.events {
+                    jobs.extend(self.handle_event(event, report));
+                }
+            }
+        }
+
+        self.record_block_metrics(block_time);
+        if let Some(control) = &self.control {
+            control.set_height(self.last_height);
+        }
+        report.blocks += 1;
+        sleep(self.block_step_delay).await;
+
+        Ok(jobs)
+    }
+
+    /// Parses an IBC v2 (Eureka) packet event. v2 packets are addressed by client IDs,
+    /// so `src_channel` in the config holds the client ID. Relaying is not supported yet –
+    /// the packet is only logged.
+    fn handle_v2_event(&self, event: &Event) {
+        let mut parsed = ParsedPacket {
+            channel_version: ChannelVersion::V2,
+            sequence: 0,
+            src_port: String::new(),
+            src_channel: String::new(),
+            dst_port: String::new(),
+            dst_channel: String::new(),
+            timeout_height: String::new(),
+            timeout_timestamp: TimeoutTimestamp::NONE,
+            data: FungibleTokenPacketData::default(),
+            payload: PacketPayload::Json,
+        };
+
+        for (key, value) in event.attributes.iter().map(decode_attr) {
+            match key.as_str() {
+                "packet_sequence" => parsed.sequence = value.parse().unwrap_or(0),
+                "packet_source_client" => parsed.src_channel = value,
+                "packet_dest_client" => parsed.dst_channel = value,
+                "packet_timeout_timestamp" => {
+                    parsed.timeout_timestamp = TimeoutTimestamp::from_nanos(value.parse().unwrap_or(0))
+                }
+                _ => {}
+            }
+        }
+
+        if parsed.src_channel != self.channel_id && parsed.dst_channel != self.channel_id {
+            return;
+        }
+
+        info!(
+            "[Block {}] IBC v2 PACKET DETECTED ({}): {} -> {} #{} (v2 relaying not supported yet)",
+            self.last_height, event.kind, parsed.src_channel, parsed.dst_channel, parsed.sequence
+        );
+        debug!("   Full packet structure: {:?}", parsed);
+    }
+
+    /// Parses a single tx event; returns a relay job if it belongs to the watched channel
+    fn handle_event(&mut self, event: &Event, report: &mut ScanReport) -> Option<RelayJob> {
+        let attributes: Vec<(String, String)> = event.attributes.iter().map(decode_attr).collect();
+        let is_v2 = attributes.iter().any(|(key, _)| key == "packet_source_client");
+        if is_v2 {
+            if self.channel_v2 {
+                self.handle_v2_event(event);
+            } else {
+                debug!("[Block {}] Ignoring IBC v2 {} event (enable_channel_v2 = false)", self.last_height, event.kind);
+            }
+            return None;
+        }
+
+        let is_relevant = self.watched_events.iter().any(|kind| *kind == event.kind)
+            && attributes.iter().any(|(key, value)| {
+                (key == "packet_src_channel" || key == "packet_dst_channel") && *value == self.channel_id
+            });
+
+        if !is_relevant {
+            return None;
+        }
+
+        info!("[Block {}] IBC PACKET DETECTED!", self.last_height);
+
+        for (key, value) in &attributes {
+            info!("   {} = {}", key, value);
+        }
+
+        if event.kind == "write_acknowledgement" {
+            if let Some(packet_ack_hex) = event_attr(event, "packet_ack_hex") {
+                let sequence = event_attr(event, "packet_sequence").unwrap_or_else(|| "?".to_string());
+                match hex::decode(packet_ack_hex) {
+                    Ok(ack) => match parse_acknowledgement(&ack) {
+                        AckOutcome::Success(_) => {
+                            info!("   Ack for sequence {}: receive succeeded", sequence);
+                        }
+                        AckOutcome::Error(reason) => {
+                            warn!("   Ack for sequence {}: receive FAILED ({}) – refund ack must be relayed to source", sequence, reason);
+                        }
+                        AckOutcome::Unknown => warn!("   Ack for sequence {}: unrecognized acknowledgement format", sequence),
+                    },
+                    Err(e) => warn!("Failed to decode ack hex: {}", e),
+                }
+            }
+        }
+
+        let parsed = match try_parse_event(event, self.max_packet_bytes, self.strict_json) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                warn!("[Block {}] Skipping {} event: {}", self.last_height, event.kind, e);
+                return None;
+            }
+        };
+
+        match &parsed.payload {
+            PacketPayload::Json => {
+                info!("   Packet parsing (human-readable, ICS-20 {:?}):", parsed.data.version);
+                info!("     Amount: {}", parsed.data.amount);
+                info!("     Denom: {}", parsed.data.denom);
+                info!("     Sender: {}", parsed.data.sender);
+                info!("     Receiver: {}", parsed.data.receiver);
+            }
+            PacketPayload::Raw(bytes) => {
+                info!("   Packet data is not JSON ({} bytes) – relaying it verbatim", bytes.len());
+            }
+        }
+        info!("   Full packet structure: {:?}", parsed);
+
+        if event.kind == "recv_packet" {
+            // Receive already happened on this chain – nothing to relay
+            info!("   Packet {} received on {}", parsed.sequence, self.src_chain);
+            return None;
+        }
+
+        if !self.packet_filter.matches(&parsed.data.sender, &parsed.data.receiver) {
+            debug!(
+                "[Block {}] Skipping packet {}: sender {} / receiver {} do not match the filters",
+                self.last_height, parsed.sequence, parsed.data.sender, parsed.data.receiver
+            );
+            return None;
+        }
+
+        if let Some(monitor) = &self.monitor {
+            // Monitor mode: never submit, only track the receive on the destination
+            if event.kind == "send_packet" {
+                report.packets += 1;
+                monitor.watch(&self.relay_name, &parsed);
+            }
+            return None;
+        }
+
+        let direction = if parsed.src_channel == self.channel_id {
+            Direction::SrcToDst
+        } else {
+            Direction::DstToSrc
+        };
+        let msg_type = if event.kind == "write_acknowledgement" { MsgType::Ack } else { MsgType::Recv };
+        let closed = self
+            .channel_info()
+            .filter(|channel| msg_type == MsgType::Recv && channel.is_closed())
+            .map(|channel| format!("{}/{}", channel.port_id, channel.channel_id));
+        if let Some(channel) = closed {
+            if !self.closed_warned {
+                warn!(
+                    "[{}] Channel {} is CLOSED – its packets can no longer be received, skipping recv relays",
+                    self.relay_name, channel
+                );
+                self.closed_warned = true;
+            }
+            debug!("[Block {}] Skipping packet {}: channel closed", self.last_height, parsed.sequence);
+            metrics::inc_counter("relay_closed_channel_skipped_total", &[("relay", &self.relay_name)]);
+            return None;
+        }
+        let mut job = RelayJob {
+            packet: parsed,
+            packet_data_hex: event_attr(event, "packet_data_hex").unwrap_or_default(),
+            direction,
+            msg_type,
+            height: self.last_height,
+            detected_at: SystemTime::now(),
+            ordered: self.channel_info().is_some_and(|channel| channel.is_ordered()),
+            check_receipt: false,
+        };
+        match self.tracker.begin(RelayRequest::new(&self.relay_name, &job, &[])) {
+            TrackStatus::New => {}
+            TrackStatus::Duplicate => {
+                debug!("[Block {}] Packet {} already relayed or in flight", self.last_height, job.packet.sequence);
+                return None;
+            }
+            TrackStatus::Unknown => {
+                debug!(
+                    "[Block {}] Packet {} is below the tracker's eviction mark, checking the destination first",
+                    self.last_height, job.packet.sequence
+                );
+                job.check_receipt = true;
+            }
+        }
+        if let Some(control) = &self.control {
+            control.packet_detected(&job);
+        }
+
+        report.packets += 1;
+        Some(job)
+    }
+}
+
+/// Queues of one destination's relay worker pool
+#[derive(Clone)]
+struct PoolQueues {
+    /// One per worker: packets of an ordered channel always go to the same worker so they
+    /// are submitted in sequence
+    pinned: Vec<mpsc::Sender<RelayJob>>,
+    /// Taken from by every worker: an unordered packet goes to whichever worker is free first
+    shared: async_channel::Sender<RelayJob>,
+}
+
+impl PoolQueues {
+    /// Queues `job` for the pool, waiting while it is full; false once its workers stopped
+    async fn send(&self, job: RelayJob) -> bool {
+        if job.ordered {
+            let mut hasher = DefaultHasher::new();
+            job.packet.src_channel.hash(&mut hasher);
+            let worker = &self.pinned[(hasher.finish() % self.pinned.len() as u64) as usize];
+            worker.send(job).await.is_ok()
+        } else {
+            self.shared.send(job).await.is_ok()
+        }
+    }
+}
+
+/// Hands a job to the relay worker pool of every destination, waiting while it is behind
+/// (backpressure on block scanning). Returns false if the primary workers have stopped;
+/// stopped workers of an extra destination only miss the packet.
+async fn send_job(queues: &[PoolQueues], job: RelayJob) -> bool {
+    let Some((primary, extra)) = queues.split_first() else {
+        warn!("No relay worker running, dropping packet");
+        return true;
+    };
+    for pool in extra {
+        if !pool.send(job.clone()).await {
+            warn!("Relay worker of an extra destination stopped, packet {} not relayed there", job.packet.sequence);
+        }
+    }
+    if !primary.send(job).await {
+        error!("Relay worker stopped, dropping packet");
+        return false;
+    }
+    true
+}
@@ -1,8 +1,29 @@
+pub mod admin;
+pub mod alert;
+pub mod cert;
+pub mod channel;
+pub mod commitment;
 pub mod config;
 pub mod ibc;
+pub mod metrics;
 pub mod transport;
+pub mod tcp;
 pub mod relay;
+pub mod rpc;
 pub mod crypto;
+pub mod denom;
+pub mod proof;
+pub mod retry;
+pub mod error;
+pub mod signer;
+pub mod simulate;
+pub mod sink;
+pub mod state;
+pub mod submit;
+
+// Mock source/destination chains for end-to-end tests
+#[cfg(feature = "testkit")]
+pub mod testkit;
 
 // ZK module – included only when the encryption-proof feature is enabled
 #[cfg(feature = "encryption-proof")]
@@ -10,13 +31,41 @@ pub mod zk;
 
 pub use config::Config;
 pub use ibc::IbcPoller;
+pub use error::{Cancelled, RelayError, TxError};
+pub use relay::{PacketCallback, RelayOutcome};
 
 // Export ZK proof generation only when the feature is enabled
 #[cfg(feature = "encryption-proof")]
-pub use zk::generate_packet_proof;
+pub use zk::{export_verifying_key, generate_packet_proof};
 
 // Stub when feature is disabled (allows code using generate_packet_proof to compile)
 #[cfg(not(feature = "encryption-proof"))]
 pub fn generate_packet_proof(_packet_data_hex: &str) -> anyhow::Result<Vec<u8>> {
     Ok(vec![]) // empty proof – just for compilation
-}
\ No newline at end of file
+}
+
+#[cfg(not(feature = "encryption-proof"))]
+pub fn export_verifying_key(_path: &str, _scheme: config::CommitmentScheme, _k: u32) -> anyhow::Result<()> {
+    anyhow::bail!("Verifying key export requires the encryption-proof feature")
+}
+
+/// Version and cargo features this binary was built with
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// Enabled optional features, e.g. `encryption-proof`
+    pub features: Vec<&'static str>,
+}
+
+/// Version and enabled features of this build
+pub fn build_info() -> BuildInfo {
+    let features = [
+        ("encryption-proof", cfg!(feature = "encryption-proof")),
+        ("metrics", cfg!(feature = "metrics")),
+        ("testkit", cfg!(feature = "testkit")),
+    ];
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        features: features.into_iter().filter(|(_, enabled)| *enabled).map(|(name, _)| name).collect(),
+    }
+}
@@ -1,78 +1,911 @@
-use aero_relay::{config::Config, ibc::IbcPoller, transport};
-use anyhow::Result;
-use std::time::Duration;
-use tracing::{error, info};
+use aero_relay::{admin::{self, RelayControl, RelayControls}, alert::{self, Notifier}, cert::ServerCert, channel::ChannelCache, commitment::{self, CommittedPacket}, config::{AlertsConfig, CertVerifierMode, CommitmentScheme, Config, RelayPair, RetryConfig, StartFrom, StateConfig, TransportConfig, TransportProtocol, ZkConfig, DEFAULT_ZK_K}, ibc::{self, IbcPoller, TimeoutTimestamp}, metrics, proof, relay::{Direction, MsgType, RelayRequest, RelayResponse, RelayRouter}, rpc::{with_timeout, RpcClient}, signer::{Signer, SignerPool}, sink::{self, EventSink}, transport::{self, PeerConnection}};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
+use tendermint_rpc::Client;
+use tracing::{error, info, warn};
+use tracing_appender::non_blocking::WorkerGuard;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter, layer::SubscriberExt};
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize crypto provider for QUIC (aws-lc-rs)
-    rustls::crypto::aws_lc_rs::default_provider()
-        .install_default()
-        .expect("Failed to install crypto provider");
+/// How long pollers get to reach a block boundary and save their state on Ctrl+C
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(15);
+
+/// Relay name the selftest registers on its loopback server
+const SELFTEST_RELAY: &str = "selftest";
+
+/// Peer token of the selftest's loopback server
+const SELFTEST_PEER_TOKEN: &str = "selftest";
+
+/// Largest response accepted by `inject`
+const INJECT_MAX_RESPONSE: usize = 1024 * 1024;
+
+/// Experimental ZK-focused IBC relayer
+#[derive(Parser, Debug)]
+#[command(name = "aero-relay", version, about)]
+struct Cli {
+    /// Path to the configuration file
+    #[arg(long, default_value = "config.toml")]
+    config: String,
+
+    /// Load and merge every *.toml file in this directory instead of `--config`
+    #[arg(long, value_name = "DIR")]
+    config_dir: Option<String>,
+
+    /// Only run the named relay from the config (repeatable; default: all relays)
+    #[arg(long = "relay", value_name = "NAME")]
+    relays: Vec<String>,
+
+    /// Relay everything outstanding since the saved state up to the current tip, then exit (for cron)
+    #[arg(long)]
+    once: bool,
+
+    /// Read-only mode: detect packets and track their receipt on the destination, never submit
+    #[arg(long, conflicts_with = "once")]
+    monitor: bool,
+
+    /// Backfill: relay every packet from this height up to `--to`, then exit
+    #[arg(long, value_name = "HEIGHT", requires = "to", conflicts_with_all = ["once", "monitor"])]
+    from: Option<u64>,
+
+    /// Last height (inclusive) of the `--from` backfill; must not be above the tip
+    #[arg(long, value_name = "HEIGHT", requires = "from")]
+    to: Option<u64>,
+
+    /// Allow `[transport] verifier = "insecure"` (no peer certificate verification; development only).
+    /// Same as AERO_INSECURE=1
+    #[arg(long, global = true)]
+    insecure_skip_verify: bool,
+
+    /// Tokio worker threads; overrides `[runtime] worker_threads`
+    #[arg(long)]
+    worker_threads: Option<usize>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write a commented example config.toml ("-" prints it to stdout)
+    GenConfig {
+        /// Output path
+        #[arg(long, default_value = "config.toml")]
+        output: String,
+        /// Overwrite the output file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Export the ZK verifying key for on-chain verification (encryption-proof feature)
+    ExportVk {
+        /// Output path
+        #[arg(long, default_value = "aero-relay.vk")]
+        output: String,
+    },
+    /// Send a RelayRequest JSON file to a running relayer's peer server and print the response
+    Inject {
+        /// Path to the RelayRequest JSON file
+        file: String,
+        /// Peer server address
+        #[arg(long, default_value = "127.0.0.1:4433")]
+        addr: String,
+        /// Connect over TCP+TLS instead of QUIC
+        #[arg(long)]
+        tcp: bool,
+        /// `[transport] peer_token` of the receiving node
+        #[arg(long, value_name = "TOKEN")]
+        peer_token: Option<String>,
+    },
+    /// Check the crypto provider, certificate generation and QUIC transport over loopback
+    /// (plus a test proof with encryption-proof); exits non-zero on any failure
+    Selftest,
+    /// Print version and enabled features, and check every configured RPC (exits non-zero if any is unreachable)
+    Status,
+    /// What-if relay of one packet (select the relay with --relay): proof query, destination
+    /// checks and tx simulation, without broadcasting. Exits non-zero if the relay would fail
+    Simulate {
+        /// Packet sequence on the relay's source channel
+        sequence: u64,
+    },
+    /// Verify a packet commitment proof (ICS-23) offline against an app hash; exits non-zero
+    /// with the failing step if it does not verify
+    VerifyProof {
+        /// Path to the packet as RelayRequest JSON
+        packet: String,
+        /// Hex-encoded MerkleProof of the packet commitment
+        #[arg(long)]
+        proof: String,
+        /// Source height the proof was queried at
+        #[arg(long)]
+        proof_height: u64,
+        /// Hex app hash at that height (from the header of the next block)
+        #[arg(long)]
+        app_hash: String,
+    },
+    /// Decode a packet_data_hex (or base64) value and print it as JSON
+    DecodePacket {
+        /// Packet data as hex or base64
+        data: String,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    // Utility subcommands run without logging or the relayer runtime
+    match &cli.command {
+        Some(Command::GenConfig { output, force }) => {
+            if output == "-" {
+                print!("{}", aero_relay::config::SAMPLE_CONFIG);
+            } else {
+                Config::write_sample(output, *force)?;
+                println!("Sample config written to {}", output);
+            }
+            return Ok(());
+        }
+        Some(Command::DecodePacket { data }) => return decode_packet(data),
+        Some(Command::VerifyProof { packet, proof, proof_height, app_hash }) => {
+            return verify_proof(packet, proof, *proof_height, app_hash);
+        }
+        Some(Command::Selftest) => return tokio::runtime::Runtime::new()?.block_on(selftest()),
+        Some(Command::Status) => {
+            let mut config = load_config(&cli)?;
+            config.retain_relays(&cli.relays)?;
+            return tokio::runtime::Runtime::new()?.block_on(status(&config));
+        }
+        Some(Command::Simulate { sequence }) => {
+            let mut config = load_config(&cli)?;
+            config.retain_relays(&cli.relays)?;
+            return tokio::runtime::Runtime::new()?.block_on(simulate(&config, *sequence));
+        }
+        Some(Command::Inject { file, addr, tcp, peer_token }) => {
+            return tokio::runtime::Runtime::new()?.block_on(inject(
+                file,
+                addr,
+                *tcp,
+                peer_token.as_deref(),
+                cli.insecure_skip_verify,
+            ));
+        }
+        Some(Command::ExportVk { output }) => {
+            // Use the configured scheme and circuit size when a config file is present
+            let (scheme, k) = if cli.config_dir.is_some() || std::path::Path::new(&cli.config).exists() {
+                let zk = load_config(&cli)?.zk;
+                (zk.scheme, zk.k)
+            } else {
+                (CommitmentScheme::Kzg, DEFAULT_ZK_K)
+            };
+            aero_relay::export_verifying_key(output, scheme, k)?;
+            println!("Verifying key written to {}", output);
+            return Ok(());
+        }
+        None => {}
+    }
 
-    // Log directory (relative to project root)
-    let log_path = "logs";
+    let mut config = load_config(&cli)?;
+    config.retain_relays(&cli.relays)?;
 
-    // Create logs directory if it doesn't exist
-    if let Err(e) = std::fs::create_dir_all(log_path) {
-        eprintln!("Failed to create logs directory: {}. Logs will go to console only.", e);
+    if config.transport.enabled {
+        // Initialize crypto provider for the peer transport (aws-lc-rs)
+        rustls::crypto::aws_lc_rs::default_provider()
+            .install_default()
+            .expect("Failed to install crypto provider");
+    }
+
+    let _log_guard = init_logging(&config.logging.dir);
+
+    info!("AeroRelay starting... ✈️");
+
+    if config.transport.verifier == CertVerifierMode::Insecure {
+        if !transport::insecure_allowed(&config.transport) {
+            bail!(
+                "[transport] verifier = \"insecure\" requires --insecure-skip-verify or {}=1",
+                transport::INSECURE_ENV
+            );
+        }
+        warn!("⚠️  INSECURE MODE: peer TLS certificates are NOT verified – any host can impersonate a peer. Never use this in production.");
     }
 
-    // Daily rotating file appender
-    let file_appender = RollingFileAppender::new(Rotation::DAILY, log_path, "aero-relay.log");
-    let (non_blocking_file, _guard) = tracing_appender::non_blocking(file_appender);
+    let build = aero_relay::build_info();
+    info!(
+        "aero-relay {} (features: {})",
+        build.version,
+        if build.features.is_empty() { "none".to_string() } else { build.features.join(", ") }
+    );
+    proof::preflight(&config.zk)?;
+
+    let runtime = build_runtime(&config, cli.worker_threads)?;
+    runtime.block_on(run(cli, config))
+}
+
+/// Console logging, plus a daily rotating file in `log_dir` when that directory is writable.
+/// The returned guard flushes the file writer and must live until shutdown.
+fn init_logging(log_dir: &str) -> Option<WorkerGuard> {
+    let file_writer = match open_log_file(log_dir) {
+        Ok(appender) => Some(tracing_appender::non_blocking(appender)),
+        Err(e) => {
+            eprintln!("File logging disabled ({}). Logs will go to console only.", e);
+            None
+        }
+    };
+    let (file_layer, guard) = match file_writer {
+        Some((writer, guard)) => (Some(fmt::layer().with_writer(writer)), Some(guard)),
+        None => (None, None),
+    };
 
-    // Initialize logging
     tracing_subscriber::registry()
         .with(fmt::layer()) // Pretty console output
-        .with(fmt::layer().with_writer(non_blocking_file)) // File output
+        .with(file_layer) // File output, if available
         .with(
             EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| EnvFilter::new("info")), // Default to info if RUST_LOG unset
         )
         .init();
 
-    info!("AeroRelay starting... ✈️");
+    guard
+}
+
+/// Creates `log_dir` if needed and confirms it is writable (read-only root filesystems)
+fn open_log_file(log_dir: &str) -> Result<RollingFileAppender> {
+    std::fs::create_dir_all(log_dir)
+        .with_context(|| format!("cannot create log directory {}", log_dir))?;
 
-    let config = Config::load("config.toml")?;
+    let probe = std::path::Path::new(log_dir).join(".aero-relay-write-test");
+    std::fs::write(&probe, b"")
+        .with_context(|| format!("log directory {} is not writable", log_dir))?;
+    let _ = std::fs::remove_file(&probe);
 
-    // Start QUIC server once (in background)
-    tokio::spawn(async move {
-        info!("QUIC Server listening on 0.0.0.0:4433");
-        if let Err(e) = transport::start_server("0.0.0.0:4433").await {
-            error!("QUIC Server error: {}", e);
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("aero-relay.log")
+        .build(log_dir)
+        .with_context(|| format!("cannot open log file in {}", log_dir))
+}
+
+/// Loads `--config-dir` when given, else the `--config` file
+fn load_config(cli: &Cli) -> Result<Config> {
+    let mut config = match &cli.config_dir {
+        Some(dir) => Config::load_dir(dir)?,
+        None => Config::load(&cli.config)?,
+    };
+    config.transport.allow_insecure = cli.insecure_skip_verify;
+    Ok(config)
+}
+
+/// Builds the multi-threaded tokio runtime sized from `[runtime]` (CLI flag wins)
+fn build_runtime(config: &Config, worker_threads: Option<usize>) -> Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+
+    if let Some(threads) = worker_threads.or(config.runtime.worker_threads) {
+        if threads == 0 {
+            bail!("--worker-threads must be at least 1");
         }
-    });
+        info!("Tokio worker threads: {}", threads);
+        builder.worker_threads(threads);
+    }
+    if let Some(threads) = config.runtime.max_blocking_threads {
+        info!("Tokio blocking threads: {}", threads);
+        builder.max_blocking_threads(threads);
+    }
 
-    // Give server time to start
-    tokio::time::sleep(Duration::from_millis(500)).await;
+    Ok(builder.build()?)
+}
 
+async fn run(cli: Cli, config: Config) -> Result<()> {
+    if cli.once {
+        return run_once(config, None).await;
+    }
+    if let (Some(from), Some(to)) = (cli.from, cli.to) {
+        return run_once(config, Some((from, to))).await;
+    }
+
+    if let Some(listen) = config.metrics.listen.clone() {
+        let max_lag = config.metrics.ready_max_lag();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(&listen, max_lag).await {
+                error!("Metrics server error: {}", e);
+            }
+        });
+    }
+
+    // Packets forwarded by peer nodes are routed to the matching local relay worker
+    let router = Arc::new(RelayRouter::new());
+
+    if config.transport.enabled {
+        // Start the peer server once (in background)
+        let transport_config = config.transport.clone();
+        let listen = config.quic_listen().to_string();
+        let server_router = router.clone();
+        if transport_config.peer_token.is_none() {
+            warn!("No [transport] peer_token set – the peer server rejects all forwarded packets");
+        }
+        tokio::spawn(async move {
+            match transport_config.protocol {
+                TransportProtocol::Quic => info!("QUIC Server listening on {} ({:?} congestion control)", listen, transport_config.congestion),
+                TransportProtocol::Tcp => info!("TCP+TLS Server listening on {}", listen),
+            }
+            if let Err(e) = transport::serve(&listen, &transport_config, Some(server_router)).await {
+                error!("Peer server error: {}", e);
+            }
+        });
+
+        // Give server time to start
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    } else {
+        info!("Peer transport disabled – not accepting packets from peers");
+    }
+
+    // Shared with the admin API, which reads status and sends pause/scan requests
+    let controls: Arc<RelayControls> = Arc::new(
+        config
+            .relays
+            .iter()
+            .map(|relay| (relay.name.clone(), Arc::new(RelayControl::new(&relay.name, &relay.src_channel))))
+            .collect(),
+    );
+    if let (Some(listen), Some(token)) = (config.admin.listen.clone(), config.admin.token.clone()) {
+        let controls = controls.clone();
+        tokio::spawn(async move {
+            if let Err(e) = admin::serve(&listen, token, controls).await {
+                error!("Admin API error: {}", e);
+            }
+        });
+    }
+
+    let notifier = alert::from_config(&config.alerts);
+    if notifier.is_some() {
+        info!("Alert webhook enabled");
+    }
+    let context = RelayContext {
+        notifier,
+        event_sink: sink::from_config(&config.events),
+        alerts: config.alerts.clone(),
+        zk: config.zk.clone(),
+        retry: config.retry,
+        transport: config.transport.clone(),
+        state: config.state.clone(),
+        router,
+        monitor: cli.monitor,
+    };
+
+    let mut running = HashMap::new();
     for relay in config.relays {
         info!("Setting up relay: {}", relay.name);
+        let control = controls[&relay.name].clone();
+        running.insert(relay.name.clone(), spawn_relay(relay, &context, control)?);
+    }
+
+    // Relays added, changed or removed in the config file are applied without a restart
+    let mut reloads = match &cli.config_dir {
+        Some(_) => None,
+        None => match Config::watch(&cli.config) {
+            Ok(reloads) => {
+                info!("Watching {} for relay changes", cli.config);
+                Some(reloads)
+            }
+            Err(e) => {
+                warn!("Config hot reload disabled: {:#}", e);
+                None
+            }
+        },
+    };
+
+    info!("AeroRelay fully started. Press Ctrl+C to stop.");
+
+    loop {
+        let reload = async {
+            match &mut reloads {
+                Some(reloads) => reloads.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => {
+                result?;
+                break;
+            }
+            Some(mut config) = reload => {
+                config.transport.allow_insecure = cli.insecure_skip_verify;
+                if let Err(e) = config.retain_relays(&cli.relays) {
+                    error!("Reloaded config not applied: {:#}", e);
+                    continue;
+                }
+                apply_relay_changes(&mut running, config.relays, &context, &controls).await;
+            }
+        }
+    }
+    info!("Shutting down...");
+
+    let stopping: Vec<_> = running.into_values().map(RunningRelay::stop).collect();
+    if tokio::time::timeout(SHUTDOWN_GRACE, futures::future::join_all(stopping)).await.is_err() {
+        warn!("Pollers did not stop within {} s; state may not be saved", SHUTDOWN_GRACE.as_secs());
+    }
+
+    Ok(())
+}
+
+/// Shared settings every relay's poller is built with
+struct RelayContext {
+    notifier: Option<Arc<dyn Notifier>>,
+    event_sink: Option<Arc<dyn EventSink>>,
+    alerts: AlertsConfig,
+    zk: ZkConfig,
+    retry: RetryConfig,
+    transport: TransportConfig,
+    state: StateConfig,
+    router: Arc<RelayRouter>,
+    monitor: bool,
+}
+
+/// A relay whose poller is running
+struct RunningRelay {
+    relay: RelayPair,
+    /// Stops the poller at the next block boundary; it saves its state
+    shutdown: watch::Sender<bool>,
+    /// Abandons relays in progress instead of holding up shutdown
+    cancel: CancellationToken,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl RunningRelay {
+    /// Signals the poller to stop; the future resolves once it has
+    fn stop(self) -> tokio::task::JoinHandle<()> {
+        let _ = self.shutdown.send(true);
+        self.cancel.cancel();
+        self.task
+    }
+}
+
+/// Starts the poller of `relay`
+fn spawn_relay(relay: RelayPair, context: &RelayContext, control: Arc<RelayControl>) -> Result<RunningRelay> {
+    let (shutdown_tx, shutdown) = watch::channel(false);
+    let cancel = CancellationToken::new();
 
-        let src_rpc = relay.src_rpc.clone();
-        let src_channel = relay.src_channel.clone();
+    let notifier = context.notifier.clone();
+    let event_sink = context.event_sink.clone();
+    let alerts = context.alerts.clone();
+    let zk = context.zk.clone();
+    let retry = context.retry;
+    let transport_config = context.transport.clone();
+    let router = context.router.clone();
+    let monitor = context.monitor;
+    let state_path = context.state.path_for(&relay.name);
+    let start_from = context.state.start_from;
+    let poller_cancel = cancel.clone();
 
-        // Spawn poller for each relay
+    let signer_pool = if !relay.dst_signers.is_empty() {
+        info!("[{}] Using {} destination signers", relay.name, relay.dst_signers.len());
+        Some(Arc::new(SignerPool::from_addresses(&relay.dst_signers)?))
+    } else if let Some(signer) = relay.dst_key_signer()? {
+        info!("[{}] Using {:?} key of {}", relay.name, signer.key().key_type(), signer.address());
+        Some(Arc::new(SignerPool::new(vec![Arc::new(signer) as Arc<dyn Signer>])?))
+    } else {
+        None
+    };
+
+    let task = {
+        let relay = relay.clone();
         tokio::spawn(async move {
-            match IbcPoller::new(&src_rpc, &src_channel).await {
-                Ok(mut poller) => {
-                    if let Err(e) = poller.poll().await {
-                        error!("Polling error [{}]: {}", src_channel, e);
+            match IbcPoller::new(&relay).await {
+                Ok(poller) => {
+                    let poller = poller
+                        .with_zk_config(&zk)
+                        .with_retry(&retry)
+                        .with_router(router)
+                        .with_shutdown(shutdown)
+                        .with_cancel(poller_cancel)
+                        .with_control(control);
+                    let poller = match state_path {
+                        Some(path) => match poller.with_state_file(path) {
+                            Ok(poller) => poller,
+                            Err(e) => {
+                                error!("Failed to restore state [{}]: {:#}", relay.src_channel, e);
+                                return;
+                            }
+                        },
+                        None => poller,
+                    };
+                    let poller = match start_from {
+                        StartFrom::Genesis => match poller.with_genesis_start().await {
+                            Ok(poller) => poller,
+                            Err(e) => {
+                                error!("Failed to find the earliest block [{}]: {:#}", relay.src_channel, e);
+                                return;
+                            }
+                        },
+                        StartFrom::Saved => poller,
+                    };
+                    let poller = match &relay.forward_to {
+                        Some(peer) => {
+                            info!("[{}] Forwarding packets to peer {}", relay.name, peer);
+                            poller.with_forward_peer(peer, &transport_config)
+                        }
+                        None => poller,
+                    };
+                    let poller = match signer_pool {
+                        Some(pool) => {
+                            if let Err(e) = sync_signer_sequences(&pool, &relay.dst_rpc).await {
+                                warn!("Failed to sync signer sequences [{}]: {}", relay.src_channel, e);
+                            }
+                            poller.with_signer_pool(pool)
+                        }
+                        None => poller,
+                    };
+                    let poller = match notifier {
+                        Some(notifier) => poller.with_notifier(notifier, &alerts),
+                        None => poller,
+                    };
+                    let poller = match event_sink {
+                        Some(sink) => poller.with_event_sink(sink),
+                        None => poller,
+                    };
+                    let mut poller = if monitor {
+                        match poller.with_monitor(&relay) {
+                            Ok(poller) => poller,
+                            Err(e) => {
+                                error!("Failed to enable monitor mode [{}]: {}", relay.src_channel, e);
+                                return;
+                            }
+                        }
+                    } else {
+                        poller
+                    };
+                    if let Err(e) = poller.subscribe().await {
+                        error!("Polling error [{}]: {}", relay.src_channel, e);
                     }
                 }
-                Err(e) => error!("Failed to initialize poller [{}]: {}", src_channel, e),
+                Err(e) => error!("Failed to initialize poller [{}]: {}", relay.src_channel, e),
             }
-        });
+        })
+    };
+
+    Ok(RunningRelay { relay, shutdown: shutdown_tx, cancel, task })
+}
+
+/// Brings the running pollers in line with a reloaded relay list: removed relays are stopped,
+/// new ones started and changed ones restarted. Unchanged relays keep running untouched.
+/// Only `[[relays]]` is applied; other sections take effect on restart.
+async fn apply_relay_changes(
+    running: &mut HashMap<String, RunningRelay>,
+    relays: Vec<RelayPair>,
+    context: &RelayContext,
+    controls: &RelayControls,
+) {
+    let names: HashSet<String> = relays.iter().map(|relay| relay.name.clone()).collect();
+    let mut stopping = Vec::new();
+    for name in running.keys().filter(|name| !names.contains(*name)).cloned().collect::<Vec<_>>() {
+        info!("[{}] Relay removed from the config, stopping it", name);
+        if let Some(relay) = running.remove(&name) {
+            stopping.push(relay.stop());
+        }
     }
 
-    info!("AeroRelay fully started. Press Ctrl+C to stop.");
+    for relay in relays {
+        if let Some(current) = running.get(&relay.name) {
+            if current.relay == relay {
+                continue;
+            }
+            info!("[{}] Relay config changed, restarting it", relay.name);
+            if let Some(current) = running.remove(&relay.name) {
+                // Wait for the old poller to save its state before the new one loads it
+                if tokio::time::timeout(SHUTDOWN_GRACE, current.stop()).await.is_err() {
+                    warn!("[{}] Previous poller did not stop within {} s", relay.name, SHUTDOWN_GRACE.as_secs());
+                }
+            }
+        } else {
+            info!("[{}] Relay added to the config, starting it", relay.name);
+        }
 
-    // Wait for shutdown signal
-    tokio::signal::ctrl_c().await?;
-    info!("Shutting down...");
+        // Relays added after startup are not listed by the admin API until the next restart
+        let control = controls
+            .get(&relay.name)
+            .cloned()
+            .unwrap_or_else(|| Arc::new(RelayControl::new(&relay.name, &relay.src_channel)));
+        let name = relay.name.clone();
+        match spawn_relay(relay, context, control) {
+            Ok(started) => {
+                running.insert(name, started);
+            }
+            Err(e) => error!("[{}] Failed to start relay: {:#}", name, e),
+        }
+    }
+
+    if tokio::time::timeout(SHUTDOWN_GRACE, futures::future::join_all(stopping)).await.is_err() {
+        warn!("Removed relays did not stop within {} s; state may not be saved", SHUTDOWN_GRACE.as_secs());
+    }
+}
+
+/// Checks a commitment proof of the packet in a `RelayRequest` file against an app hash
+fn verify_proof(packet: &str, proof: &str, proof_height: u64, app_hash: &str) -> Result<()> {
+    let content = std::fs::read(packet).context(format!("Failed to read {}", packet))?;
+    let request: RelayRequest = serde_json::from_slice(&content).context("Invalid RelayRequest JSON")?;
+    let packet = CommittedPacket::try_from(&request)?;
+    let proof = hex::decode(proof.trim()).context("Proof is not hex")?;
+    let app_hash = hex::decode(app_hash.trim()).context("App hash is not hex")?;
+
+    commitment::verify_packet_proof(&packet, &proof, &app_hash)
+        .context(format!("Proof of packet {} at height {} does not verify", packet.sequence, proof_height))?;
+    println!(
+        "Proof OK: packet {} ({}) is committed at height {}",
+        packet.sequence,
+        packet.commitment_path(),
+        proof_height
+    );
+    Ok(())
+}
+
+/// Runs the poller's decode/parse path on a single packet and prints the result
+fn decode_packet(data: &str) -> Result<()> {
+    let bytes = ibc::decode_packet_data(data)?;
+    let v = ibc::parse_packet_json(&bytes)?;
+
+    let output = if ibc::is_transfer_packet(&v) {
+        let parsed = ibc::parse_transfer_data(&v)?;
+        serde_json::json!({
+            "version": format!("{:?}", parsed.version),
+            "amount": parsed.amount,
+            "denom": parsed.denom,
+            "sender": parsed.sender,
+            "receiver": parsed.receiver,
+        })
+    } else {
+        // Not an ICS-20 transfer – show the raw application payload
+        serde_json::json!({ "raw": v })
+    };
+
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// Sends a crafted packet to the peer server, exercising the transport end to end.
+/// Peers with the default self-signed certificate need `insecure`.
+async fn inject(file: &str, addr: &str, tcp: bool, peer_token: Option<&str>, insecure: bool) -> Result<()> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read packet file {}", file))?;
+    let mut packet: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("{} is not valid JSON", file))?;
+    if let (Some(token), Some(fields)) = (peer_token, packet.as_object_mut()) {
+        fields.insert("peer_token".to_string(), token.into());
+    }
+    let payload = serde_json::to_vec(&packet)?;
+
+    let transport_config = TransportConfig {
+        protocol: if tcp { TransportProtocol::Tcp } else { TransportProtocol::Quic },
+        verifier: if insecure { CertVerifierMode::Insecure } else { CertVerifierMode::WebPki },
+        allow_insecure: insecure,
+        ..TransportConfig::default()
+    };
+    let conn = transport::connect(addr, &transport_config).await?;
+    let response = conn.request(&payload, INJECT_MAX_RESPONSE).await?;
+    conn.close().await;
+
+    println!("{}", String::from_utf8_lossy(&response));
+    Ok(())
+}
+
+/// Loopback round trip: a RelayRequest sent to a local QUIC server must arrive on the
+/// registered relay queue unchanged
+async fn selftest() -> Result<()> {
+    rustls::crypto::aws_lc_rs::default_provider()
+        .install_default()
+        .map_err(|_| anyhow!("Failed to install the aws-lc-rs crypto provider"))?;
+    println!("crypto provider: OK");
+
+    // Loopback to our own self-signed certificate: skipping verification is the point here
+    let transport_config = TransportConfig {
+        verifier: CertVerifierMode::Insecure,
+        allow_insecure: true,
+        peer_token: Some(SELFTEST_PEER_TOKEN.to_string()),
+        ..TransportConfig::default()
+    };
+    let cert = Arc::new(ServerCert::self_signed(transport_config.cert_validity_days)?);
+    let endpoint = transport::bind_server("127.0.0.1:0", &transport_config, cert.clone())?;
+    let addr = endpoint.local_addr()?.to_string();
+    println!("self-signed certificate (valid until {}) and QUIC server on {}: OK", cert.cert_expiry(), addr);
+
+    let router = Arc::new(RelayRouter::new());
+    let (queue, mut jobs) = mpsc::channel(1);
+    router.register(SELFTEST_RELAY, queue);
+    tokio::spawn(transport::serve_endpoint(endpoint, None, Some(router), transport_config.peer_token.clone()));
+
+    let packet_data = r#"{"amount":"1","denom":"uatom","receiver":"cosmos1receiver","sender":"cosmos1sender"}"#;
+    let request = RelayRequest {
+        relay: SELFTEST_RELAY.to_string(),
+        sequence: 42,
+        src_port: "transfer".to_string(),
+        src_channel: "channel-0".to_string(),
+        dst_port: "transfer".to_string(),
+        dst_channel: "channel-1".to_string(),
+        timeout_height: String::new(),
+        timeout_timestamp: TimeoutTimestamp::NONE,
+        packet_data_hex: hex::encode(packet_data),
+        height: 1,
+        proof_hex: String::new(),
+        detected_at_ms: 0,
+        ordered: false,
+        direction: Direction::SrcToDst,
+        msg_type: MsgType::Recv,
+        idempotency_key: String::new(),
+        peer_token: String::new(),
+    };
+
+    let started = Instant::now();
+    let conn = transport::establish_connection(&addr, &transport_config).await?;
+    let connected = started.elapsed();
+    let response = transport::forward_to_peer(&conn, &request, transport_config.peer_token.as_deref()).await?;
+    let round_trip = started.elapsed() - connected;
+    conn.close(0u32.into(), b"done");
+
+    if let RelayResponse::Rejected { reason } = response {
+        bail!("QUIC loopback: request rejected: {}", reason);
+    }
+    let job = tokio::time::timeout(Duration::from_secs(5), jobs.recv())
+        .await
+        .context("QUIC loopback: request was never routed")?
+        .ok_or_else(|| anyhow!("QUIC loopback: relay queue closed"))?;
+    if job.packet.sequence != request.sequence || job.packet_data_hex != request.packet_data_hex {
+        bail!("QUIC loopback: routed packet does not match the one sent");
+    }
+    println!(
+        "QUIC loopback: OK (handshake {} ms, round trip {} ms)",
+        connected.as_millis(),
+        round_trip.as_millis()
+    );
+
+    #[cfg(feature = "encryption-proof")]
+    {
+        let started = Instant::now();
+        aero_relay::generate_packet_proof(&request.packet_data_hex)?;
+        println!("ZK proof generation: OK ({:.1} s)", started.elapsed().as_secs_f64());
+    }
+
+    Ok(())
+}
+
+/// Prints the simulated relay of packet `sequence` as JSON
+async fn simulate(config: &Config, sequence: u64) -> Result<()> {
+    let [relay] = config.relays.as_slice() else {
+        bail!("simulate needs exactly one relay; select it with --relay");
+    };
+    let poller = IbcPoller::new(relay).await?.with_retry(&config.retry);
+    let result = poller.simulate_relay(sequence).await?;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    if let Some(error) = &result.error {
+        bail!("Packet {} would not be relayed: {}", sequence, error);
+    }
+    Ok(())
+}
+
+/// Pre-flight check: prints the build, each RPC's chain ID, height and node version, and the
+/// state of each watched channel
+async fn status(config: &Config) -> Result<()> {
+    let build = aero_relay::build_info();
+    println!("aero-relay {}", build.version);
+    println!("features: {}", if build.features.is_empty() { "none".to_string() } else { build.features.join(" ") });
+
+    let mut unreachable = 0;
+    for relay in &config.relays {
+        println!("\n[{}] {} -> {}", relay.name, relay.src_chain, relay.dst_chain);
+        let endpoints = relay
+            .src_endpoints()
+            .into_iter()
+            .map(|url| ("src", url))
+            .chain(std::iter::once(("dst", relay.dst_rpc.clone())));
+
+        for (side, url) in endpoints {
+            let result = async {
+                let client = RpcClient::new(&url)?;
+                with_timeout(relay.rpc_timeout(), "status", client.status()).await
+            }
+            .await;
+
+            match result {
+                Ok(status) => println!(
+                    "  {} {}: chain {}, height {}, node {}",
+                    side, url, status.node_info.network, status.sync_info.latest_block_height, status.node_info.version
+                ),
+                Err(e) => {
+                    println!("  {} {}: UNREACHABLE ({:#})", side, url, e);
+                    unreachable += 1;
+                }
+            }
+        }
+
+        let channel = async {
+            let mut channels = ChannelCache::new(RpcClient::new(&relay.src_rpc)?, relay.rpc_timeout(), None);
+            channels.load(&relay.src_port, &relay.src_channel).await.cloned()
+        }
+        .await;
+        match channel {
+            Ok(channel) if channel.is_closed() => println!(
+                "  channel {}/{}: CLOSED – packets will not be relayed",
+                channel.port_id, channel.channel_id
+            ),
+            Ok(channel) => println!(
+                "  channel {}/{}: {}, {:?}",
+                channel.port_id, channel.channel_id, channel.state, channel.ordering
+            ),
+            Err(e) => println!("  channel {}/{}: unknown ({:#})", relay.src_port, relay.src_channel, e),
+        }
+    }
+
+    if unreachable > 0 {
+        bail!("{} RPC endpoint(s) unreachable", unreachable);
+    }
+    Ok(())
+}
+
+/// Loads the on-chain sequence of every pooled signer from the destination chain
+async fn sync_signer_sequences(pool: &SignerPool, dst_rpc: &str) -> Result<()> {
+    let client = RpcClient::new(dst_rpc)?;
+    pool.sync_sequences(&client).await
+}
+
+/// Runs one catch-up pass for every relay and exits: up to the current tip, or over the
+/// `range` of blocks when backfilling (which leaves saved state untouched).
+/// Fails (non-zero exit code) if any relay could not be scanned or any packet failed to relay.
+async fn run_once(config: Config, range: Option<(u64, u64)>) -> Result<()> {
+    let mut tasks = Vec::new();
+
+    for relay in config.relays {
+        let zk = config.zk.clone();
+        let retry = config.retry;
+        let transport_config = config.transport.clone();
+        let state_path = config.state.path_for(&relay.name);
+        let start_from = config.state.start_from;
+        tasks.push(tokio::spawn(async move {
+            let result = async {
+                let mut poller = IbcPoller::new(&relay).await?.with_zk_config(&zk).with_retry(&retry);
+                if let Some(peer) = &relay.forward_to {
+                    poller = poller.with_forward_peer(peer, &transport_config);
+                }
+                match range {
+                    Some((from, to)) => poller.run_range(from, to).await,
+                    None => {
+                        if let Some(path) = state_path {
+                            poller = poller.with_state_file(path)?;
+                        }
+                        if start_from == StartFrom::Genesis {
+                            poller = poller.with_genesis_start().await?;
+                        }
+                        poller.run_once().await
+                    }
+                }
+            }
+            .await;
+            (relay.name, result)
+        }));
+    }
+
+    let mut failed_relays = 0;
+    for task in tasks {
+        let (name, result) = task.await?;
+        match result {
+            Ok(report) => {
+                info!(
+                    "[{}] Scanned {} blocks: {} packets, {} failed",
+                    name, report.blocks, report.packets, report.failed
+                );
+                if report.failed > 0 {
+                    failed_relays += 1;
+                }
+            }
+            Err(e) => {
+                error!("[{}] One-shot scan failed: {}", name, e);
+                failed_relays += 1;
+            }
+        }
+    }
+
+    if failed_relays > 0 {
+        bail!("{} relay(s) failed during one-shot scan", failed_relays);
+    }
 
+    info!("One-shot scan complete");
     Ok(())
-}
\ No newline at end of file
+}
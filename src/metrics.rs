@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{debug, info};
+
+/// Upper bounds (seconds) of histogram buckets
+const BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+#[derive(Default, Clone)]
+struct Histogram {
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// Series are keyed by metric name and rendered label set
+type SeriesKey = (String, String);
+
+#[derive(Default)]
+struct Registry {
+    counters: BTreeMap<SeriesKey, f64>,
+    gauges: BTreeMap<SeriesKey, f64>,
+    histograms: BTreeMap<SeriesKey, Histogram>,
+}
+
+lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry::default());
+}
+
+fn key(name: &str, labels: &[(&str, &str)]) -> SeriesKey {
+    let rendered = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(",");
+    (name.to_string(), rendered)
+}
+
+/// Increments a counter by one
+pub fn inc_counter(name: &str, labels: &[(&str, &str)]) {
+    add_counter(name, labels, 1.0);
+}
+
+/// Increments a counter by `value`
+pub fn add_counter(name: &str, labels: &[(&str, &str)], value: f64) {
+    let mut registry = REGISTRY.lock().unwrap();
+    *registry.counters.entry(key(name, labels)).or_insert(0.0) += value;
+}
+
+/// Sets a gauge to `value`
+pub fn set_gauge(name: &str, labels: &[(&str, &str)], value: f64) {
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.gauges.insert(key(name, labels), value);
+}
+
+/// Records one observation (seconds) in a histogram
+pub fn observe(name: &str, labels: &[(&str, &str)], value: f64) {
+    let mut registry = REGISTRY.lock().unwrap();
+    let histogram = registry.histograms.entry(key(name, labels)).or_insert_with(|| Histogram {
+        counts: vec![0; BUCKETS.len()],
+        ..Default::default()
+    });
+
+    for (i, bound) in BUCKETS.iter().enumerate() {
+        if value <= *bound {
+            histogram.counts[i] += 1;
+        }
+    }
+    histogram.sum += value;
+    histogram.count += 1;
+}
+
+/// Current values of every series of the gauge `name`
+pub fn gauge_values(name: &str) -> Vec<f64> {
+    let registry = REGISTRY.lock().unwrap();
+    registry
+        .gauges
+        .iter()
+        .filter(|((n, _), _)| n == name)
+        .map(|(_, value)| *value)
+        .collect()
+}
+
+fn series(name: &str, labels: &str) -> String {
+    if labels.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}{{{}}}", name, labels)
+    }
+}
+
+/// Renders all metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let registry = REGISTRY.lock().unwrap();
+    let mut out = String::new();
+
+    for ((name, labels), value) in &registry.counters {
+        let _ = writeln!(out, "{} {}", series(name, labels), value);
+    }
+    for ((name, labels), value) in &registry.gauges {
+        let _ = writeln!(out, "{} {}", series(name, labels), value);
+    }
+    for ((name, labels), histogram) in &registry.histograms {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (bound, count) in BUCKETS.iter().zip(&histogram.counts) {
+            let _ = writeln!(out, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, sep, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, labels, sep, histogram.count);
+        let _ = writeln!(out, "{} {}", series(&format!("{}_sum", name), labels), histogram.sum);
+        let _ = writeln!(out, "{} {}", series(&format!("{}_count", name), labels), histogram.count);
+    }
+
+    #[cfg(feature = "metrics")]
+    process_metrics(&mut out);
+
+    out
+}
+
+/// Process CPU and memory usage (requires the `metrics` feature)
+#[cfg(feature = "metrics")]
+fn process_metrics(out: &mut String) {
+    use sysinfo::{get_current_pid, System};
+
+    let Ok(pid) = get_current_pid() else { return };
+    let mut system = System::new();
+    system.refresh_process(pid);
+
+    if let Some(process) = system.process(pid) {
+        let _ = writeln!(out, "process_resident_memory_bytes {}", process.memory());
+        let _ = writeln!(out, "process_cpu_usage_percent {}", process.cpu_usage());
+    }
+}
+
+/// Serves `GET /metrics` (Prometheus text) and `GET /ready` on `listen_addr`.
+/// `/ready` answers 503 until a block has been processed and while any channel's
+/// `relay_block_lag_seconds` exceeds `max_lag`.
+pub async fn serve(listen_addr: &str, max_lag: Duration) -> Result<()> {
+    let listener = TcpListener::bind(listen_addr)
+        .await
+        .context(format!("Failed to bind metrics server to {}", listen_addr))?;
+
+    info!("Metrics server listening on {}", listen_addr);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            let service = service_fn(move |req| async move { Ok::<_, Infallible>(respond(&req, max_lag)) });
+            if let Err(e) = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                debug!("Metrics connection error: {}", e);
+            }
+        });
+    }
+}
+
+fn respond(req: &Request<Incoming>, max_lag: Duration) -> Response<Full<Bytes>> {
+    let (status, body) = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => (StatusCode::OK, render()),
+        (&Method::GET, "/ready") => {
+            let lags = gauge_values("relay_block_lag_seconds");
+            if !lags.is_empty() && lags.iter().all(|lag| *lag <= max_lag.as_secs_f64()) {
+                (StatusCode::OK, "ready\n".to_string())
+            } else {
+                (StatusCode::SERVICE_UNAVAILABLE, "not ready\n".to_string())
+            }
+        }
+        _ => (StatusCode::NOT_FOUND, "not found\n".to_string()),
+    };
+
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response
+}
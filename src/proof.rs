@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::config::{CommitmentScheme, ProofBackendKind, ZkConfig};
+use crate::error::Cancelled;
+
+/// A proving system for packet commitments, so relay logic does not depend on a specific SNARK
+pub trait ProofBackend: Send + Sync {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Produces a proof over the packet commitment input (raw packet data)
+    fn generate(&self, commitment_input: &[u8]) -> Result<Vec<u8>>;
+
+    /// Checks `proof` against the public input it was generated for
+    fn verify(&self, proof: &[u8], public: &[u8]) -> Result<bool>;
+}
+
+/// Lightweight backend: the "proof" is the blake3 hash of the input.
+/// Binds the packet contents but proves nothing in zero knowledge.
+pub struct Blake3Backend;
+
+impl ProofBackend for Blake3Backend {
+    fn name(&self) -> &'static str {
+        "blake3"
+    }
+
+    fn generate(&self, commitment_input: &[u8]) -> Result<Vec<u8>> {
+        Ok(blake3::hash(commitment_input).as_bytes().to_vec())
+    }
+
+    fn verify(&self, proof: &[u8], public: &[u8]) -> Result<bool> {
+        Ok(proof == blake3::hash(public).as_bytes())
+    }
+}
+
+/// halo2 (KZG or IPA over BN254) packet commitment circuit from `zk.rs`, sized 2^k rows
+#[cfg(feature = "encryption-proof")]
+pub struct Halo2Backend {
+    pub scheme: CommitmentScheme,
+    pub k: u32,
+}
+
+#[cfg(feature = "encryption-proof")]
+impl ProofBackend for Halo2Backend {
+    fn name(&self) -> &'static str {
+        "halo2"
+    }
+
+    fn generate(&self, commitment_input: &[u8]) -> Result<Vec<u8>> {
+        let setup = crate::zk::proving_setup(self.scheme, self.k)?;
+        crate::zk::generate_packet_proof_with_rng(setup, &hex::encode(commitment_input), rand_core::OsRng)
+    }
+
+    /// The circuit has no public instances yet, so `public` is not checked
+    fn verify(&self, proof: &[u8], _public: &[u8]) -> Result<bool> {
+        crate::zk::verify_packet_proof(self.scheme, self.k, proof)
+    }
+}
+
+/// Runs `backend.generate` on the blocking pool so proving never stalls the runtime.
+/// Fails with `Cancelled` as soon as `cancel` fires; the blocking thread cannot be
+/// interrupted, so the abandoned proof finishes in the background.
+pub async fn generate_packet_proof_async(
+    backend: Arc<dyn ProofBackend>,
+    input: Vec<u8>,
+    cancel: CancellationToken,
+) -> Result<Vec<u8>> {
+    let task = tokio::task::spawn_blocking(move || backend.generate(&input));
+    tokio::select! {
+        result = task => result.context("Proof task panicked")?,
+        _ = cancel.cancelled() => Err(Cancelled.into()),
+    }
+}
+
+/// Builds the backend selected in `[zk]`; `None` disables proof generation.
+/// Without an explicit choice this is halo2 when `encryption-proof` is enabled.
+pub fn from_config(zk: &ZkConfig) -> Result<Option<Arc<dyn ProofBackend>>> {
+    let kind = zk.backend.unwrap_or(if cfg!(feature = "encryption-proof") {
+        ProofBackendKind::Halo2
+    } else {
+        ProofBackendKind::None
+    });
+
+    match kind {
+        ProofBackendKind::None => Ok(None),
+        ProofBackendKind::Blake3 => Ok(Some(Arc::new(Blake3Backend))),
+        #[cfg(feature = "encryption-proof")]
+        ProofBackendKind::Halo2 => Ok(Some(Arc::new(Halo2Backend { scheme: zk.scheme, k: zk.k }))),
+        #[cfg(not(feature = "encryption-proof"))]
+        ProofBackendKind::Halo2 => anyhow::bail!("The halo2 proof backend requires the encryption-proof feature"),
+    }
+}
+
+/// Startup check: builds the halo2 setup for `[zk] k` so an undersized circuit fails
+/// with the minimum viable `k` before relaying starts, instead of mid-relay. Logs which
+/// backend proves every packet and, for halo2, the cost of one warmup proof, so a
+/// binary built with `encryption-proof` by accident is noticed.
+pub fn preflight(zk: &ZkConfig) -> Result<()> {
+    let Some(backend) = from_config(zk)? else {
+        info!("Proof generation disabled");
+        return Ok(());
+    };
+
+    #[cfg(feature = "encryption-proof")]
+    if backend.name() == "halo2" {
+        if zk.backend.is_none() {
+            warn!("halo2 proofs are on because the binary was built with encryption-proof; set [zk] backend = \"none\" to disable them");
+        }
+        let cached = crate::zk::params_cached(zk.scheme, zk.k);
+        info!(
+            "halo2 proofs: scheme {:?}, k = {}, params {}",
+            zk.scheme,
+            zk.k,
+            if cached { "cached" } else { "not cached (running setup)" }
+        );
+        crate::zk::proving_setup(zk.scheme, zk.k)?;
+    }
+
+    let started = std::time::Instant::now();
+    backend.generate(b"aero-relay warmup").context("Warmup proof failed")?;
+    let elapsed = started.elapsed();
+    info!(
+        "Proof backend {} proves every relayed packet; warmup proof took {} ms",
+        backend.name(),
+        elapsed.as_millis()
+    );
+    if elapsed > zk.proof_timeout() {
+        warn!(
+            "Warmup proof took longer than proof_timeout_ms = {}; packets will hit the {:?} policy",
+            zk.proof_timeout_ms, zk.on_proof_timeout
+        );
+    }
+    Ok(())
+}
@@ -1,2 +1,1084 @@
-// Placeholder module for future relay logic
-// Currently unused – kept for planned features (e.g. packet forwarding, signing, broadcasting)
\ No newline at end of file
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tendermint_rpc::query::Query;
+use tendermint_rpc::{Client, Order};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use ibc_proto::ibc::applications::transfer::v2::FungibleTokenPacketData as ProtoFungibleTokenPacketData;
+use ibc_proto::ibc::core::channel::v1::{MsgAcknowledgement, MsgRecvPacket, Packet};
+use ibc_proto::ibc::core::client::v1::Height as IbcHeight;
+
+use crate::admin::RelayControl;
+use crate::alert::{AlertEvent, Notifier};
+use crate::config::{ProofTimeoutPolicy, TransportConfig, ZkConfig, DEFAULT_GAS_ESTIMATE};
+use crate::denom::DenomResolver;
+use crate::error::Cancelled;
+use crate::ibc::{self, ChannelVersion, PacketPayload, ParsedPacket, TimeoutTimestamp};
+use crate::metrics;
+use crate::proof::{self, ProofBackend};
+use crate::rpc::{with_timeout, RpcClient};
+use crate::signer::SignerPool;
+use crate::simulate::query_ack_proof;
+use crate::sink::{EventSink, RelayEvent};
+use crate::state::PacketTracker;
+use crate::submit::{self, Fee, GasPrice};
+use crate::transport::{self, PeerConnection};
+
+/// Which way a packet travels relative to the relay's configured source and destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Sent on the watched source channel
+    #[default]
+    SrcToDst,
+    /// Sent by the counterparty towards the watched channel
+    DstToSrc,
+}
+
+impl Direction {
+    /// Metric label value
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::SrcToDst => "src_to_dst",
+            Direction::DstToSrc => "dst_to_src",
+        }
+    }
+}
+
+/// IBC message a detected event is relayed as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MsgType {
+    /// `send_packet` → MsgRecvPacket
+    #[default]
+    Recv,
+    /// `write_acknowledgement` → MsgAcknowledgement
+    Ack,
+    /// Expired packet → MsgTimeout
+    Timeout,
+}
+
+impl MsgType {
+    /// Metric label value
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MsgType::Recv => "recv",
+            MsgType::Ack => "ack",
+            MsgType::Timeout => "timeout",
+        }
+    }
+}
+
+/// Packet handed from detection (poller) to relaying (worker)
+#[derive(Debug, Clone)]
+pub struct RelayJob {
+    pub packet: ParsedPacket,
+    pub packet_data_hex: String,
+    pub direction: Direction,
+    pub msg_type: MsgType,
+    /// Source height the packet was detected at (used as proof height)
+    pub height: u64,
+    /// Wall-clock detection time; start of the `relay_e2e_seconds` measurement
+    pub detected_at: SystemTime,
+    /// Source channel is ORDERED (packets must be submitted in sequence)
+    pub ordered: bool,
+    /// The tracker evicted this sequence's range, so it may already be relayed:
+    /// query the destination before relaying
+    pub check_receipt: bool,
+}
+
+/// The IBC `Packet` for a detected packet: ICS-20 data re-encoded as protobuf, raw
+/// payloads verbatim. The data must stay as the source committed it (memo included), or
+/// the destination rejects the commitment proof.
+pub fn ibc_packet(parsed: &ParsedPacket) -> Result<Packet> {
+    let data_bytes = match &parsed.payload {
+        PacketPayload::Json => {
+            let fungible_data = ProtoFungibleTokenPacketData {
+                denom: parsed.data.denom.clone(),
+                amount: parsed.data.amount.clone(),
+                sender: parsed.data.sender.clone(),
+                receiver: parsed.data.receiver.clone(),
+                memo: parsed.data.memo.clone(),
+            };
+
+            let mut data_bytes = Vec::new();
+            prost::Message::encode(&fungible_data, &mut data_bytes)
+                .context("Failed to encode FungibleTokenPacketData")?;
+            data_bytes
+        }
+        PacketPayload::Raw(bytes) => bytes.clone(),
+    };
+
+    let revision_height = parsed.timeout_height
+        .split('-')
+        .nth(1)
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0);
+
+    Ok(Packet {
+        sequence: parsed.sequence,
+        source_port: parsed.src_port.clone(),
+        source_channel: parsed.src_channel.clone(),
+        destination_port: parsed.dst_port.clone(),
+        destination_channel: parsed.dst_channel.clone(),
+        data: data_bytes,
+        timeout_height: if revision_height > 0 {
+            Some(IbcHeight {
+                revision_number: 1,
+                revision_height,
+            })
+        } else {
+            None
+        },
+        timeout_timestamp: parsed.timeout_timestamp.nanos(),
+    })
+}
+
+/// Assembles a `MsgRecvPacket`; `build` fails while a required field is missing.
+/// The proof is optional (empty until proofs are submitted).
+#[derive(Debug, Clone, Default)]
+pub struct RecvPacketBuilder {
+    packet: Option<Packet>,
+    proof: Vec<u8>,
+    proof_height: Option<IbcHeight>,
+    signer: Option<String>,
+}
+
+impl RecvPacketBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn packet(mut self, packet: Packet) -> Self {
+        self.packet = Some(packet);
+        self
+    }
+
+    /// Encoded commitment proof (`proof_commitment`)
+    pub fn proof(mut self, proof: Vec<u8>) -> Self {
+        self.proof = proof;
+        self
+    }
+
+    /// Source height the proof verifies at (revision 0)
+    pub fn proof_height(mut self, height: u64) -> Self {
+        self.proof_height = Some(IbcHeight {
+            revision_number: 0,
+            revision_height: height,
+        });
+        self
+    }
+
+    /// Destination account submitting the message
+    pub fn signer(mut self, signer: impl Into<String>) -> Self {
+        self.signer = Some(signer.into());
+        self
+    }
+
+    pub fn build(self) -> Result<MsgRecvPacket> {
+        let packet = self.packet.ok_or_else(|| anyhow!("MsgRecvPacket needs a packet"))?;
+        let proof_height = self
+            .proof_height
+            .filter(|height| height.revision_height > 0)
+            .ok_or_else(|| anyhow!("MsgRecvPacket for packet {} needs a proof height", packet.sequence))?;
+        let signer = self
+            .signer
+            .filter(|signer| !signer.is_empty())
+            .ok_or_else(|| anyhow!("MsgRecvPacket for packet {} needs a signer", packet.sequence))?;
+
+        Ok(MsgRecvPacket {
+            packet: Some(packet),
+            proof_commitment: self.proof,
+            proof_height: Some(proof_height),
+            signer,
+        })
+    }
+}
+
+/// Counts a finished relay attempt in `relay_packets_total`. Labels stay bounded:
+/// direction, message type and outcome – never addresses, sequences or denoms (any sender
+/// can mint a new `ibc/<hash>` or made-up denom).
+fn record_packet_metric(relay: &str, job: &RelayJob, outcome: &RelayOutcome) {
+    let outcome = match outcome {
+        RelayOutcome::Relayed { .. } => "relayed",
+        RelayOutcome::Skipped { .. } => "skipped",
+        RelayOutcome::Failed { .. } => "failed",
+    };
+    metrics::inc_counter(
+        "relay_packets_total",
+        &[
+            ("relay", relay),
+            ("direction", job.direction.as_str()),
+            ("msg_type", job.msg_type.as_str()),
+            ("outcome", outcome),
+        ],
+    );
+}
+
+/// Seconds elapsed since `since` (zero if the clock went backwards)
+pub fn seconds_since(since: SystemTime) -> f64 {
+    SystemTime::now().duration_since(since).unwrap_or_default().as_secs_f64()
+}
+
+/// Wire message between aero-relay nodes: a packet detected (and proven) on one node,
+/// forwarded to a peer that submits it. Sent as JSON on its own QUIC stream (or TCP frame).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayRequest {
+    /// Relay (config name) on the receiving node that should submit the packet
+    pub relay: String,
+    pub sequence: u64,
+    pub src_port: String,
+    pub src_channel: String,
+    pub dst_port: String,
+    pub dst_channel: String,
+    pub timeout_height: String,
+    pub timeout_timestamp: TimeoutTimestamp,
+    pub packet_data_hex: String,
+    /// Source height the packet was detected at
+    pub height: u64,
+    /// Hex-encoded proof, empty when none was generated
+    #[serde(default)]
+    pub proof_hex: String,
+    /// Detection time on the forwarding node (unix ms, 0 if unknown)
+    #[serde(default)]
+    pub detected_at_ms: u64,
+    #[serde(default)]
+    pub ordered: bool,
+    #[serde(default)]
+    pub direction: Direction,
+    #[serde(default)]
+    pub msg_type: MsgType,
+    /// `RelayRequest::packet_key`, set by the forwarding node. Receivers recompute the key
+    /// (only comparing it with this one) and drop requests whose key they have seen
+    /// recently. Empty from nodes that predate it.
+    #[serde(default)]
+    pub idempotency_key: String,
+    /// `[transport] peer_token` of the forwarding node, checked by the receiver. Only set
+    /// on the wire (`transport::forward_to_peer`).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub peer_token: String,
+}
+
+/// Peer's answer to a `RelayRequest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RelayResponse {
+    /// Queued on the peer's relay worker for submission
+    Queued,
+    Rejected { reason: String },
+}
+
+impl RelayRequest {
+    pub fn new(relay: &str, job: &RelayJob, proof: &[u8]) -> Self {
+        let packet = &job.packet;
+        Self {
+            relay: relay.to_string(),
+            sequence: packet.sequence,
+            src_port: packet.src_port.clone(),
+            src_channel: packet.src_channel.clone(),
+            dst_port: packet.dst_port.clone(),
+            dst_channel: packet.dst_channel.clone(),
+            timeout_height: packet.timeout_height.clone(),
+            timeout_timestamp: packet.timeout_timestamp,
+            packet_data_hex: job.packet_data_hex.clone(),
+            height: job.height,
+            proof_hex: hex::encode(proof),
+            detected_at_ms: job
+                .detected_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            ordered: job.ordered,
+            direction: job.direction,
+            msg_type: job.msg_type,
+            idempotency_key: String::new(),
+            peer_token: String::new(),
+        }
+        .with_idempotency_key()
+    }
+
+    fn with_idempotency_key(mut self) -> Self {
+        self.idempotency_key = self.packet_key();
+        self
+    }
+
+    /// Deterministic key of the packet: hash of its channel, sequence and commitment
+    /// (timeouts and data). Retries and re-detections of a packet share the key.
+    pub fn packet_key(&self) -> String {
+        let mut commitment = blake3::Hasher::new();
+        commitment.update(self.timeout_height.as_bytes());
+        commitment.update(&self.timeout_timestamp.nanos().to_be_bytes());
+        commitment.update(self.packet_data_hex.to_lowercase().as_bytes());
+
+        let mut key = blake3::Hasher::new();
+        for part in [&self.src_port, &self.src_channel, &self.dst_port, &self.dst_channel] {
+            key.update(part.as_bytes());
+            key.update(&[0]);
+        }
+        key.update(&self.sequence.to_be_bytes());
+        key.update(commitment.finalize().as_bytes());
+        key.finalize().to_hex().to_string()
+    }
+
+    /// Rebuilds the relay job; transfer data is re-parsed from `packet_data_hex`.
+    /// Data that is not JSON is relayed raw, as the sending node already accepted it.
+    pub fn into_job(self) -> Result<RelayJob> {
+        let bytes = ibc::decode_packet_data(&self.packet_data_hex)?;
+        let (data, payload) = match ibc::parse_packet_json(&bytes) {
+            Ok(json) if ibc::is_transfer_packet(&json) => (ibc::parse_transfer_data(&json)?, PacketPayload::Json),
+            Ok(_) => return Err(anyhow!("packet {} is not an ICS-20 transfer", self.sequence)),
+            Err(_) => (Default::default(), PacketPayload::Raw(bytes)),
+        };
+
+        Ok(RelayJob {
+            packet: ParsedPacket {
+                channel_version: ChannelVersion::V1,
+                sequence: self.sequence,
+                src_port: self.src_port,
+                src_channel: self.src_channel,
+                dst_port: self.dst_port,
+                dst_channel: self.dst_channel,
+                timeout_height: self.timeout_height,
+                timeout_timestamp: self.timeout_timestamp,
+                data,
+                payload,
+            },
+            packet_data_hex: self.packet_data_hex,
+            direction: self.direction,
+            msg_type: self.msg_type,
+            height: self.height,
+            detected_at: match self.detected_at_ms {
+                0 => SystemTime::now(),
+                ms => UNIX_EPOCH + Duration::from_millis(ms),
+            },
+            ordered: self.ordered,
+            check_receipt: false,
+        })
+    }
+}
+
+/// Idempotency keys a router remembers; the oldest are forgotten beyond this
+const SEEN_KEYS_CAPACITY: usize = 10_000;
+
+/// Hands requests received from peers to the local worker of the named relay
+#[derive(Default)]
+pub struct RelayRouter {
+    queues: Mutex<HashMap<String, mpsc::Sender<RelayJob>>>,
+    seen: Mutex<SeenKeys>,
+}
+
+/// Recently accepted idempotency keys, oldest first
+#[derive(Default)]
+struct SeenKeys {
+    keys: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl RelayRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the relay's worker reachable for forwarded packets
+    pub fn register(&self, relay: &str, queue: mpsc::Sender<RelayJob>) {
+        self.queues.lock().unwrap().insert(relay.to_string(), queue);
+    }
+
+    /// `relay` as a metric label: "unknown" unless a relay of that name is registered, so
+    /// peers cannot add label values
+    pub fn relay_label<'a>(&self, relay: &'a str) -> &'a str {
+        if self.queues.lock().unwrap().contains_key(relay) {
+            relay
+        } else {
+            "unknown"
+        }
+    }
+
+    /// Records `key` as seen; false if it already was (the request is a duplicate)
+    pub fn mark_seen(&self, key: &str) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+        if !seen.keys.insert(key.to_string()) {
+            return false;
+        }
+        seen.order.push_back(key.to_string());
+        if seen.order.len() > SEEN_KEYS_CAPACITY {
+            if let Some(oldest) = seen.order.pop_front() {
+                seen.keys.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Forgets `key` so a retry of a rejected request is accepted
+    pub fn forget(&self, key: &str) {
+        let mut seen = self.seen.lock().unwrap();
+        if seen.keys.remove(key) {
+            seen.order.retain(|k| k != key);
+        }
+    }
+
+    pub async fn submit(&self, request: RelayRequest) -> RelayResponse {
+        let queue = self.queues.lock().unwrap().get(&request.relay).cloned();
+        let Some(queue) = queue else {
+            return RelayResponse::Rejected {
+                reason: format!("no relay worker for '{}' on this node", request.relay),
+            };
+        };
+
+        let relay = request.relay.clone();
+        let job = match request.into_job() {
+            Ok(job) => job,
+            Err(e) => return RelayResponse::Rejected { reason: format!("invalid packet: {:#}", e) },
+        };
+
+        info!("[{}] Queuing forwarded packet {}", relay, job.packet.sequence);
+        match queue.send(job).await {
+            Ok(()) => RelayResponse::Queued,
+            Err(_) => RelayResponse::Rejected { reason: format!("relay worker for '{}' stopped", relay) },
+        }
+    }
+}
+
+/// Forwards packets to a peer node instead of submitting them locally.
+/// The connection is opened on first use and reopened after it closes.
+struct PeerForwarder {
+    addr: String,
+    transport: TransportConfig,
+    conn: Option<Arc<dyn PeerConnection>>,
+}
+
+impl PeerForwarder {
+    async fn forward(&mut self, request: &RelayRequest) -> Result<RelayResponse> {
+        let conn = match &self.conn {
+            Some(conn) if !conn.is_closed() => conn.clone(),
+            _ => {
+                let conn = transport::connect(&self.addr, &self.transport).await?;
+                self.conn = Some(conn.clone());
+                conn
+            }
+        };
+        transport::forward_to_peer(conn.as_ref(), request, self.transport.peer_token.as_deref()).await
+    }
+}
+
+/// How often `AckWatch` looks for the acknowledgement
+const ACK_POLL_INTERVAL: Duration = Duration::from_secs(6);
+/// `AckWatch` gives up after this long without an acknowledgement
+const ACK_WATCH_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Follows one relayed packet until the destination writes its acknowledgement, then
+/// forms the MsgAcknowledgement for the source (with the proof of the acknowledgement).
+/// Like MsgRecvPacket it is not broadcast yet.
+struct AckWatch {
+    relay: String,
+    /// Destination RPC
+    client: RpcClient,
+    rpc_timeout: Duration,
+    control: Option<Arc<RelayControl>>,
+    cancel: CancellationToken,
+    /// Source-chain signer of the MsgAcknowledgement (`src_signer`)
+    signer: String,
+}
+
+impl AckWatch {
+    async fn run(self, packet: Packet) {
+        let deadline = Instant::now() + ACK_WATCH_TIMEOUT;
+        loop {
+            match self.find_ack(&packet).await {
+                Ok(Some((ack, height))) => {
+                    self.acknowledged(packet, ack, height).await;
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => debug!("[{}] Ack lookup for packet {} failed: {:#}", self.relay, packet.sequence, e),
+            }
+            if Instant::now() >= deadline {
+                warn!(
+                    "[{}] No acknowledgement of packet {} within {} s, no longer following it",
+                    self.relay,
+                    packet.sequence,
+                    ACK_WATCH_TIMEOUT.as_secs()
+                );
+                return;
+            }
+            tokio::select! {
+                _ = self.cancel.cancelled() => return,
+                _ = tokio::time::sleep(ACK_POLL_INTERVAL) => {}
+            }
+        }
+    }
+
+    /// The acknowledgement bytes and destination height, once written
+    async fn find_ack(&self, packet: &Packet) -> Result<Option<(Vec<u8>, u64)>> {
+        let query: Query = format!(
+            "write_acknowledgement.packet_dst_channel='{}' AND write_acknowledgement.packet_sequence={}",
+            packet.destination_channel, packet.sequence
+        )
+        .parse()?;
+        let res = with_timeout(
+            self.rpc_timeout,
+            "tx_search",
+            self.client.tx_search(query, false, 1, 1, Order::Ascending),
+        )
+        .await?;
+
+        let Some(tx) = res.txs.into_iter().next() else { return Ok(None) };
+        let ack_hex = tx
+            .tx_result
+            .events
+            .iter()
+            .filter(|event| event.kind == "write_acknowledgement")
+            .find_map(|event| ibc::event_attr(event, "packet_ack_hex"))
+            .ok_or_else(|| anyhow!("write_acknowledgement without packet_ack_hex"))?;
+        Ok(Some((hex::decode(ack_hex).context("Invalid packet_ack_hex")?, tx.height.value())))
+    }
+
+    async fn acknowledged(&self, packet: Packet, ack: Vec<u8>, height: u64) {
+        let sequence = packet.sequence;
+        let source_channel = packet.source_channel.clone();
+        match ibc::parse_acknowledgement(&ack) {
+            ibc::AckOutcome::Error(reason) => {
+                info!("[{}] Packet {} acknowledged with an error ({}), refund pending", self.relay, sequence, reason)
+            }
+            _ => info!("[{}] Packet {} acknowledged at destination height {}", self.relay, sequence, height),
+        }
+        if let Some(control) = &self.control {
+            control.packet_acked(&source_channel, sequence);
+        }
+
+        let proof = query_ack_proof(
+            &self.client,
+            &packet.destination_port,
+            &packet.destination_channel,
+            sequence,
+            self.rpc_timeout,
+        )
+        .await;
+        let (proof_acked, proof_height) = match proof {
+            Ok(Some(proof)) => proof,
+            Ok(None) => {
+                // Pruned once the source processed the ack, e.g. through another relayer
+                info!("[{}] Acknowledgement of packet {} is no longer stored, nothing to relay back", self.relay, sequence);
+                return;
+            }
+            Err(e) => {
+                warn!("[{}] Ack proof query for packet {} failed: {:#}", self.relay, sequence, e);
+                return;
+            }
+        };
+
+        let msg = MsgAcknowledgement {
+            packet: Some(packet),
+            acknowledgement: ack,
+            proof_acked,
+            proof_height: Some(IbcHeight {
+                revision_number: 0,
+                revision_height: proof_height,
+            }),
+            signer: self.signer.clone(),
+        };
+        info!(
+            "[{}] MsgAcknowledgement formed for sequence {} ({} ack bytes, proof at height {}, signer {})",
+            self.relay,
+            sequence,
+            msg.acknowledgement.len(),
+            proof_height,
+            msg.signer
+        );
+        metrics::inc_counter("relay_acks_followed_total", &[("relay", &self.relay)]);
+    }
+}
+
+/// Totals reported by a worker when its queue closes
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RelayStats {
+    pub relayed: u64,
+    pub skipped: u64,
+    pub failed: u64,
+}
+
+/// Result of one relay attempt, reported to the `on_packet` callback
+#[derive(Debug, Clone)]
+pub enum RelayOutcome {
+    /// Deliberately not relayed (e.g. proof timeout with the skip policy)
+    Skipped { reason: String },
+    /// Relayed; `tx_hash` is set once the message is broadcast,
+    /// `fee` is the estimated cost when a gas price is configured
+    Relayed { tx_hash: Option<String>, fee: Option<Fee> },
+    Failed { error: String },
+}
+
+/// Observer invoked after each relay attempt with the packet and its outcome
+pub type PacketCallback = Arc<dyn Fn(&ParsedPacket, &RelayOutcome) + Send + Sync>;
+
+/// Owns the submit path: consumes detected packets from the poller's queue,
+/// so slow relaying never blocks block scanning beyond the queue bound.
+pub struct RelayWorker {
+    relay_name: String,
+    /// Signer address when there is no signer pool (`RelayPair::signer_address`)
+    signer: String,
+    signer_pool: Option<Arc<SignerPool>>,
+    notifier: Option<Arc<dyn Notifier>>,
+    failure_threshold: u32,
+    consecutive_failures: u32,
+    on_packet: Option<PacketCallback>,
+    zk: ZkConfig,
+    /// Proof system for packet commitments; no proofs when unset
+    proof_backend: Option<Arc<dyn ProofBackend>>,
+    /// Fee estimation is off without a gas price
+    gas_price: Option<GasPrice>,
+    gas_estimate: u64,
+    /// Peer that submits this relay's packets (mesh mode)
+    forwarder: Option<PeerForwarder>,
+    /// Resolves `ibc/<hash>` denoms for logging
+    denom_resolver: Option<Arc<DenomResolver>>,
+    /// In-flight/relayed bookkeeping shared with the poller
+    tracker: Option<Arc<PacketTracker>>,
+    /// Destination RPC for `RelayJob::check_receipt` lookups and timeout checks, with its call timeout
+    receipt_client: Option<(RpcClient, Duration)>,
+    /// Admin API handle; failed packets are dead-lettered there
+    control: Option<Arc<RelayControl>>,
+    /// Fires on shutdown; aborts a running proof
+    cancel: CancellationToken,
+    /// Receives every relay outcome
+    event_sink: Option<Arc<dyn EventSink>>,
+    /// Source-chain signer of MsgAcknowledgement; when set, the destination is watched for
+    /// the acknowledgement of each relayed packet
+    ack_signer: Option<String>,
+    /// Destination RPC URL (and call timeout) whose mempool is checked for the packet
+    /// before relaying it
+    mempool_check: Option<(String, Duration)>,
+    /// Position in the destination's worker pool; selects the signer and labels metrics
+    pool_slot: Option<usize>,
+}
+
+impl RelayWorker {
+    pub fn new(relay_name: &str) -> Self {
+        Self {
+            relay_name: relay_name.to_string(),
+            signer: String::new(),
+            signer_pool: None,
+            notifier: None,
+            failure_threshold: 0,
+            consecutive_failures: 0,
+            on_packet: None,
+            zk: ZkConfig::default(),
+            proof_backend: proof::from_config(&ZkConfig::default()).unwrap_or(None),
+            gas_price: None,
+            gas_estimate: DEFAULT_GAS_ESTIMATE,
+            forwarder: None,
+            denom_resolver: None,
+            tracker: None,
+            receipt_client: None,
+            control: None,
+            cancel: CancellationToken::new(),
+            event_sink: None,
+            ack_signer: None,
+            mempool_check: None,
+            pool_slot: None,
+        }
+    }
+
+    /// Forward packets (with proof) to the aero-relay node at `addr`, which submits
+    /// them on its relay of the same name
+    pub fn set_forward_peer(&mut self, addr: &str, transport: TransportConfig) {
+        self.forwarder = Some(PeerForwarder {
+            addr: addr.to_string(),
+            transport,
+            conn: None,
+        });
+    }
+
+    /// Report finished packets to the poller's tracker
+    pub fn set_tracker(&mut self, tracker: Arc<PacketTracker>) {
+        self.tracker = Some(tracker);
+    }
+
+    /// Destination RPC used to check packets the tracker can no longer vouch for
+    /// and to skip packets whose timeout has passed on the destination
+    pub fn set_receipt_client(&mut self, client: RpcClient, rpc_timeout: Duration) {
+        self.receipt_client = Some((client, rpc_timeout));
+    }
+
+    /// Record failed packets for the admin API's dead-letter listing
+    pub fn set_control(&mut self, control: Arc<RelayControl>) {
+        self.control = Some(control);
+    }
+
+    /// Abandon proof generation (and stop relaying) once `cancel` fires
+    pub fn set_cancel(&mut self, cancel: CancellationToken) {
+        self.cancel = cancel;
+    }
+
+    /// Skip packets another relayer already has pending in the mempool of the
+    /// destination at `rpc_url`
+    pub fn set_mempool_check(&mut self, rpc_url: &str, rpc_timeout: Duration) {
+        self.mempool_check = Some((rpc_url.to_string(), rpc_timeout));
+    }
+
+    /// Log `ibc/<hash>` denoms with their resolved trace (display only)
+    pub fn set_denom_resolver(&mut self, resolver: Arc<DenomResolver>) {
+        self.denom_resolver = Some(resolver);
+    }
+
+    /// Enables per-packet fee estimates of `gas_estimate` gas at `gas_price`
+    pub fn set_gas_price(&mut self, gas_price: GasPrice, gas_estimate: u64) {
+        self.gas_price = Some(gas_price);
+        self.gas_estimate = gas_estimate;
+    }
+
+    /// Applies proof settings, including the backend selected in `[zk]`
+    pub fn set_zk_config(&mut self, zk: ZkConfig) {
+        self.proof_backend = match proof::from_config(&zk) {
+            Ok(backend) => backend,
+            Err(e) => {
+                warn!("[{}] {} – proofs disabled", self.relay_name, e);
+                None
+            }
+        };
+        self.zk = zk;
+    }
+
+    /// Replaces the proof backend (`None` disables proofs)
+    pub fn set_proof_backend(&mut self, backend: Option<Arc<dyn ProofBackend>>) {
+        self.proof_backend = backend;
+    }
+
+    /// Address messages are signed with when there is no signer pool
+    pub fn set_signer(&mut self, signer: &str) {
+        self.signer = signer.to_string();
+    }
+
+    pub fn set_signer_pool(&mut self, pool: Arc<SignerPool>) {
+        self.signer_pool = Some(pool);
+    }
+
+    /// Alert after `failure_threshold` consecutive relay failures
+    pub fn set_notifier(&mut self, notifier: Arc<dyn Notifier>, failure_threshold: u32) {
+        self.notifier = Some(notifier);
+        self.failure_threshold = failure_threshold.max(1);
+    }
+
+    /// After relaying a packet, watch the destination (receipt client) for its
+    /// `write_acknowledgement` and form the MsgAcknowledgement back to the source, signed
+    /// by the source-chain account `src_signer`
+    pub fn set_follow_acks(&mut self, src_signer: String) {
+        self.ack_signer = Some(src_signer);
+    }
+
+    /// Makes this worker slot `slot` of a worker pool: it signs with the signer pool's
+    /// `slot`-th signer instead of rotating through all of them
+    pub fn set_pool_slot(&mut self, slot: usize) {
+        self.pool_slot = Some(slot);
+    }
+
+    /// Publish every relay outcome to `sink`
+    pub fn set_event_sink(&mut self, sink: Arc<dyn EventSink>) {
+        self.event_sink = Some(sink);
+    }
+
+    /// Called after every relay attempt; runs on the worker task, so it must not block
+    pub fn set_on_packet(&mut self, on_packet: PacketCallback) {
+        self.on_packet = Some(on_packet);
+    }
+
+    /// Relays jobs from `jobs` (this worker's own queue) and `shared` (the queue all workers
+    /// of the pool take from) until both are closed
+    pub async fn run(mut self, mut jobs: mpsc::Receiver<RelayJob>, shared: async_channel::Receiver<RelayJob>) -> RelayStats {
+        let mut stats = RelayStats::default();
+
+        loop {
+            let job = tokio::select! {
+                Some(job) = jobs.recv() => job,
+                Ok(job) = shared.recv() => job,
+                else => break,
+            };
+            let outcome = match self.relay_packet(&job).await {
+                Ok(outcome) => outcome,
+                Err(e) if e.is::<Cancelled>() => {
+                    // Left in flight, so the saved state re-queues it on restart
+                    info!("[{}] Relay of packet {} cancelled by shutdown", self.relay_name, job.packet.sequence);
+                    break;
+                }
+                Err(e) => {
+                    error!("Failed to relay packet: {:?}", e);
+                    RelayOutcome::Failed { error: format!("{:#}", e) }
+                }
+            };
+            match &outcome {
+                RelayOutcome::Relayed { .. } => {
+                    stats.relayed += 1;
+                    self.follow_ack(&job);
+                    // Forwarded packets are measured on the peer that submits them
+                    if self.forwarder.is_none() {
+                        metrics::observe(
+                            "relay_e2e_seconds",
+                            &[
+                                ("relay", &self.relay_name),
+                                ("mode", "relay"),
+                                ("direction", job.direction.as_str()),
+                                ("msg_type", job.msg_type.as_str()),
+                            ],
+                            seconds_since(job.detected_at),
+                        );
+                    }
+                }
+                RelayOutcome::Skipped { .. } => stats.skipped += 1,
+                RelayOutcome::Failed { error } => {
+                    stats.failed += 1;
+                    if let Some(control) = &self.control {
+                        control.dead_letter(RelayRequest::new(&self.relay_name, &job, &[]), error.clone());
+                    }
+                }
+            }
+            if let Some(control) = &self.control {
+                let timed_out = matches!(outcome, RelayOutcome::Skipped { .. })
+                    && job.packet.timeout_timestamp.has_expired(SystemTime::now());
+                control.packet_finished(&job, &outcome, timed_out);
+            }
+            self.record_result(&outcome);
+            record_packet_metric(&self.relay_name, &job, &outcome);
+            if let Some(slot) = self.pool_slot {
+                metrics::inc_counter(
+                    "relay_worker_packets_total",
+                    &[("relay", &self.relay_name), ("worker", &slot.to_string())],
+                );
+            }
+            if let Some(tracker) = &self.tracker {
+                tracker.finish(job.packet.sequence, &outcome);
+            }
+
+            if let Some(sink) = &self.event_sink {
+                sink.publish(&RelayEvent::new(&self.relay_name, &job, &outcome));
+            }
+            if let Some(on_packet) = &self.on_packet {
+                on_packet(&job.packet, &outcome);
+            }
+        }
+
+        info!("[{}] Relay worker stopped", self.relay_name);
+        stats
+    }
+
+    /// Starts watching for the acknowledgement of a relayed receive (`follow_acks`)
+    fn follow_ack(&self, job: &RelayJob) {
+        let Some(signer) = self.ack_signer.clone() else { return };
+        if job.msg_type != MsgType::Recv || self.forwarder.is_some() {
+            return;
+        }
+        let Some((client, rpc_timeout)) = self.receipt_client.clone() else { return };
+        let packet = match ibc_packet(&job.packet) {
+            Ok(packet) => packet,
+            Err(e) => {
+                warn!("[{}] Cannot follow the ack of packet {}: {:#}", self.relay_name, job.packet.sequence, e);
+                return;
+            }
+        };
+        let watch = AckWatch {
+            relay: self.relay_name.clone(),
+            client,
+            rpc_timeout,
+            control: self.control.clone(),
+            cancel: self.cancel.clone(),
+            signer,
+        };
+        tokio::spawn(watch.run(packet));
+    }
+
+    /// Counts consecutive relay failures; alerts once when the threshold is reached
+    fn record_result(&mut self, outcome: &RelayOutcome) {
+        let Some(notifier) = &self.notifier else { return };
+
+        match outcome {
+            RelayOutcome::Failed { error } => {
+                self.consecutive_failures += 1;
+                if self.consecutive_failures == self.failure_threshold {
+                    notifier.notify(AlertEvent::RelayFailures {
+                        relay: self.relay_name.clone(),
+                        consecutive: self.consecutive_failures,
+                        last_error: error.clone(),
+                    });
+                }
+            }
+            _ => self.consecutive_failures = 0,
+        }
+    }
+
+    /// Latest destination block time, queried only for packets with a timestamp timeout.
+    /// `None` (no check) without a destination RPC or when the query fails.
+    async fn dst_block_time(&self, timeout: TimeoutTimestamp) -> Option<SystemTime> {
+        let (client, rpc_timeout) = self.receipt_client.as_ref().filter(|_| timeout.is_set())?;
+        match with_timeout(*rpc_timeout, "status", client.status()).await {
+            Ok(status) => {
+                let nanos = status.sync_info.latest_block_time.unix_timestamp_nanos();
+                Some(UNIX_EPOCH + Duration::from_nanos(nanos.max(0) as u64))
+            }
+            Err(e) => {
+                warn!("Could not fetch the destination block time: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Process a detected IBC packet (forms MsgRecvPacket and optional ZK proof)
+    async fn relay_packet(&mut self, job: &RelayJob) -> Result<RelayOutcome> {
+        let parsed = &job.packet;
+        let packet_start = Instant::now();
+
+        if job.check_receipt {
+            // Without a confirmed absence of the receipt, failing beats relaying twice
+            let (client, rpc_timeout) = self
+                .receipt_client
+                .as_ref()
+                .ok_or_else(|| anyhow!("packet {} needs a receipt check but no destination RPC is set", parsed.sequence))?;
+            let received = with_timeout(*rpc_timeout, "receipt query", ibc::packet_received(client, parsed, job.ordered))
+                .await
+                .context(format!("Receipt check for packet {} failed", parsed.sequence))?;
+            if received {
+                info!("Packet {} already received on the destination – skipping", parsed.sequence);
+                return Ok(RelayOutcome::Skipped {
+                    reason: "already received on the destination".to_string(),
+                });
+            }
+        }
+
+        if let Some(block_time) = self.dst_block_time(parsed.timeout_timestamp).await {
+            if parsed.timeout_timestamp.has_expired(block_time) {
+                warn!(
+                    "Packet {} timed out at {} (destination time is past it) – not relaying",
+                    parsed.sequence, parsed.timeout_timestamp
+                );
+                return Ok(RelayOutcome::Skipped {
+                    reason: format!("timed out at {}", parsed.timeout_timestamp),
+                });
+            }
+        }
+
+        info!("Forming MsgRecvPacket for sequence {}", parsed.sequence);
+
+        let packet = ibc_packet(parsed)?;
+
+        if let Some((rpc_url, rpc_timeout)) = &self.mempool_check {
+            match submit::is_in_mempool(rpc_url, &packet, *rpc_timeout).await {
+                Ok(true) => {
+                    info!("Packet {} is already pending in the destination mempool, skipping", parsed.sequence);
+                    return Ok(RelayOutcome::Skipped {
+                        reason: "already pending in the destination mempool".to_string(),
+                    });
+                }
+                Ok(false) => {}
+                Err(e) => warn!("[{}] Mempool check failed, relaying anyway: {:#}", self.relay_name, e),
+            }
+        }
+
+        let signer = match &self.signer_pool {
+            Some(pool) => {
+                let signer = match self.pool_slot {
+                    Some(slot) => pool.select_slot(slot),
+                    None => pool.select(&parsed.src_channel, job.ordered),
+                };
+                // The sequence is reserved (`reserve_sequence`) only once a tx is signed
+                if let Some(sequence) = pool.peek_sequence(signer.address()) {
+                    info!("  Using signer {} (next sequence {})", signer.address(), sequence);
+                }
+                signer.address().to_string()
+            }
+            None => self.signer.clone(),
+        };
+
+        let msg = RecvPacketBuilder::new()
+            .packet(packet)
+            .proof_height(job.height)
+            .signer(signer)
+            .build()?;
+
+        info!("MsgRecvPacket formed successfully!");
+        info!("  Sequence: {}", msg.packet.as_ref().unwrap().sequence);
+        info!("  Src: {} / {}", msg.packet.as_ref().unwrap().source_port, msg.packet.as_ref().unwrap().source_channel);
+        info!("  Dst: {} / {}", msg.packet.as_ref().unwrap().destination_port, msg.packet.as_ref().unwrap().destination_channel);
+        let denom = match &self.denom_resolver {
+            Some(resolver) => resolver.display(&parsed.data.denom).await,
+            None => parsed.data.denom.clone(),
+        };
+        info!("  Amount: {} {}", parsed.data.amount, denom);
+        let timeout_height = if parsed.timeout_height.is_empty() { "none" } else { &parsed.timeout_height };
+        info!("  Timeout: height {}, timestamp {}", timeout_height, parsed.timeout_timestamp);
+        info!("  Signer: {}", msg.signer);
+
+        // Nothing is broadcast yet, so this is an estimate, not spend; a broadcast path should
+        // count `TxResponse::fee_spent` of committed txs under its own metric
+        let fee = self.gas_price.as_ref().map(|price| price.fee_for(self.gas_estimate));
+        if let Some(fee) = &fee {
+            info!("  Estimated fee: {} ({} gas)", fee, self.gas_estimate);
+            metrics::add_counter(
+                "relay_fee_estimated",
+                &[("relay", &self.relay_name), ("denom", &fee.denom)],
+                fee.amount as f64,
+            );
+        }
+
+        let mut proof = Vec::new();
+        if let Some(backend) = &self.proof_backend {
+            info!("Launching {} proof generation...", backend.name());
+            let zk_start = Instant::now();
+
+            // Runs on the blocking pool; on timeout or shutdown the proof is abandoned
+            // (the thread finishes in the background)
+            let proof_task = async {
+                let input = hex::decode(&job.packet_data_hex).context("Invalid packet data hex")?;
+                proof::generate_packet_proof_async(backend.clone(), input, self.cancel.clone()).await
+            };
+
+            match tokio::time::timeout(self.zk.proof_timeout(), proof_task).await {
+                Ok(Ok(generated)) => {
+                    let zk_time = zk_start.elapsed().as_millis() as f64 / 1000.0;
+                    info!("Proof generated successfully (size: {} bytes, time: {:.3} sec)", generated.len(), zk_time);
+                    proof = generated;
+                }
+                Ok(Err(e)) if e.is::<Cancelled>() => return Err(e),
+                Ok(Err(e)) => {
+                    error!("Proof generation failed: {:?}", e);
+                    metrics::inc_counter("zk_proof_failures_total", &[("relay", &self.relay_name)]);
+                }
+                Err(_) => {
+                    metrics::inc_counter("zk_proof_timeouts_total", &[("relay", &self.relay_name)]);
+                    match self.zk.on_proof_timeout {
+                        ProofTimeoutPolicy::Proceed => {
+                            warn!("Proof timed out after {} ms – proceeding without proof", self.zk.proof_timeout_ms);
+                        }
+                        ProofTimeoutPolicy::Skip => {
+                            warn!("Proof timed out after {} ms – skipping packet {}", self.zk.proof_timeout_ms, parsed.sequence);
+                            return Ok(RelayOutcome::Skipped {
+                                reason: format!("proof timed out after {} ms", self.zk.proof_timeout_ms),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(forwarder) = self.forwarder.as_mut() {
+            let request = RelayRequest::new(&self.relay_name, job, &proof);
+            match forwarder.forward(&request).await? {
+                RelayResponse::Queued => info!("Packet {} forwarded to peer {}", parsed.sequence, forwarder.addr),
+                RelayResponse::Rejected { reason } => {
+                    return Err(anyhow!("Peer {} rejected packet {}: {}", forwarder.addr, parsed.sequence, reason));
+                }
+            }
+        }
+
+        let packet_duration = packet_start.elapsed();
+        let packet_secs = packet_duration.as_secs_f64();
+
+        info!("Packet processing metrics (sequence {}):", parsed.sequence);
+        info!("   Total time: {:.3} sec", packet_secs);
+
+        // Not broadcast yet, so there is no tx hash
+        Ok(RelayOutcome::Relayed { tx_hash: None, fee })
+    }
+}
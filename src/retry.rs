@@ -0,0 +1,46 @@
+use anyhow::Result;
+use std::future::Future;
+use tracing::warn;
+
+use crate::config::{RetryPolicy, RetryStep};
+use crate::error::RelayError;
+use crate::metrics;
+
+/// Whether `error` may go away on its own: timeouts and transport failures do,
+/// an answer from the chain (rejection, missing data) does not
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    if let Some(e) = error.downcast_ref::<RelayError>() {
+        return e.is_retryable();
+    }
+    error.downcast_ref::<tendermint_rpc::Error>().is_some()
+}
+
+/// Runs `call` up to `policy.max_attempts` times, backing off between attempts, as long
+/// as it fails with a transient error. Only for steps that are safe to repeat as a whole
+/// (queries, simulation) – not for broadcasts, which would need the signer's sequence checked.
+pub async fn retry<T, F, Fut>(policy: &RetryPolicy, step: RetryStep, mut call: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                let delay = policy.backoff(attempt);
+                warn!(
+                    "{} failed (attempt {}/{}), retrying in {} ms: {:#}",
+                    step.as_str(),
+                    attempt,
+                    policy.max_attempts,
+                    delay.as_millis(),
+                    e
+                );
+                metrics::inc_counter("relay_step_retries_total", &[("step", step.as_str())]);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
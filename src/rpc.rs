@@ -0,0 +1,395 @@
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use serde_json::Value;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tendermint::block::Height;
+use tendermint_rpc::endpoint::{block, block_results, broadcast};
+use tendermint_rpc::{Client, Error as RpcError, HttpClient, SimpleRequest};
+use tokio::net::UnixStream;
+use tracing::{debug, info, warn};
+
+use crate::error::RelayError;
+use crate::metrics;
+
+/// Default timeout for a single RPC call
+pub const DEFAULT_RPC_TIMEOUT_MS: u64 = 10_000;
+
+/// Runs an RPC call (or a sequence of them) with a deadline. An elapsed deadline is
+/// returned as the retryable `RelayError::Timeout`, so a hung node behaves like a failed call.
+pub async fn with_timeout<T, E, F>(timeout: Duration, call: &str, fut: F) -> Result<T>
+where
+    F: Future<Output = std::result::Result<T, E>>,
+    E: Into<anyhow::Error>,
+{
+    match tokio::time::timeout(timeout, fut).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(_) => Err(RelayError::Timeout(format!("{} after {} ms", call, timeout.as_millis())).into()),
+    }
+}
+
+/// The RPC serves a different chain than configured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainIdMismatch {
+    pub expected: String,
+    pub actual: String,
+}
+
+impl std::fmt::Display for ChainIdMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RPC serves chain '{}', but '{}' is configured", self.actual, self.expected)
+    }
+}
+
+impl std::error::Error for ChainIdMismatch {}
+
+/// Fails with `ChainIdMismatch` unless the node behind `client` serves chain `expected` –
+/// a guard against an RPC URL that points at the wrong network
+pub async fn verify_chain_id<C: Client + Sync>(client: &C, expected: &str) -> Result<()> {
+    let timeout = Duration::from_millis(DEFAULT_RPC_TIMEOUT_MS);
+    let status = with_timeout(timeout, "status", client.status()).await?;
+    let actual = status.node_info.network.as_str();
+    if actual != expected {
+        return Err(ChainIdMismatch {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// How often an `RpcPool` re-measures its endpoints
+pub const RPC_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Failed calls or probes in a row after which a pool endpoint is quarantined
+const QUARANTINE_AFTER_FAILURES: u32 = 3;
+
+/// How long a quarantined endpoint is skipped (unless a probe succeeds earlier)
+const QUARANTINE_DURATION: Duration = Duration::from_secs(60);
+
+/// Tendermint RPC client over HTTP(S) or a Unix domain socket (`unix:///path/to/socket`),
+/// or a latency-aware pool of such endpoints
+#[derive(Clone, Debug)]
+pub enum RpcClient {
+    Http(HttpClient),
+    Unix(UnixSocketClient),
+    Pool(Arc<RpcPool>),
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Result<Self> {
+        match url.strip_prefix("unix://") {
+            Some(path) => Ok(RpcClient::Unix(UnixSocketClient::new(path))),
+            None => Ok(RpcClient::Http(HttpClient::new(url)?)),
+        }
+    }
+}
+
+// HttpClient overrides some endpoints to handle older node versions (compat mode),
+// so those are delegated explicitly instead of going through `perform`.
+#[async_trait]
+impl Client for RpcClient {
+    async fn block<H>(&self, height: H) -> Result<block::Response, RpcError>
+    where
+        H: Into<Height> + Send,
+    {
+        match self {
+            RpcClient::Http(c) => c.block(height).await,
+            RpcClient::Unix(c) => c.block(height).await,
+            RpcClient::Pool(c) => c.block(height).await,
+        }
+    }
+
+    async fn block_results<H>(&self, height: H) -> Result<block_results::Response, RpcError>
+    where
+        H: Into<Height> + Send,
+    {
+        match self {
+            RpcClient::Http(c) => c.block_results(height).await,
+            RpcClient::Unix(c) => c.block_results(height).await,
+            RpcClient::Pool(c) => c.block_results(height).await,
+        }
+    }
+
+    async fn broadcast_tx_commit<T>(&self, tx: T) -> Result<broadcast::tx_commit::Response, RpcError>
+    where
+        T: Into<Vec<u8>> + Send,
+    {
+        match self {
+            RpcClient::Http(c) => c.broadcast_tx_commit(tx).await,
+            RpcClient::Unix(c) => c.broadcast_tx_commit(tx).await,
+            RpcClient::Pool(c) => c.broadcast_tx_commit(tx).await,
+        }
+    }
+
+    async fn perform<R>(&self, request: R) -> Result<R::Output, RpcError>
+    where
+        R: SimpleRequest,
+    {
+        match self {
+            RpcClient::Http(c) => c.perform(request).await,
+            RpcClient::Unix(c) => c.perform(request).await,
+            RpcClient::Pool(c) => c.perform(request).await,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    /// Last successful `abci_info` round trip; unmeasured endpoints rank last
+    latency: Option<Duration>,
+    /// Failed calls or probes in a row
+    failures: u32,
+    quarantined_until: Option<Instant>,
+}
+
+/// Several RPC endpoints of one chain used as a single client. Each call goes to the
+/// healthy endpoint with the lowest measured `abci_info` round trip (list order breaks
+/// ties). An endpoint failing `QUARANTINE_AFTER_FAILURES` times in a row is skipped for
+/// `QUARANTINE_DURATION`, or until a probe reaches it again.
+#[derive(Debug)]
+pub struct RpcPool {
+    /// Label of the pool in logs and metrics (the relay name)
+    name: String,
+    endpoints: Vec<(String, RpcClient)>,
+    health: Mutex<Vec<EndpointHealth>>,
+}
+
+impl RpcPool {
+    pub fn new(name: &str, urls: &[String]) -> Result<Self> {
+        if urls.is_empty() {
+            bail!("RPC pool {} needs at least one endpoint", name);
+        }
+        let endpoints = urls
+            .iter()
+            .map(|url| Ok((url.clone(), RpcClient::new(url)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            name: name.to_string(),
+            health: Mutex::new(endpoints.iter().map(|_| EndpointHealth::default()).collect()),
+            endpoints,
+        })
+    }
+
+    /// Measures the `abci_info` round trip of every endpoint, quarantined ones included
+    pub async fn probe(&self, timeout: Duration) {
+        for (index, (url, client)) in self.endpoints.iter().enumerate() {
+            let started = Instant::now();
+            let reachable = matches!(tokio::time::timeout(timeout, client.abci_info()).await, Ok(Ok(_)));
+            let latency = started.elapsed();
+
+            if reachable {
+                let mut health = self.health.lock().unwrap();
+                let endpoint = &mut health[index];
+                if endpoint.quarantined_until.take().is_some() {
+                    info!("[{}] RPC {} reachable again, quarantine lifted", self.name, url);
+                }
+                endpoint.latency = Some(latency);
+                endpoint.failures = 0;
+                metrics::set_gauge(
+                    "rpc_endpoint_latency_seconds",
+                    &[("pool", &self.name), ("endpoint", url)],
+                    latency.as_secs_f64(),
+                );
+            } else {
+                self.record(index, false);
+            }
+        }
+        self.report_active();
+    }
+
+    /// Re-probes every `interval` until the pool is dropped
+    pub fn spawn_probes(self: &Arc<Self>, interval: Duration, timeout: Duration) {
+        let pool = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(pool) = pool.upgrade() else { return };
+                pool.probe(timeout).await;
+            }
+        });
+    }
+
+    /// URL of the endpoint calls currently go to
+    pub fn active_endpoint(&self) -> &str {
+        &self.endpoints[self.best()].0
+    }
+
+    /// Fastest endpoint out of quarantine; if all are quarantined, the one released first
+    fn best(&self) -> usize {
+        let health = self.health.lock().unwrap();
+        let now = Instant::now();
+        health
+            .iter()
+            .enumerate()
+            .filter(|(_, endpoint)| !endpoint.quarantined_until.is_some_and(|until| until > now))
+            .min_by_key(|(_, endpoint)| endpoint.latency.unwrap_or(Duration::MAX))
+            .or_else(|| health.iter().enumerate().min_by_key(|(_, endpoint)| endpoint.quarantined_until))
+            .map_or(0, |(index, _)| index)
+    }
+
+    fn record(&self, index: usize, ok: bool) {
+        let quarantined = {
+            let mut health = self.health.lock().unwrap();
+            let endpoint = &mut health[index];
+            if ok {
+                endpoint.failures = 0;
+                return;
+            }
+            endpoint.failures += 1;
+            let already = endpoint.quarantined_until.is_some_and(|until| until > Instant::now());
+            if endpoint.failures < QUARANTINE_AFTER_FAILURES || already {
+                return;
+            }
+            endpoint.quarantined_until = Some(Instant::now() + QUARANTINE_DURATION);
+            endpoint.failures
+        };
+        warn!(
+            "[{}] RPC {} failed {} times in a row, quarantined for {} s",
+            self.name,
+            self.endpoints[index].0,
+            quarantined,
+            QUARANTINE_DURATION.as_secs()
+        );
+        self.report_active();
+    }
+
+    /// `rpc_active_endpoint` is 1 for the endpoint calls go to, 0 for the others
+    fn report_active(&self) {
+        let active = self.best();
+        for (index, (url, _)) in self.endpoints.iter().enumerate() {
+            metrics::set_gauge(
+                "rpc_active_endpoint",
+                &[("pool", &self.name), ("endpoint", url)],
+                if index == active { 1.0 } else { 0.0 },
+            );
+        }
+    }
+
+    fn pick(&self) -> (usize, &RpcClient) {
+        let index = self.best();
+        (index, &self.endpoints[index].1)
+    }
+}
+
+#[async_trait]
+impl Client for RpcPool {
+    async fn block<H>(&self, height: H) -> Result<block::Response, RpcError>
+    where
+        H: Into<Height> + Send,
+    {
+        let (index, client) = self.pick();
+        let result = client.block(height).await;
+        self.record(index, result.is_ok());
+        result
+    }
+
+    async fn block_results<H>(&self, height: H) -> Result<block_results::Response, RpcError>
+    where
+        H: Into<Height> + Send,
+    {
+        let (index, client) = self.pick();
+        let result = client.block_results(height).await;
+        self.record(index, result.is_ok());
+        result
+    }
+
+    async fn broadcast_tx_commit<T>(&self, tx: T) -> Result<broadcast::tx_commit::Response, RpcError>
+    where
+        T: Into<Vec<u8>> + Send,
+    {
+        let (index, client) = self.pick();
+        let result = client.broadcast_tx_commit(tx).await;
+        self.record(index, result.is_ok());
+        result
+    }
+
+    async fn perform<R>(&self, request: R) -> Result<R::Output, RpcError>
+    where
+        R: SimpleRequest,
+    {
+        let (index, client) = self.pick();
+        let result = client.perform(request).await;
+        self.record(index, result.is_ok());
+        result
+    }
+}
+
+/// Calls a JSON-RPC method the tendermint-rpc client does not cover (e.g. `unconfirmed_txs`)
+/// on the node at `url` (http(s):// or unix://) and returns its `result`
+pub async fn raw_call(url: &str, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+    let body = serde_json::json!({ "jsonrpc": "2.0", "id": 0, "method": method, "params": params });
+    let call = async {
+        let response = match url.strip_prefix("unix://") {
+            Some(path) => UnixSocketClient::new(path).post(body.to_string()).await?,
+            None => reqwest::Client::new().post(url).json(&body).send().await?.bytes().await?,
+        };
+        anyhow::Ok(serde_json::from_slice::<Value>(&response)?)
+    };
+    let mut response = match tokio::time::timeout(timeout, call).await {
+        Ok(response) => response?,
+        Err(_) => return Err(RelayError::Timeout(format!("{} after {} ms", method, timeout.as_millis())).into()),
+    };
+
+    if !response["error"].is_null() {
+        bail!("{} failed: {}", method, response["error"]);
+    }
+    match response.get_mut("result") {
+        Some(result) => Ok(result.take()),
+        None => Err(anyhow!("{} returned no result", method)),
+    }
+}
+
+/// JSON-RPC over HTTP/1.1 on a Unix domain socket (sidecar deployments)
+#[derive(Clone, Debug)]
+pub struct UnixSocketClient {
+    path: PathBuf,
+}
+
+impl UnixSocketClient {
+    pub fn new(path: &str) -> Self {
+        Self { path: PathBuf::from(path) }
+    }
+
+    /// POSTs a JSON-RPC body on a fresh connection and returns the response body
+    async fn post(&self, body: String) -> Result<Bytes> {
+        let stream = UnixStream::connect(&self.path).await?;
+        let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                debug!("Unix socket RPC connection closed with error: {}", e);
+            }
+        });
+
+        let request = hyper::Request::post("/")
+            .header(hyper::header::HOST, "localhost")
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(body)))?;
+
+        let response = sender.send_request(request).await?;
+        Ok(response.into_body().collect().await?.to_bytes())
+    }
+}
+
+#[async_trait]
+impl Client for UnixSocketClient {
+    async fn perform<R>(&self, request: R) -> Result<R::Output, RpcError>
+    where
+        R: SimpleRequest,
+    {
+        let body = <R as tendermint_rpc::Request>::into_json(request);
+        let response = self
+            .post(body)
+            .await
+            .map_err(|e| RpcError::client_internal(format!("{}: {}", self.path.display(), e)))?;
+
+        <R::Response as tendermint_rpc::Response>::from_string(&response).map(Into::into)
+    }
+}
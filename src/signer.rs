@@ -0,0 +1,339 @@
+use anyhow::{anyhow, bail, Context, Result};
+use bech32::{Bech32, Hrp};
+use ibc_proto::cosmos::auth::v1beta1::{BaseAccount, QueryAccountRequest, QueryAccountResponse};
+use k256::ecdsa::signature::Signer as _;
+use prost::Message;
+use ripemd::Ripemd160;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tendermint_rpc::Client;
+use tracing::info;
+
+/// Account that signs relay transactions on the destination chain
+pub trait Signer: Send + Sync {
+    /// Bech32 account address
+    fn address(&self) -> &str;
+
+    /// Signs serialized sign bytes (SignDoc)
+    fn sign(&self, sign_bytes: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Signer known only by its address – used until key material is loaded
+pub struct AddressSigner {
+    address: String,
+}
+
+impl AddressSigner {
+    pub fn new(address: &str) -> Self {
+        Self { address: address.to_string() }
+    }
+}
+
+impl Signer for AddressSigner {
+    fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn sign(&self, _sign_bytes: &[u8]) -> Result<Vec<u8>> {
+        bail!("No signing key loaded for {}", self.address)
+    }
+}
+
+/// HD path used to derive secp256k1 keys from a mnemonic when `hd_path` is unset (Cosmos coin type 118)
+pub const DEFAULT_HD_PATH: &str = "m/44'/118'/0'/0/0";
+
+/// Account key algorithm
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyType {
+    /// Standard Cosmos SDK account keys
+    #[default]
+    Secp256k1,
+    Ed25519,
+}
+
+/// Private key of an account, whatever its algorithm and the encoding it was loaded from
+#[derive(Clone)]
+pub enum SigningKey {
+    Secp256k1(k256::ecdsa::SigningKey),
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl SigningKey {
+    /// Parses `input` as a BIP39 mnemonic (derived along `hd_path`), a bech32 `...priv1...`
+    /// key or 32 hex-encoded bytes. Mnemonics are only supported for secp256k1: Cosmos
+    /// defines no HD derivation for ed25519 account keys.
+    pub fn parse(input: &str, key_type: KeyType, hd_path: &str) -> Result<Self> {
+        let input = input.trim();
+
+        if input.split_whitespace().count() > 1 {
+            if key_type == KeyType::Ed25519 {
+                bail!("Mnemonic keys are only supported for key_type = \"secp256k1\"");
+            }
+            let mnemonic = bip32::Mnemonic::new(input, bip32::Language::English)
+                .map_err(|e| anyhow!("Invalid mnemonic: {}", e))?;
+            let path: bip32::DerivationPath = hd_path.parse().map_err(|e| anyhow!("Invalid hd_path '{}': {}", hd_path, e))?;
+            let xprv = bip32::XPrv::derive_from_path(mnemonic.to_seed(""), &path)
+                .map_err(|e| anyhow!("Key derivation along {} failed: {}", hd_path, e))?;
+            return Ok(SigningKey::Secp256k1(xprv.private_key().clone()));
+        }
+
+        let bytes = match bech32::decode(input) {
+            Ok((hrp, bytes)) if hrp.as_str().contains("priv") => bytes,
+            Ok((hrp, _)) => bail!("Bech32 value with prefix '{}' is not a private key", hrp.as_str()),
+            Err(_) => hex::decode(input.trim_start_matches("0x"))
+                .map_err(|_| anyhow!("Private key is neither a mnemonic, bech32 nor hex"))?,
+        };
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| anyhow!("Private key must be 32 bytes, got {}", bytes.len()))?;
+
+        Ok(match key_type {
+            KeyType::Secp256k1 => SigningKey::Secp256k1(
+                k256::ecdsa::SigningKey::from_bytes(&bytes.into()).map_err(|_| anyhow!("Invalid secp256k1 private key"))?,
+            ),
+            KeyType::Ed25519 => SigningKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(&bytes)),
+        })
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            SigningKey::Secp256k1(_) => KeyType::Secp256k1,
+            SigningKey::Ed25519(_) => KeyType::Ed25519,
+        }
+    }
+
+    /// Public key as put in `SignerInfo`: compressed SEC1 (33 bytes) or raw ed25519 (32 bytes)
+    pub fn public_key(&self) -> Vec<u8> {
+        match self {
+            SigningKey::Secp256k1(key) => key.verifying_key().to_sec1_bytes().to_vec(),
+            SigningKey::Ed25519(key) => key.verifying_key().to_bytes().to_vec(),
+        }
+    }
+
+    /// Bech32 account address: RIPEMD160(SHA256(pubkey)) for secp256k1,
+    /// the first 20 bytes of SHA256(pubkey) for ed25519
+    pub fn address(&self, prefix: &str) -> Result<String> {
+        let sha = Sha256::digest(self.public_key());
+        let hash = match self {
+            SigningKey::Secp256k1(_) => Ripemd160::digest(sha).to_vec(),
+            SigningKey::Ed25519(_) => sha[..20].to_vec(),
+        };
+        let hrp = Hrp::parse(prefix).map_err(|e| anyhow!("Invalid account prefix '{}': {}", prefix, e))?;
+        bech32::encode::<Bech32>(hrp, &hash).map_err(|e| anyhow!("Failed to encode address: {}", e))
+    }
+
+    /// Signature over `sign_bytes` (64 bytes; secp256k1 signs their SHA-256 digest)
+    pub fn sign(&self, sign_bytes: &[u8]) -> Vec<u8> {
+        match self {
+            SigningKey::Secp256k1(key) => {
+                let signature: k256::ecdsa::Signature = key.sign(sign_bytes);
+                signature.to_bytes().to_vec()
+            }
+            SigningKey::Ed25519(key) => ed25519_dalek::Signer::sign(key, sign_bytes).to_bytes().to_vec(),
+        }
+    }
+}
+
+impl std::fmt::Debug for SigningKey {
+    /// Never prints key material
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SigningKey({:?})", self.key_type())
+    }
+}
+
+/// Signer backed by a loaded private key
+pub struct KeySigner {
+    address: String,
+    key: SigningKey,
+}
+
+impl KeySigner {
+    /// Signer for `key`, with its address under `prefix`
+    pub fn new(key: SigningKey, prefix: &str) -> Result<Self> {
+        Ok(Self { address: key.address(prefix)?, key })
+    }
+
+    pub fn key(&self) -> &SigningKey {
+        &self.key
+    }
+}
+
+impl Signer for KeySigner {
+    fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn sign(&self, sign_bytes: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.key.sign(sign_bytes))
+    }
+}
+
+/// Hands out account sequence numbers locally so several txs from the same
+/// account can be in flight without waiting for each commit.
+#[derive(Debug, Default)]
+pub struct SequenceManager {
+    next: AtomicU64,
+}
+
+impl SequenceManager {
+    pub fn new(start: u64) -> Self {
+        Self { next: AtomicU64::new(start) }
+    }
+
+    /// Reserves the next sequence number
+    pub fn next(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Sequence that the next reservation will return
+    pub fn peek(&self) -> u64 {
+        self.next.load(Ordering::SeqCst)
+    }
+
+    /// Resynchronizes with the chain (e.g. after an account sequence mismatch)
+    pub fn reset(&self, sequence: u64) {
+        self.next.store(sequence, Ordering::SeqCst);
+    }
+}
+
+struct SignerSlot {
+    signer: Arc<dyn Signer>,
+    sequences: SequenceManager,
+}
+
+/// Pool of destination signers; broadcasts are spread round-robin so several
+/// transactions can be in flight at once.
+pub struct SignerPool {
+    slots: Vec<SignerSlot>,
+    cursor: AtomicUsize,
+}
+
+impl SignerPool {
+    pub fn new(signers: Vec<Arc<dyn Signer>>) -> Result<Self> {
+        if signers.is_empty() {
+            bail!("Signer pool requires at least one signer");
+        }
+
+        let slots = signers
+            .into_iter()
+            .map(|signer| SignerSlot { signer, sequences: SequenceManager::default() })
+            .collect();
+
+        Ok(Self { slots, cursor: AtomicUsize::new(0) })
+    }
+
+    /// Builds an address-only pool from configured signer addresses
+    pub fn from_addresses(addresses: &[String]) -> Result<Self> {
+        Self::new(
+            addresses
+                .iter()
+                .map(|a| Arc::new(AddressSigner::new(a)) as Arc<dyn Signer>)
+                .collect(),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Picks the signer for one relay attempt. Ordered channels are always pinned to the
+    /// same signer so their packets are submitted in sequence order; unordered channels
+    /// rotate round-robin. No sequence is reserved: attempts that end without a signed tx
+    /// must not move the local sequence away from the chain's.
+    pub fn select(&self, channel_id: &str, ordered: bool) -> Arc<dyn Signer> {
+        let index = if ordered {
+            let mut hasher = DefaultHasher::new();
+            channel_id.hash(&mut hasher);
+            (hasher.finish() % self.slots.len() as u64) as usize
+        } else {
+            self.cursor.fetch_add(1, Ordering::Relaxed) % self.slots.len()
+        };
+
+        self.slots[index].signer.clone()
+    }
+
+    /// Like `select`, but always the signer at `slot` (modulo the pool size), so each
+    /// worker of a relay worker pool broadcasts from its own account
+    pub fn select_slot(&self, slot: usize) -> Arc<dyn Signer> {
+        self.slots[slot % self.slots.len()].signer.clone()
+    }
+
+    /// Reserves the next sequence of `address` for a tx that is being signed now
+    pub fn reserve_sequence(&self, address: &str) -> Option<u64> {
+        self.slot(address).map(|slot| slot.sequences.next())
+    }
+
+    /// Sequence the next tx of `address` will use, without reserving it
+    pub fn peek_sequence(&self, address: &str) -> Option<u64> {
+        self.slot(address).map(|slot| slot.sequences.peek())
+    }
+
+    fn slot(&self, address: &str) -> Option<&SignerSlot> {
+        self.slots.iter().find(|s| s.signer.address() == address)
+    }
+
+    /// Resets the local sequence of one signer (e.g. after a sequence mismatch error)
+    pub fn reset_sequence(&self, address: &str, sequence: u64) {
+        if let Some(slot) = self.slot(address) {
+            slot.sequences.reset(sequence);
+        }
+    }
+
+    /// Loads the current on-chain sequence of every signer
+    pub async fn sync_sequences<C: Client + Sync>(&self, client: &C) -> Result<()> {
+        for slot in &self.slots {
+            let (_, sequence) = query_account(client, slot.signer.address()).await?;
+            slot.sequences.reset(sequence);
+            info!("Signer {} synced at sequence {}", slot.signer.address(), sequence);
+        }
+        Ok(())
+    }
+}
+
+/// Checks that `address` is valid bech32 with the expected account prefix
+pub fn validate_address(address: &str, prefix: &str) -> Result<()> {
+    let (hrp, _) = bech32::decode(address).map_err(|e| anyhow!("Invalid address {}: {}", address, e))?;
+    if hrp.as_str() != prefix {
+        bail!("Address {} has prefix '{}', expected '{}'", address, hrp.as_str(), prefix);
+    }
+    Ok(())
+}
+
+/// Queries `(account_number, sequence)` for an address via the auth module
+pub async fn query_account<C: Client + Sync>(client: &C, address: &str) -> Result<(u64, u64)> {
+    let request = QueryAccountRequest { address: address.to_string() };
+
+    let res = client
+        .abci_query(
+            Some("/cosmos.auth.v1beta1.Query/Account".to_string()),
+            request.encode_to_vec(),
+            None,
+            false,
+        )
+        .await
+        .context(format!("Failed to query account {}", address))?;
+
+    if res.code.is_err() {
+        bail!("Account query for {} failed: {}", address, res.log);
+    }
+
+    let account = QueryAccountResponse::decode(res.value.as_slice())?
+        .account
+        .ok_or_else(|| anyhow!("Account {} not found", address))?;
+
+    if account.type_url != "/cosmos.auth.v1beta1.BaseAccount" {
+        bail!("Unsupported account type for {}: {}", address, account.type_url);
+    }
+
+    let base = BaseAccount::decode(account.value.as_slice())?;
+    Ok((base.account_number, base.sequence))
+}
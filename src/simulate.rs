@@ -0,0 +1,152 @@
+use anyhow::{anyhow, bail, Result};
+use ibc_proto::cosmos::tx::signing::v1beta1::SignMode;
+use ibc_proto::cosmos::tx::v1beta1::{
+    mode_info, AuthInfo, ModeInfo, SignerInfo, SimulateRequest, SimulateResponse, Tx, TxBody,
+};
+use ibc_proto::google::protobuf::Any;
+use ibc_proto::ibc::core::channel::v1::MsgRecvPacket;
+use prost::Message;
+use serde::Serialize;
+use std::time::Duration;
+use tendermint_rpc::Client;
+
+use crate::rpc::with_timeout;
+use crate::signer::query_account;
+use crate::submit::FeePayment;
+
+/// What relaying one packet would do (`IbcPoller::simulate_relay`); nothing is broadcast
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SimResult {
+    pub sequence: u64,
+    /// Source height the packet was sent at
+    pub send_height: u64,
+    /// Source height of the commitment proof; unset once the commitment is deleted
+    pub proof_height: Option<u64>,
+    /// Size of the encoded commitment proof
+    pub proof_bytes: usize,
+    pub already_received: bool,
+    pub timed_out: bool,
+    pub gas_wanted: Option<u64>,
+    pub gas_used: Option<u64>,
+    /// Fee for `gas_used` at the relay's `gas_price`
+    pub fee: Option<String>,
+    /// Why the relay would fail (first problem found); unset if it would succeed
+    pub error: Option<String>,
+}
+
+impl SimResult {
+    pub fn would_succeed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Queries the packet commitment on the source with a proof: `(MerkleProof bytes, proof height)`.
+/// `None` once the commitment is deleted (packet acknowledged or timed out).
+pub async fn query_commitment_proof<C: Client + Sync>(
+    client: &C,
+    port_id: &str,
+    channel_id: &str,
+    sequence: u64,
+    rpc_timeout: Duration,
+) -> Result<Option<(Vec<u8>, u64)>> {
+    let key = format!("commitments/ports/{}/channels/{}/sequences/{}", port_id, channel_id, sequence);
+    query_ibc_proof(client, key, rpc_timeout).await
+}
+
+/// Queries the acknowledgement commitment of a received packet on the destination with a
+/// proof: `(MerkleProof bytes, proof height)`. `None` until the acknowledgement is written.
+pub async fn query_ack_proof<C: Client + Sync>(
+    client: &C,
+    port_id: &str,
+    channel_id: &str,
+    sequence: u64,
+    rpc_timeout: Duration,
+) -> Result<Option<(Vec<u8>, u64)>> {
+    let key = format!("acks/ports/{}/channels/{}/sequences/{}", port_id, channel_id, sequence);
+    query_ibc_proof(client, key, rpc_timeout).await
+}
+
+/// Proven query of `key` in the IBC store at the latest height; `None` if it is unset
+async fn query_ibc_proof<C: Client + Sync>(client: &C, key: String, rpc_timeout: Duration) -> Result<Option<(Vec<u8>, u64)>> {
+    let res = with_timeout(
+        rpc_timeout,
+        "abci_query",
+        client.abci_query(Some("store/ibc/key".to_string()), key.into_bytes(), None, true),
+    )
+    .await?;
+
+    if res.code.is_err() {
+        bail!("IBC store query failed: {}", res.log);
+    }
+    if res.value.is_empty() {
+        return Ok(None);
+    }
+
+    // Each proof op carries an encoded ics23 CommitmentProof – exactly the repeated
+    // `proofs` field of an IBC MerkleProof
+    let mut proof = Vec::new();
+    for op in res.proof.map(|proof| proof.ops).unwrap_or_default() {
+        prost::encoding::bytes::encode(1, &op.data, &mut proof);
+    }
+    Ok(Some((proof, res.height.value())))
+}
+
+/// Runs `msg` through the destination's tx simulation: `(gas_wanted, gas_used)`.
+/// An `Err` is the reason the chain would reject it (e.g. an invalid proof, an
+/// outdated client or a missing fee grant of `fee_payment`).
+pub async fn simulate_recv_packet<C: Client + Sync>(
+    client: &C,
+    msg: &MsgRecvPacket,
+    fee_payment: &FeePayment,
+    rpc_timeout: Duration,
+) -> Result<(u64, u64)> {
+    // Simulation skips signature checks but still compares the signer's sequence
+    let (_, sequence) = query_account(client, &msg.signer).await?;
+
+    let tx = Tx {
+        body: Some(TxBody {
+            messages: vec![Any {
+                type_url: "/ibc.core.channel.v1.MsgRecvPacket".to_string(),
+                value: msg.encode_to_vec(),
+            }],
+            ..Default::default()
+        }),
+        auth_info: Some(AuthInfo {
+            signer_infos: vec![SignerInfo {
+                public_key: None,
+                mode_info: Some(ModeInfo {
+                    sum: Some(mode_info::Sum::Single(mode_info::Single { mode: SignMode::Direct as i32 })),
+                }),
+                sequence,
+            }],
+            fee: Some(fee_payment.tx_fee(None, 0)),
+            ..Default::default()
+        }),
+        signatures: vec![Vec::new()],
+    };
+    let request = SimulateRequest {
+        tx_bytes: tx.encode_to_vec(),
+        ..Default::default()
+    };
+
+    let res = with_timeout(
+        rpc_timeout,
+        "abci_query",
+        client.abci_query(
+            Some("/cosmos.tx.v1beta1.Service/Simulate".to_string()),
+            request.encode_to_vec(),
+            None,
+            false,
+        ),
+    )
+    .await?;
+
+    if res.code.is_err() {
+        bail!("Simulation failed: {}", res.log);
+    }
+
+    let gas = SimulateResponse::decode(res.value.as_slice())?
+        .gas_info
+        .ok_or_else(|| anyhow!("Simulation returned no gas info"))?;
+    Ok((gas.gas_wanted, gas.gas_used))
+}
@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::config::EventsConfig;
+use crate::metrics;
+use crate::relay::{Direction, MsgType, RelayJob, RelayOutcome};
+
+/// Events buffered for the broker; further events are dropped while it is full
+const EVENT_BUFFER_CAPACITY: usize = 10_000;
+
+/// Outcome of one relay attempt as published to `[events]`
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayEvent {
+    pub relay: String,
+    pub sequence: u64,
+    pub src_port: String,
+    pub src_channel: String,
+    pub dst_port: String,
+    pub dst_channel: String,
+    pub direction: Direction,
+    pub msg_type: MsgType,
+    pub denom: String,
+    pub amount: String,
+    /// Source height the packet was detected at
+    pub height: u64,
+    /// `relayed`, `skipped` or `failed`
+    pub outcome: &'static str,
+    pub tx_hash: Option<String>,
+    pub fee: Option<String>,
+    /// Why the packet was skipped or failed
+    pub reason: Option<String>,
+    /// Unix ms
+    pub timestamp_ms: u64,
+}
+
+impl RelayEvent {
+    pub fn new(relay: &str, job: &RelayJob, outcome: &RelayOutcome) -> Self {
+        let packet = &job.packet;
+        let (kind, tx_hash, fee, reason) = match outcome {
+            RelayOutcome::Relayed { tx_hash, fee } => {
+                ("relayed", tx_hash.clone(), fee.as_ref().map(ToString::to_string), None)
+            }
+            RelayOutcome::Skipped { reason } => ("skipped", None, None, Some(reason.clone())),
+            RelayOutcome::Failed { error } => ("failed", None, None, Some(error.clone())),
+        };
+        Self {
+            relay: relay.to_string(),
+            sequence: packet.sequence,
+            src_port: packet.src_port.clone(),
+            src_channel: packet.src_channel.clone(),
+            dst_port: packet.dst_port.clone(),
+            dst_channel: packet.dst_channel.clone(),
+            direction: job.direction,
+            msg_type: job.msg_type,
+            denom: packet.data.denom.clone(),
+            amount: packet.data.amount.clone(),
+            height: job.height,
+            outcome: kind,
+            tx_hash,
+            fee,
+            reason,
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+        }
+    }
+}
+
+/// Receives every relay outcome. `publish` must not block the relay worker –
+/// implementations deliver in the background and drop events they cannot deliver.
+pub trait EventSink: Send + Sync {
+    fn publish(&self, event: &RelayEvent);
+}
+
+/// Publishes each event as JSON to a NATS subject. Delivery is fire-and-forget:
+/// events that do not fit the buffer or fail to publish are counted in
+/// `event_sink_dropped_total` and otherwise ignored.
+pub struct NatsSink {
+    events: mpsc::Sender<Vec<u8>>,
+}
+
+impl NatsSink {
+    /// Starts the publisher task; the connection is (re)established in the background
+    pub fn new(url: &str, subject: &str) -> Self {
+        let (events, mut rx) = mpsc::channel::<Vec<u8>>(EVENT_BUFFER_CAPACITY);
+        let url = url.to_string();
+        let subject = subject.to_string();
+
+        tokio::spawn(async move {
+            let client = match async_nats::ConnectOptions::new().retry_on_initial_connect().connect(&url).await {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("Event sink disabled, cannot connect to NATS at {}: {}", url, e);
+                    return;
+                }
+            };
+            info!("Publishing relay events to NATS subject {}", subject);
+
+            while let Some(payload) = rx.recv().await {
+                if let Err(e) = client.publish(subject.clone(), payload.into()).await {
+                    debug!("Failed to publish relay event: {}", e);
+                    metrics::inc_counter("event_sink_dropped_total", &[("sink", "nats")]);
+                }
+            }
+        });
+
+        Self { events }
+    }
+}
+
+impl EventSink for NatsSink {
+    fn publish(&self, event: &RelayEvent) {
+        let payload = match serde_json::to_vec(event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                debug!("Failed to encode relay event: {}", e);
+                return;
+            }
+        };
+        if self.events.try_send(payload).is_err() {
+            metrics::inc_counter("event_sink_dropped_total", &[("sink", "nats")]);
+        }
+    }
+}
+
+/// Builds the configured event sink (None if `[events]` is not configured)
+pub fn from_config(config: &EventsConfig) -> Option<Arc<dyn EventSink>> {
+    config
+        .nats_url
+        .as_ref()
+        .map(|url| Arc::new(NatsSink::new(url, &config.subject)) as Arc<dyn EventSink>)
+}
@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::metrics;
+use crate::relay::{RelayOutcome, RelayRequest};
+
+/// Snapshot of a poller, written on shutdown and restored on startup so a restart
+/// neither skips blocks nor loses or repeats packets
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PollerState {
+    pub relay: String,
+    pub channel_id: String,
+    /// Last source height whose packets were all handed to the worker
+    pub last_height: u64,
+    /// Packets handed to the worker but not finished; re-queued on restore
+    pub in_flight: Vec<RelayRequest>,
+    /// Sequences already relayed (or skipped); not queued again
+    pub relayed: Vec<u64>,
+    /// Highest sequence evicted from the tracker (see `PacketTracker`)
+    #[serde(default)]
+    pub evicted_up_to: u64,
+}
+
+/// Writes the snapshot atomically (temporary file + rename)
+pub fn save_state(path: &Path, state: &PollerState) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).context(format!("Failed to create state directory {}", dir.display()))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(state)?)
+        .context(format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).context(format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Reads a snapshot; `None` if none was saved yet
+pub fn load_state(path: &Path) -> Result<Option<PollerState>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read(path).context(format!("Failed to read {}", path.display()))?;
+    let state = serde_json::from_slice(&content).context(format!("Invalid poller state in {}", path.display()))?;
+    Ok(Some(state))
+}
+
+/// Result of `PacketTracker::begin`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackStatus {
+    /// Not seen before – relay it
+    New,
+    /// Already in flight or relayed – skip it
+    Duplicate,
+    /// At or below an evicted sequence, so the tracker cannot tell; relay only
+    /// after the destination confirms it has no receipt
+    Unknown,
+}
+
+/// Relay progress shared by a poller and its worker, keyed by packet sequence:
+/// packets handed to the worker but not finished (in flight) and sequences already relayed.
+///
+/// Both sets hold at most `capacity` entries. Eviction is lowest sequence first – sequences
+/// grow over time, so this drops the oldest packets. The highest evicted sequence is
+/// remembered; anything at or below it is reported as `Unknown` instead of `New`.
+#[derive(Debug)]
+pub struct PacketTracker {
+    relay: String,
+    capacity: usize,
+    inner: Mutex<TrackerState>,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    in_flight: BTreeMap<u64, RelayRequest>,
+    relayed: BTreeSet<u64>,
+    evicted_up_to: u64,
+}
+
+impl TrackerState {
+    fn evict(&mut self, capacity: usize) {
+        while self.in_flight.len() > capacity {
+            if let Some((sequence, _)) = self.in_flight.pop_first() {
+                self.evicted_up_to = self.evicted_up_to.max(sequence);
+            }
+        }
+        while self.relayed.len() > capacity {
+            if let Some(sequence) = self.relayed.pop_first() {
+                self.evicted_up_to = self.evicted_up_to.max(sequence);
+            }
+        }
+    }
+}
+
+impl PacketTracker {
+    pub fn new(relay: &str, capacity: usize) -> Self {
+        Self {
+            relay: relay.to_string(),
+            capacity: capacity.max(1),
+            inner: Mutex::new(TrackerState::default()),
+        }
+    }
+
+    /// Marks a packet as in flight unless it is a known duplicate
+    pub fn begin(&self, request: RelayRequest) -> TrackStatus {
+        let mut state = self.inner.lock().unwrap();
+        let sequence = request.sequence;
+        if state.relayed.contains(&sequence) || state.in_flight.contains_key(&sequence) {
+            return TrackStatus::Duplicate;
+        }
+
+        let status = if sequence <= state.evicted_up_to {
+            TrackStatus::Unknown
+        } else {
+            TrackStatus::New
+        };
+        state.in_flight.insert(sequence, request);
+        state.evict(self.capacity);
+        self.record_sizes(&state);
+        status
+    }
+
+    /// Records a finished attempt. Relayed and skipped packets count as done;
+    /// failed ones are forgotten so they can be picked up again.
+    pub fn finish(&self, sequence: u64, outcome: &RelayOutcome) {
+        let mut state = self.inner.lock().unwrap();
+        state.in_flight.remove(&sequence);
+        if !matches!(outcome, RelayOutcome::Failed { .. }) {
+            state.relayed.insert(sequence);
+            state.evict(self.capacity);
+        }
+        self.record_sizes(&state);
+    }
+
+    /// Copies in-flight requests, relayed sequences and the eviction mark into `snapshot`
+    pub fn fill(&self, snapshot: &mut PollerState) {
+        let state = self.inner.lock().unwrap();
+        snapshot.in_flight = state.in_flight.values().cloned().collect();
+        snapshot.relayed = state.relayed.iter().copied().collect();
+        snapshot.evicted_up_to = state.evicted_up_to;
+    }
+
+    /// Restores relayed sequences and the eviction mark; in-flight packets are
+    /// re-queued through `begin`
+    pub fn restore(&self, snapshot: &PollerState) {
+        let mut state = self.inner.lock().unwrap();
+        state.relayed.extend(snapshot.relayed.iter().copied());
+        state.evicted_up_to = state.evicted_up_to.max(snapshot.evicted_up_to);
+        state.evict(self.capacity);
+        self.record_sizes(&state);
+    }
+
+    fn record_sizes(&self, state: &TrackerState) {
+        metrics::set_gauge(
+            "relay_tracker_entries",
+            &[("relay", &self.relay), ("kind", "in_flight")],
+            state.in_flight.len() as f64,
+        );
+        metrics::set_gauge(
+            "relay_tracker_entries",
+            &[("relay", &self.relay), ("kind", "relayed")],
+            state.relayed.len() as f64,
+        );
+    }
+}
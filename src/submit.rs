@@ -0,0 +1,217 @@
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use ibc_proto::cosmos::base::v1beta1::Coin;
+use ibc_proto::cosmos::tx::v1beta1::{Fee as ProtoFee, TxBody, TxRaw};
+use ibc_proto::ibc::core::channel::v1::{MsgRecvPacket, Packet};
+use prost::Message;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use tendermint_rpc::Client;
+use tracing::{info, warn};
+
+use crate::error::{RelayError, TxError};
+use crate::rpc;
+
+/// Pending transactions `is_in_mempool` looks at
+const MEMPOOL_SCAN_LIMIT: u32 = 100;
+
+/// How a signed transaction is handed to the destination node
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BroadcastMode {
+    /// Return after CheckTx (mempool admission)
+    #[default]
+    Sync,
+    /// Wait until the transaction is included in a block (DeliverTx result)
+    Commit,
+}
+
+/// Successful broadcast result
+#[derive(Debug, Clone)]
+pub struct TxResponse {
+    pub hash: String,
+    /// Inclusion height (only known in `commit` mode)
+    pub height: Option<u64>,
+    pub gas_wanted: i64,
+    pub gas_used: i64,
+}
+
+impl TxResponse {
+    /// Fee actually charged, from `gas_used`. Only known for `commit` broadcasts.
+    pub fn fee_spent(&self, price: &GasPrice) -> Option<Fee> {
+        self.height.map(|_| price.fee_for(self.gas_used.max(0) as u64))
+    }
+}
+
+/// Gas price in the fee denom, e.g. `0.025uosmo`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasPrice {
+    pub amount: f64,
+    pub denom: String,
+}
+
+impl GasPrice {
+    /// Fee for `gas` units, rounded up like the SDK does
+    pub fn fee_for(&self, gas: u64) -> Fee {
+        Fee {
+            amount: (self.amount * gas as f64).ceil() as u128,
+            denom: self.denom.clone(),
+        }
+    }
+}
+
+impl FromStr for GasPrice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(|| anyhow!("gas price '{}' has no denom", s))?;
+        let (amount, denom) = s.split_at(split);
+        let amount: f64 = amount
+            .parse()
+            .map_err(|_| anyhow!("gas price '{}' has an invalid amount", s))?;
+
+        Ok(GasPrice {
+            amount,
+            denom: denom.to_string(),
+        })
+    }
+}
+
+/// Fee amount in the smallest unit of `denom`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fee {
+    pub amount: u128,
+    pub denom: String,
+}
+
+impl fmt::Display for Fee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.denom)
+    }
+}
+
+/// Accounts other than the signer that pay for relay transactions: a fee `granter`
+/// whose fee grant to the signer covers the fee, or a `payer` (who must also sign)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeePayment {
+    pub granter: Option<String>,
+    pub payer: Option<String>,
+}
+
+impl FeePayment {
+    /// The tx `Fee` for `amount` (none: zero fee, e.g. for simulation) and `gas_limit`
+    pub fn tx_fee(&self, amount: Option<&Fee>, gas_limit: u64) -> ProtoFee {
+        ProtoFee {
+            amount: amount
+                .map(|fee| Coin { denom: fee.denom.clone(), amount: fee.amount.to_string() })
+                .into_iter()
+                .collect(),
+            gas_limit,
+            payer: self.payer.clone().unwrap_or_default(),
+            granter: self.granter.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Broadcasts raw tx bytes and parses the node response.
+/// A non-zero code from CheckTx or DeliverTx is returned as `RelayError::Tx`,
+/// transport failures as `RelayError::Submission`.
+pub async fn broadcast_tx<C>(client: &C, tx_bytes: Vec<u8>, mode: BroadcastMode) -> Result<TxResponse, RelayError>
+where
+    C: Client + Sync,
+{
+    match mode {
+        BroadcastMode::Sync => {
+            let res = client
+                .broadcast_tx_sync(tx_bytes)
+                .await
+                .map_err(|e| RelayError::Submission(e.to_string()))?;
+
+            if res.code.is_err() {
+                let err = TxError {
+                    code: res.code.value(),
+                    codespace: res.codespace,
+                    raw_log: res.log,
+                };
+                warn!("Tx {} rejected in CheckTx: {}", res.hash, err);
+                return Err(err.into());
+            }
+
+            info!("Tx {} accepted into mempool", res.hash);
+            Ok(TxResponse {
+                hash: res.hash.to_string(),
+                height: None,
+                gas_wanted: 0,
+                gas_used: 0,
+            })
+        }
+        BroadcastMode::Commit => {
+            let res = client
+                .broadcast_tx_commit(tx_bytes)
+                .await
+                .map_err(|e| RelayError::Submission(e.to_string()))?;
+
+            if res.check_tx.code.is_err() {
+                let err = TxError {
+                    code: res.check_tx.code.value(),
+                    codespace: res.check_tx.codespace,
+                    raw_log: res.check_tx.log,
+                };
+                warn!("Tx {} rejected in CheckTx: {}", res.hash, err);
+                return Err(err.into());
+            }
+
+            if res.tx_result.code.is_err() {
+                let err = TxError {
+                    code: res.tx_result.code.value(),
+                    codespace: res.tx_result.codespace,
+                    raw_log: res.tx_result.log,
+                };
+                warn!("Tx {} failed in DeliverTx at height {}: {}", res.hash, res.height, err);
+                return Err(err.into());
+            }
+
+            info!("Tx {} committed at height {}", res.hash, res.height);
+            Ok(TxResponse {
+                hash: res.hash.to_string(),
+                height: Some(res.height.value()),
+                gas_wanted: res.tx_result.gas_wanted,
+                gas_used: res.tx_result.gas_used,
+            })
+        }
+    }
+}
+
+/// Whether a pending transaction in the destination mempool already carries a
+/// `MsgRecvPacket` for `packet`, i.e. another relayer submitted it. Best effort:
+/// only the first `MEMPOOL_SCAN_LIMIT` pending transactions are inspected, and
+/// transactions that do not decode are ignored.
+pub async fn is_in_mempool(rpc_url: &str, packet: &Packet, rpc_timeout: Duration) -> Result<bool> {
+    let result = rpc::raw_call(
+        rpc_url,
+        "unconfirmed_txs",
+        serde_json::json!({ "limit": MEMPOOL_SCAN_LIMIT.to_string() }),
+        rpc_timeout,
+    )
+    .await?;
+
+    let txs = result["txs"].as_array().map(Vec::as_slice).unwrap_or_default();
+    Ok(txs
+        .iter()
+        .filter_map(|tx| base64::engine::general_purpose::STANDARD.decode(tx.as_str()?).ok())
+        .filter_map(|bytes| TxRaw::decode(bytes.as_slice()).ok())
+        .filter_map(|raw| TxBody::decode(raw.body_bytes.as_slice()).ok())
+        .flat_map(|body| body.messages)
+        .filter(|msg| msg.type_url == "/ibc.core.channel.v1.MsgRecvPacket")
+        .filter_map(|msg| MsgRecvPacket::decode(msg.value.as_slice()).ok()?.packet)
+        .any(|pending| {
+            pending.sequence == packet.sequence
+                && pending.source_port == packet.source_port
+                && pending.source_channel == packet.source_channel
+                && pending.destination_channel == packet.destination_channel
+        }))
+}
@@ -0,0 +1,193 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use rustls_pki_types::ServerName;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio_rustls::{client, server, TlsAcceptor, TlsConnector};
+use tracing::{error, info, warn};
+
+use crate::config::TransportConfig;
+use crate::relay::RelayRouter;
+use crate::transport::{self, PeerConnection, MAX_MESSAGE_BYTES};
+
+/// Longest a client may take to complete the TLS handshake before its socket is dropped
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS-over-TCP peer connection for networks that block UDP.
+/// Messages are length-prefixed frames; requests on one connection are answered in order.
+pub struct TcpConnection {
+    stream: Mutex<client::TlsStream<TcpStream>>,
+    closed: AtomicBool,
+}
+
+#[async_trait]
+impl PeerConnection for TcpConnection {
+    async fn request(&self, data: &[u8], max_response: usize) -> Result<Vec<u8>> {
+        let mut stream = self.stream.lock().await;
+        let result = exchange(&mut *stream, data, max_response).await;
+        match &result {
+            Ok(response) => info!("Sent {} bytes, received {} bytes via TCP", data.len(), response.len()),
+            Err(_) => self.closed.store(true, Ordering::Relaxed),
+        }
+        result
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    async fn close(&self) {
+        let _ = self.stream.lock().await.shutdown().await;
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Writes a request frame and reads the matching response frame
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, data: &[u8], max_response: usize) -> Result<Vec<u8>> {
+    write_frame(stream, data).await?;
+    match read_frame(stream, max_response).await? {
+        Some(response) => Ok(response),
+        None => bail!("Peer closed the TCP connection"),
+    }
+}
+
+/// Writes one frame: a big-endian u32 length followed by the payload
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, data: &[u8]) -> Result<()> {
+    let len = u32::try_from(data.len()).context("Message too large for a TCP frame")?;
+    writer.write_all(&len.to_be_bytes()).await?;
+    writer.write_all(data).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads one frame; `None` if the peer closed the connection between frames
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R, max_len: usize) -> Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > max_len {
+        bail!("TCP frame of {} bytes exceeds the {} byte limit", len, max_len);
+    }
+    let mut data = vec![0u8; len];
+    reader.read_exact(&mut data).await.context("Truncated TCP frame")?;
+    Ok(Some(data))
+}
+
+/// Establish a TLS-over-TCP client connection, verifying the peer as configured in `config.verifier`
+pub async fn establish_connection(dst_addr: &str, config: &TransportConfig) -> Result<TcpConnection> {
+    let connector = TlsConnector::from(Arc::new(transport::client_crypto(config)?));
+    let server_name = ServerName::try_from(transport::server_name(dst_addr, config).to_string())
+        .context("Invalid TLS server name")?;
+
+    let tcp = TcpStream::connect(dst_addr)
+        .await
+        .context(format!("Failed to connect via TCP to {}", dst_addr))?;
+    tcp.set_nodelay(true)?;
+    let stream = connector
+        .connect(server_name, tcp)
+        .await
+        .context(format!("TLS handshake with {} failed", dst_addr))?;
+
+    info!("TCP+TLS connection established with {}", dst_addr);
+    Ok(TcpConnection {
+        stream: Mutex::new(stream),
+        closed: AtomicBool::new(false),
+    })
+}
+
+/// Send data over an existing TCP connection. The peer's reply is read and discarded
+/// so the next request stays aligned with its response.
+pub async fn send_packet(conn: &TcpConnection, data: Vec<u8>) -> Result<()> {
+    conn.request(&data, MAX_MESSAGE_BYTES).await?;
+    Ok(())
+}
+
+/// Start the TCP+TLS server (self-signed cert, listens indefinitely).
+/// Forwarded `RelayRequest`s carrying `config.peer_token` are handed to `router`; without
+/// a router or a configured token they are rejected.
+pub async fn start_server(listen_addr: &str, config: &TransportConfig, router: Option<Arc<RelayRouter>>) -> Result<()> {
+    let acceptor = TlsAcceptor::from(Arc::new(transport::server_crypto(transport::server_cert(config)?)?));
+    let listener = TcpListener::bind(listen_addr)
+        .await
+        .context("Failed to bind server to address")?;
+
+    info!("TCP+TLS server started on {}", listen_addr);
+
+    let idle_timeout = config.connection_idle_timeout();
+    let peer_token = Arc::new(config.peer_token.clone());
+    loop {
+        let (tcp, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                error!("Error accepting connection: {}", e);
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        let peer_token = peer_token.clone();
+        tokio::spawn(async move {
+            // `idle_timeout` only starts after the handshake; without this a client that
+            // never finishes it would hold the task and socket forever
+            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(tcp)).await {
+                Ok(Ok(stream)) => {
+                    info!("New TCP connection from {}", peer);
+                    if let Err(e) = handle_connection(stream, peer, idle_timeout, router, peer_token.as_deref()).await {
+                        warn!("Error handling connection: {}", e);
+                    }
+                }
+                Ok(Err(e)) => error!("TLS handshake with {} failed: {}", peer, e),
+                Err(_) => warn!(
+                    "TLS handshake with {} did not complete within {} s, closing",
+                    peer,
+                    TLS_HANDSHAKE_TIMEOUT.as_secs()
+                ),
+            }
+        });
+    }
+}
+
+/// Serves each frame as one `RelayRequest` answered with a `RelayResponse` frame.
+/// Connections that send nothing for `idle_timeout` are closed.
+async fn handle_connection(
+    mut stream: server::TlsStream<TcpStream>,
+    peer: SocketAddr,
+    idle_timeout: Option<Duration>,
+    router: Option<Arc<RelayRouter>>,
+    peer_token: Option<&str>,
+) -> Result<()> {
+    loop {
+        let frame = match idle_timeout {
+            Some(idle_timeout) => match tokio::time::timeout(idle_timeout, read_frame(&mut stream, MAX_MESSAGE_BYTES)).await {
+                Ok(frame) => frame?,
+                Err(_) => {
+                    info!(
+                        "Closing idle TCP connection from {} (no requests for {} s)",
+                        peer,
+                        idle_timeout.as_secs()
+                    );
+                    let _ = stream.shutdown().await;
+                    return Ok(());
+                }
+            },
+            None => read_frame(&mut stream, MAX_MESSAGE_BYTES).await?,
+        };
+        let Some(request) = frame else {
+            return Ok(());
+        };
+        info!("Received {} bytes via TCP", request.len());
+
+        let response = transport::handle_request(&request, router.as_deref(), peer_token).await?;
+        write_frame(&mut stream, &response).await?;
+    }
+}
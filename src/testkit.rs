@@ -0,0 +1,345 @@
+//! In-process mock chains for end-to-end tests (`testkit` feature).
+//!
+//! `MockChain` serves the subset of the Tendermint JSON-RPC a poller uses (`abci_info`,
+//! `block_results`; everything else answers with an error the poller tolerates) and
+//! produces a block per emitted packet. `MockDestination` records what the relay
+//! worker relays, via the poller's `on_packet` callback:
+//!
+//! ```ignore
+//! let src = MockChain::start().await?;
+//! let dst = MockDestination::new();
+//! let relay = testkit::relay_pair("test", &src.rpc_url(), "channel-0");
+//! let mut poller = IbcPoller::new(&relay).await?.with_on_packet(dst.on_packet());
+//! tokio::spawn(async move { poller.poll().await });
+//!
+//! src.emit_send_packet(&MockPacket::transfer(1, "channel-0", "channel-1"));
+//! let received = dst.wait_for(1, Duration::from_secs(30)).await?;
+//! assert_eq!(received[0].sequence, 1);
+//! ```
+
+use anyhow::{bail, Context, Result};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tracing::debug;
+
+use crate::config::RelayPair;
+use crate::ibc::ParsedPacket;
+use crate::relay::{PacketCallback, RelayOutcome};
+
+/// An ICS-20 packet to emit as a `send_packet` event
+#[derive(Debug, Clone)]
+pub struct MockPacket {
+    pub sequence: u64,
+    pub src_port: String,
+    pub src_channel: String,
+    pub dst_port: String,
+    pub dst_channel: String,
+    pub amount: String,
+    pub denom: String,
+    pub sender: String,
+    pub receiver: String,
+}
+
+impl MockPacket {
+    /// A 1000uatom transfer between the `transfer` ports of the two channels
+    pub fn transfer(sequence: u64, src_channel: &str, dst_channel: &str) -> Self {
+        Self {
+            sequence,
+            src_port: "transfer".to_string(),
+            src_channel: src_channel.to_string(),
+            dst_port: "transfer".to_string(),
+            dst_channel: dst_channel.to_string(),
+            amount: "1000".to_string(),
+            denom: "uatom".to_string(),
+            sender: "cosmos1sender".to_string(),
+            receiver: "osmo1receiver".to_string(),
+        }
+    }
+
+    fn event(&self) -> Value {
+        let data = json!({
+            "amount": self.amount,
+            "denom": self.denom,
+            "receiver": self.receiver,
+            "sender": self.sender,
+        });
+        let attributes = [
+            ("packet_sequence", self.sequence.to_string()),
+            ("packet_src_port", self.src_port.clone()),
+            ("packet_src_channel", self.src_channel.clone()),
+            ("packet_dst_port", self.dst_port.clone()),
+            ("packet_dst_channel", self.dst_channel.clone()),
+            ("packet_timeout_height", "0-0".to_string()),
+            ("packet_timeout_timestamp", "0".to_string()),
+            ("packet_data_hex", hex::encode(data.to_string())),
+        ];
+
+        json!({
+            "type": "send_packet",
+            "attributes": attributes
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": value, "index": true }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Mock source chain: blocks are created on demand, each holding the events emitted into it
+pub struct MockChain {
+    addr: SocketAddr,
+    state: Mutex<ChainState>,
+}
+
+struct ChainState {
+    height: u64,
+    /// Events of the txs in each non-empty block
+    blocks: BTreeMap<u64, Vec<Value>>,
+}
+
+impl MockChain {
+    /// Serves the mock RPC on a free localhost port, starting at height 1
+    pub async fn start() -> Result<Arc<Self>> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.context("Failed to bind mock RPC")?;
+        let chain = Arc::new(Self {
+            addr: listener.local_addr()?,
+            state: Mutex::new(ChainState { height: 1, blocks: BTreeMap::new() }),
+        });
+
+        let server = chain.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let chain = server.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |req| {
+                        let chain = chain.clone();
+                        async move { Ok::<_, Infallible>(chain.respond(req).await) }
+                    });
+                    if let Err(e) = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                        debug!("Mock RPC connection error: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(chain)
+    }
+
+    /// URL to use as `src_rpc`
+    pub fn rpc_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn height(&self) -> u64 {
+        self.state.lock().unwrap().height
+    }
+
+    /// Produces `blocks` empty blocks
+    pub fn advance(&self, blocks: u64) {
+        self.state.lock().unwrap().height += blocks;
+    }
+
+    /// Produces a block with one tx emitting `send_packet` for `packet`; returns its height
+    pub fn emit_send_packet(&self, packet: &MockPacket) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.height += 1;
+        let height = state.height;
+        state.blocks.insert(height, vec![packet.event()]);
+        height
+    }
+
+    async fn respond(&self, req: Request<Incoming>) -> Response<Full<Bytes>> {
+        let body = match req.into_body().collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(e) => return rpc_response(Value::Null, Err(format!("unreadable request: {}", e))),
+        };
+        let request: Value = serde_json::from_slice(&body).unwrap_or_default();
+        let id = request["id"].clone();
+
+        let result = match request["method"].as_str().unwrap_or("") {
+            "abci_info" => Ok(self.abci_info()),
+            "block_results" => self.block_results(&request["params"]),
+            method => Err(format!("{} is not supported by the mock chain", method)),
+        };
+        rpc_response(id, result)
+    }
+
+    fn abci_info(&self) -> Value {
+        json!({
+            "response": {
+                "data": "mock",
+                "version": "0.0.0",
+                "app_version": "1",
+                "last_block_height": self.height().to_string(),
+                "last_block_app_hash": "",
+            }
+        })
+    }
+
+    fn block_results(&self, params: &Value) -> std::result::Result<Value, String> {
+        let height = params["height"]
+            .as_str()
+            .and_then(|h| h.parse::<u64>().ok())
+            .or_else(|| params["height"].as_u64())
+            .ok_or("missing height")?;
+
+        let state = self.state.lock().unwrap();
+        if height > state.height {
+            return Err(format!("height {} is not available, latest height is {}", height, state.height));
+        }
+        let txs_results: Vec<Value> = state
+            .blocks
+            .get(&height)
+            .into_iter()
+            .flatten()
+            .map(|event| {
+                json!({
+                    "code": 0,
+                    "data": null,
+                    "log": "",
+                    "info": "",
+                    "gas_wanted": "0",
+                    "gas_used": "0",
+                    "events": [event],
+                    "codespace": "",
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "height": height.to_string(),
+            "txs_results": txs_results,
+            "finalize_block_events": [],
+            "validator_updates": [],
+            "consensus_param_updates": null,
+            "app_hash": "",
+        }))
+    }
+}
+
+fn rpc_response(id: Value, result: std::result::Result<Value, String>) -> Response<Full<Bytes>> {
+    let body = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(message) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32603, "message": "Internal error", "data": message },
+        }),
+    };
+    Response::new(Full::new(Bytes::from(body.to_string())))
+}
+
+/// A packet the relay worker handled, as it would be submitted in MsgRecvPacket
+#[derive(Debug, Clone)]
+pub struct ReceivedPacket {
+    pub sequence: u64,
+    pub src_port: String,
+    pub src_channel: String,
+    pub dst_port: String,
+    pub dst_channel: String,
+    pub amount: String,
+    pub denom: String,
+    pub sender: String,
+    pub receiver: String,
+    pub outcome: RelayOutcome,
+}
+
+impl From<(&ParsedPacket, &RelayOutcome)> for ReceivedPacket {
+    fn from((packet, outcome): (&ParsedPacket, &RelayOutcome)) -> Self {
+        Self {
+            sequence: packet.sequence,
+            src_port: packet.src_port.clone(),
+            src_channel: packet.src_channel.clone(),
+            dst_port: packet.dst_port.clone(),
+            dst_channel: packet.dst_channel.clone(),
+            amount: packet.data.amount.clone(),
+            denom: packet.data.denom.clone(),
+            sender: packet.data.sender.clone(),
+            receiver: packet.data.receiver.clone(),
+            outcome: outcome.clone(),
+        }
+    }
+}
+
+/// Mock destination: records every relay attempt of a poller
+#[derive(Default)]
+pub struct MockDestination {
+    received: Mutex<Vec<ReceivedPacket>>,
+    changed: Notify,
+}
+
+impl MockDestination {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Callback for `IbcPoller::with_on_packet`
+    pub fn on_packet(self: &Arc<Self>) -> PacketCallback {
+        let destination = self.clone();
+        Arc::new(move |packet, outcome| {
+            destination.received.lock().unwrap().push((packet, outcome).into());
+            destination.changed.notify_waiters();
+        })
+    }
+
+    pub fn received(&self) -> Vec<ReceivedPacket> {
+        self.received.lock().unwrap().clone()
+    }
+
+    /// Waits until at least `count` packets were recorded
+    pub async fn wait_for(&self, count: usize, timeout: Duration) -> Result<Vec<ReceivedPacket>> {
+        let wait = async {
+            loop {
+                let changed = self.changed.notified();
+                let received = self.received();
+                if received.len() >= count {
+                    return received;
+                }
+                changed.await;
+            }
+        };
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(received) => Ok(received),
+            Err(_) => bail!(
+                "Only {} of {} packets reached the destination within {} s",
+                self.received().len(),
+                count,
+                timeout.as_secs()
+            ),
+        }
+    }
+}
+
+/// Relay config watching `src_channel` on the mock chain at `src_rpc`; the destination
+/// RPC points at the same mock (it is only used for optional checks)
+pub fn relay_pair(name: &str, src_rpc: &str, src_channel: &str) -> RelayPair {
+    let toml = format!(
+        r#"
+name = "{name}"
+src_chain = "mock-src"
+src_rpc = "{src_rpc}"
+src_channel = "{src_channel}"
+src_port = "transfer"
+dst_chain = "mock-dst"
+dst_rpc = "{src_rpc}"
+dst_channel = "channel-1"
+dst_port = "transfer"
+signer = "mock1ufml4llk38hsakhh4czken98fj7fplc7e2m6el"
+rpc_timeout_ms = 2000
+connect_retries = 0
+verify_chain_id = false
+"#
+    );
+    toml::from_str(&toml).expect("testkit relay config is valid")
+}
@@ -1,10 +1,47 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use quinn::congestion::{BbrConfig, CubicConfig, NewRenoConfig};
 use quinn::{Connection, Endpoint, ServerConfig};
-use rcgen::generate_simple_self_signed;
-use rustls_pki_types::{CertificateDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use crate::cert::ServerCert;
+use crate::config::{CertVerifierMode, CongestionControl, TransportConfig, TransportProtocol};
+use crate::metrics;
+use crate::relay::{RelayRequest, RelayResponse, RelayRouter};
+use crate::tcp;
 use tracing::{debug, error, info, warn};
 
+/// A connection to a peer node, independent of the wire protocol
+#[async_trait]
+pub trait PeerConnection: Send + Sync {
+    /// Sends one message and waits for the peer's full reply
+    async fn request(&self, data: &[u8], max_response: usize) -> Result<Vec<u8>>;
+
+    /// True once the connection is gone and must be re-established
+    fn is_closed(&self) -> bool;
+
+    async fn close(&self);
+}
+
+#[async_trait]
+impl PeerConnection for Connection {
+    async fn request(&self, data: &[u8], max_response: usize) -> Result<Vec<u8>> {
+        send_request(self, data, max_response).await
+    }
+
+    fn is_closed(&self) -> bool {
+        self.close_reason().is_some()
+    }
+
+    async fn close(&self) {
+        Connection::close(self, 0u32.into(), b"done");
+    }
+}
+
 #[derive(Debug)]
 struct SkipServerVerification;
 
@@ -50,25 +87,137 @@ impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
     }
 }
 
-/// Establish a QUIC client connection (skips certificate verification for self-signed certs)
-pub async fn establish_connection(dst_addr: &str) -> Result<Connection> {
+/// Environment variable that opts in to `verifier = "insecure"` (value `1`)
+pub const INSECURE_ENV: &str = "AERO_INSECURE";
+
+/// Whether skipping certificate verification was explicitly allowed
+/// (`--insecure-skip-verify` or `AERO_INSECURE=1`)
+pub fn insecure_allowed(config: &TransportConfig) -> bool {
+    config.allow_insecure || std::env::var(INSECURE_ENV).is_ok_and(|value| value == "1")
+}
+
+/// Application close code sent when the server reaps an idle connection
+pub const CLOSE_IDLE_TIMEOUT: u32 = 0x10;
+
+/// Largest request or response accepted on a stream (or in a TCP frame)
+pub(crate) const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+
+/// Received payloads buffered for the `serve_stream` consumer
+const RECEIVED_QUEUE_CAPACITY: usize = 1024;
+
+/// Requests `send_many` keeps open at once; the peer's `max_concurrent_streams` may lower this further
+const SEND_MANY_MAX_IN_FLIGHT: usize = 32;
+
+/// Buffer size for each read in `read_to_end`
+const READ_CHUNK_BYTES: usize = 16 * 1024;
+
+/// Builds the quinn transport settings shared by client and server endpoints
+fn quic_transport(config: &TransportConfig) -> Arc<quinn::TransportConfig> {
+    let mut transport = quinn::TransportConfig::default();
+    match config.congestion {
+        CongestionControl::Bbr => transport.congestion_controller_factory(Arc::new(BbrConfig::default())),
+        CongestionControl::Cubic => transport.congestion_controller_factory(Arc::new(CubicConfig::default())),
+        CongestionControl::NewReno => transport.congestion_controller_factory(Arc::new(NewRenoConfig::default())),
+    };
+    transport.max_concurrent_bidi_streams(config.max_concurrent_streams.into());
+    Arc::new(transport)
+}
+
+/// Loads the platform's native root certificates
+fn native_roots() -> Result<rustls::RootCertStore> {
+    let native = rustls_native_certs::load_native_certs();
+    for e in &native.errors {
+        warn!("Failed to load a native root certificate: {}", e);
+    }
+
+    let mut roots = rustls::RootCertStore::empty();
+    let (added, ignored) = roots.add_parsable_certificates(native.certs);
+    if roots.is_empty() {
+        bail!("No usable root certificates found in the system trust store");
+    }
+
+    debug!("Loaded {} native root certificates ({} ignored)", added, ignored);
+    Ok(roots)
+}
+
+/// Host part of a `host:port` address (brackets stripped from IPv6 literals)
+fn host_of(addr: &str) -> &str {
+    let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+/// Client TLS settings shared by both transports. Certificates are verified according to
+/// `config.verifier`: WebPKI against the system roots, or skipped for self-signed peers –
+/// the latter is refused unless `insecure_allowed`.
+pub(crate) fn client_crypto(config: &TransportConfig) -> Result<rustls::ClientConfig> {
+    let provider = rustls::crypto::aws_lc_rs::default_provider();
+    let builder = rustls::ClientConfig::builder_with_provider(Arc::new(provider))
+        .with_safe_default_protocol_versions()?;
+    Ok(match config.verifier {
+        CertVerifierMode::Insecure if !insecure_allowed(config) => bail!(
+            "verifier = \"insecure\" skips peer certificate verification; pass --insecure-skip-verify or set {}=1 to allow it (development only)",
+            INSECURE_ENV
+        ),
+        CertVerifierMode::Insecure => builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+            .with_no_client_auth(),
+        CertVerifierMode::WebPki => builder
+            .with_root_certificates(native_roots()?)
+            .with_no_client_auth(),
+    })
+}
+
+/// Server TLS settings shared by both transports, presenting `cert`
+pub(crate) fn server_crypto(cert: Arc<ServerCert>) -> Result<rustls::ServerConfig> {
     let provider = rustls::crypto::aws_lc_rs::default_provider();
-    let crypto = rustls::ClientConfig::builder_with_provider(Arc::new(provider))
-        .with_safe_default_protocol_versions()?
-        .dangerous()
-        .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
-        .with_no_client_auth();
+    Ok(rustls::ServerConfig::builder_with_provider(Arc::new(provider))
+        .with_safe_default_protocol_versions()
+        .context("Failed to create server config")?
+        .with_no_client_auth()
+        .with_cert_resolver(cert))
+}
+
+/// Generates the server's self-signed certificate and starts its expiry watch
+pub(crate) fn server_cert(config: &TransportConfig) -> Result<Arc<ServerCert>> {
+    let cert = Arc::new(ServerCert::self_signed(config.cert_validity_days)?);
+    info!("Server certificate valid until {}", cert.cert_expiry());
+    tokio::spawn(cert.clone().watch_expiry(config.clone()));
+    Ok(cert)
+}
+
+/// Name (SNI) the peer certificate is checked against
+pub(crate) fn server_name<'a>(dst_addr: &'a str, config: &'a TransportConfig) -> &'a str {
+    config.server_name.as_deref().unwrap_or_else(|| host_of(dst_addr))
+}
+
+/// Connects to a peer over the configured `[transport] protocol`
+pub async fn connect(dst_addr: &str, config: &TransportConfig) -> Result<Arc<dyn PeerConnection>> {
+    Ok(match config.protocol {
+        TransportProtocol::Quic => Arc::new(establish_connection(dst_addr, config).await?),
+        TransportProtocol::Tcp => Arc::new(tcp::establish_connection(dst_addr, config).await?),
+    })
+}
 
-    let client_config = quinn::ClientConfig::new(Arc::new(
+/// Establish a QUIC client connection, verifying the peer as configured in `config.verifier`
+pub async fn establish_connection(dst_addr: &str, config: &TransportConfig) -> Result<Connection> {
+    let crypto = client_crypto(config)?;
+    let mut client_config = quinn::ClientConfig::new(Arc::new(
         quinn::crypto::rustls::QuicClientConfig::try_from(crypto)?,
     ));
+    client_config.transport_config(quic_transport(config));
 
     let mut endpoint = Endpoint::client("0.0.0.0:0".parse()?)
         .context("Failed to create client endpoint")?;
     endpoint.set_default_client_config(client_config);
 
+    let remote = tokio::net::lookup_host(dst_addr)
+        .await
+        .context(format!("Failed to resolve {}", dst_addr))?
+        .next()
+        .ok_or_else(|| anyhow!("No address found for {}", dst_addr))?;
     let conn = endpoint
-        .connect(dst_addr.parse()?, "aero-relay")?
+        .connect(remote, server_name(dst_addr, config))?
         .await
         .context(format!("Failed to connect via QUIC to {}", dst_addr))?;
 
@@ -94,60 +243,293 @@ pub async fn send_packet(conn: &Connection, data: Vec<u8>) -> Result<()> {
     Ok(())
 }
 
-/// Start the QUIC server (self-signed cert, listens indefinitely)
-pub async fn start_server(listen_addr: &str) -> Result<()> {
-    let subject_alt_names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
-    let cert = generate_simple_self_signed(subject_alt_names)?;
+/// Reads `recv` chunk by chunk until the peer finishes the stream. Fails once more than
+/// `max_size` bytes arrive instead of truncating, so a partial message is never processed.
+pub async fn read_to_end(recv: &mut quinn::RecvStream, max_size: usize) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut chunk = vec![0u8; READ_CHUNK_BYTES];
+    while let Some(n) = recv.read(&mut chunk).await.context("Failed to read from QUIC stream")? {
+        if data.len() + n > max_size {
+            bail!("QUIC message exceeds the {} byte limit", max_size);
+        }
+        data.extend_from_slice(&chunk[..n]);
+    }
+    Ok(data)
+}
 
-    let cert_der = CertificateDer::from(cert.cert);
-    let key_der = PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
+/// Send a request on a new bidirectional stream and wait for the peer's full response
+pub async fn send_request(conn: &Connection, data: &[u8], max_response: usize) -> Result<Vec<u8>> {
+    let (mut send, mut recv) = conn
+        .open_bi()
+        .await
+        .context("Failed to open bidirectional stream")?;
 
-    let provider = rustls::crypto::aws_lc_rs::default_provider();
-    let server_crypto = rustls::ServerConfig::builder_with_provider(Arc::new(provider))
-        .with_safe_default_protocol_versions()?
-        .with_no_client_auth()
-        .with_single_cert(vec![cert_der], key_der.into())
-        .context("Failed to create server config")?;
+    send.write_all(data)
+        .await
+        .context("Failed to write data to QUIC stream")?;
+    let _ = send.finish();
 
-    let server_config = ServerConfig::with_crypto(Arc::new(
-        quinn::crypto::rustls::QuicServerConfig::try_from(server_crypto)?,
-    ));
-    let endpoint = Endpoint::server(server_config, listen_addr.parse()?)
-        .context("Failed to bind server to address")?;
+    let response = read_to_end(&mut recv, max_response)
+        .await
+        .context("Failed to read response")?;
+
+    info!("Sent {} bytes, received {} bytes via QUIC", data.len(), response.len());
+    Ok(response)
+}
+
+/// Sends each payload as its own request, concurrently over one connection, and returns the
+/// responses in payload order. At most `SEND_MANY_MAX_IN_FLIGHT` streams are open at a time;
+/// opening a stream also waits while the peer's `max_concurrent_streams` limit is reached.
+/// Fails with the first failed request.
+pub async fn send_many(conn: &Connection, payloads: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+    let count = payloads.len();
+    let responses: Vec<Vec<u8>> = stream::iter(payloads)
+        .map(|payload| async move { send_request(conn, &payload, MAX_MESSAGE_BYTES).await })
+        .buffered(SEND_MANY_MAX_IN_FLIGHT)
+        .try_collect()
+        .await?;
+
+    debug!("Sent {} requests concurrently via QUIC", count);
+    Ok(responses)
+}
+
+/// Forwards a packet to a peer node, authenticated with `peer_token`, and returns its answer
+pub async fn forward_to_peer(
+    conn: &dyn PeerConnection,
+    request: &RelayRequest,
+    peer_token: Option<&str>,
+) -> Result<RelayResponse> {
+    let request = RelayRequest {
+        peer_token: peer_token.unwrap_or_default().to_string(),
+        ..request.clone()
+    };
+    let payload = serde_json::to_vec(&request)?;
+    let response = conn.request(&payload, MAX_MESSAGE_BYTES).await?;
+    serde_json::from_slice(&response).context("Invalid response from peer")
+}
+
+/// Runs the peer server over the configured `[transport] protocol`
+pub async fn serve(listen_addr: &str, config: &TransportConfig, router: Option<Arc<RelayRouter>>) -> Result<()> {
+    match config.protocol {
+        TransportProtocol::Quic => start_server(listen_addr, config, router).await,
+        TransportProtocol::Tcp => tcp::start_server(listen_addr, config, router).await,
+    }
+}
 
+/// Start the QUIC server (self-signed cert, listens indefinitely).
+/// Forwarded `RelayRequest`s carrying `config.peer_token` are handed to `router`; without
+/// a router or a configured token they are rejected.
+pub async fn start_server(listen_addr: &str, config: &TransportConfig, router: Option<Arc<RelayRouter>>) -> Result<()> {
+    let endpoint = bind_server(listen_addr, config, server_cert(config)?)?;
     info!("QUIC server started on {}", listen_addr);
+    serve_endpoint(endpoint, config.connection_idle_timeout(), router, config.peer_token.clone()).await
+}
+
+/// Binds the QUIC server endpoint presenting `cert` (port 0 picks a free port, see `Endpoint::local_addr`)
+pub fn bind_server(listen_addr: &str, config: &TransportConfig, cert: Arc<ServerCert>) -> Result<Endpoint> {
+    let mut server_config = ServerConfig::with_crypto(Arc::new(
+        quinn::crypto::rustls::QuicServerConfig::try_from(server_crypto(cert)?)?,
+    ));
+    server_config.transport_config(quic_transport(config));
+    Endpoint::server(server_config, listen_addr.parse()?).context("Failed to bind server to address")
+}
 
-    while let Some(connecting) = endpoint.accept().await {
+/// Accepts connections on a bound endpoint until it is closed, answering each
+/// `RelayRequest` that presents `peer_token` through `router`
+pub async fn serve_endpoint(
+    endpoint: Endpoint,
+    idle_timeout: Option<Duration>,
+    router: Option<Arc<RelayRouter>>,
+    peer_token: Option<String>,
+) -> Result<()> {
+    let mut payloads = Box::pin(endpoint_stream(endpoint, idle_timeout));
+    let peer_token = Arc::new(peer_token);
+    while let Some((_, request, responder)) = payloads.next().await {
+        let router = router.clone();
+        let peer_token = peer_token.clone();
         tokio::spawn(async move {
-            match connecting.await {
-                Ok(new_conn) => {
-                    info!("New QUIC connection from {}", new_conn.remote_address());
-                    if let Err(e) = handle_connection(new_conn).await {
-                        warn!("Error handling connection: {}", e);
+            match handle_request(&request, router.as_deref(), peer_token.as_deref()).await {
+                Ok(bytes) => {
+                    if let Err(e) = responder.send(&bytes).await {
+                        debug!("Failed to send response: {}", e);
                     }
                 }
-                Err(e) => error!("Error accepting connection: {}", e),
+                Err(e) => error!("{}", e),
             }
         });
     }
     Ok(())
 }
 
-/// Echo received data back to client (simple relay behavior)
-async fn handle_connection(conn: Connection) -> Result<()> {
-    while let Ok((mut send, mut recv)) = conn.accept_bi().await {
+/// Answers one payload received by `serve_stream`. Dropping it closes the stream
+/// without a response.
+pub struct ResponseSender {
+    send: quinn::SendStream,
+}
+
+impl ResponseSender {
+    /// Writes `response` and finishes the stream
+    pub async fn send(mut self, response: &[u8]) -> Result<()> {
+        self.send.write_all(response).await?;
+        self.send.finish()?;
+        Ok(())
+    }
+}
+
+/// Runs a QUIC server on `listen_addr` and yields every received payload (one per stream,
+/// at most `MAX_MESSAGE_BYTES`) with the peer address and a handle for the response, for
+/// embedders that handle messages themselves instead of routing `RelayRequest`s.
+/// Streams are read concurrently; a consumer that stops pulling holds them back.
+pub fn serve_stream(
+    listen_addr: &str,
+    config: &TransportConfig,
+) -> Result<impl Stream<Item = (SocketAddr, Vec<u8>, ResponseSender)>> {
+    let endpoint = bind_server(listen_addr, config, server_cert(config)?)?;
+    info!("QUIC server started on {}", listen_addr);
+    Ok(endpoint_stream(endpoint, config.connection_idle_timeout()))
+}
+
+/// Payloads received on a bound endpoint; ends once the endpoint and all its connections
+/// are closed (or the stream is dropped)
+fn endpoint_stream(
+    endpoint: Endpoint,
+    idle_timeout: Option<Duration>,
+) -> impl Stream<Item = (SocketAddr, Vec<u8>, ResponseSender)> {
+    let (tx, rx) = mpsc::channel(RECEIVED_QUEUE_CAPACITY);
+    tokio::spawn(async move {
+        while let Some(connecting) = endpoint.accept().await {
+            if tx.is_closed() {
+                break;
+            }
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                match connecting.await {
+                    Ok(new_conn) => {
+                        info!("New QUIC connection from {}", new_conn.remote_address());
+                        handle_connection(new_conn, idle_timeout, tx).await;
+                    }
+                    Err(e) => error!("Error accepting connection: {}", e),
+                }
+            });
+        }
+    });
+
+    stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|item| (item, rx)) })
+}
+
+/// Waits for the next stream; `None` once the connection is closed or was reaped
+/// for opening no stream within `idle_timeout`
+async fn accept_stream(
+    conn: &Connection,
+    idle_timeout: Option<Duration>,
+) -> Option<(quinn::SendStream, quinn::RecvStream)> {
+    let Some(idle_timeout) = idle_timeout else {
+        return conn.accept_bi().await.ok();
+    };
+
+    match tokio::time::timeout(idle_timeout, conn.accept_bi()).await {
+        Ok(stream) => stream.ok(),
+        Err(_) => {
+            info!(
+                "Closing idle QUIC connection from {} (no streams for {} s)",
+                conn.remote_address(),
+                idle_timeout.as_secs()
+            );
+            conn.close(CLOSE_IDLE_TIMEOUT.into(), b"idle timeout");
+            None
+        }
+    }
+}
+
+/// Reads each stream of the connection as one payload and hands it to `received`
+async fn handle_connection(
+    conn: Connection,
+    idle_timeout: Option<Duration>,
+    received: mpsc::Sender<(SocketAddr, Vec<u8>, ResponseSender)>,
+) {
+    let remote = conn.remote_address();
+    while let Some((send, mut recv)) = accept_stream(&conn, idle_timeout).await {
+        let received = received.clone();
         tokio::spawn(async move {
-            let mut buffer = vec![0u8; 64 * 1024];
-            match recv.read(&mut buffer).await {
-                Ok(Some(len)) => {
-                    info!("Received {} bytes via QUIC", len);
-                    let _ = send.write_all(&buffer[..len]).await;
-                    let _ = send.finish();
+            let request = match read_to_end(&mut recv, MAX_MESSAGE_BYTES).await {
+                Ok(request) => request,
+                Err(e) => {
+                    warn!("Error reading stream: {}", e);
+                    return;
                 }
-                Ok(None) => debug!("Stream closed by client"),
-                Err(e) => warn!("Error reading stream: {}", e),
-            }
+            };
+            info!("Received {} bytes via QUIC", request.len());
+            let _ = received.send((remote, request, ResponseSender { send })).await;
         });
+        if received.is_closed() {
+            conn.close(0u32.into(), b"server stopped");
+            break;
+        }
     }
-    Ok(())
-}
\ No newline at end of file
+}
+
+/// Answers one encoded `RelayRequest` with an encoded `RelayResponse` (both transports).
+/// Only requests presenting `peer_token` reach `router`; none do without a token.
+pub(crate) async fn handle_request(
+    request: &[u8],
+    router: Option<&RelayRouter>,
+    peer_token: Option<&str>,
+) -> Result<Vec<u8>> {
+    let response = match (serde_json::from_slice::<RelayRequest>(request), router) {
+        (Ok(_), Some(_)) if peer_token.is_none() => RelayResponse::Rejected {
+            reason: "this node accepts no forwarded packets without [transport] peer_token".to_string(),
+        },
+        (Ok(request), Some(_)) if !peer_token_matches(&request.peer_token, peer_token.unwrap_or_default()) => {
+            warn!("[{}] Rejected packet {} with an invalid peer token", request.relay, request.sequence);
+            // Unlabeled: the relay name is unauthenticated
+            metrics::inc_counter("transport_unauthorized_requests_total", &[]);
+            RelayResponse::Rejected { reason: "invalid peer token".to_string() }
+        }
+        (Ok(request), Some(router)) => {
+            // Recomputed from the packet: a sender-chosen key could evade deduplication or
+            // shadow another packet's key
+            let key = request.packet_key();
+            if !request.idempotency_key.is_empty() && request.idempotency_key != key {
+                warn!(
+                    "[{}] Packet {} carries idempotency key {}, expected {}",
+                    request.relay, request.sequence, request.idempotency_key, key
+                );
+                metrics::inc_counter(
+                    "transport_idempotency_key_mismatch_total",
+                    &[("relay", router.relay_label(&request.relay))],
+                );
+            }
+            if router.mark_seen(&key) {
+                let response = router.submit(request).await;
+                if matches!(response, RelayResponse::Rejected { .. }) {
+                    router.forget(&key);
+                }
+                response
+            } else {
+                // Already queued here: acknowledge so the sender does not retry
+                debug!("[{}] Dropped duplicate of packet {}", request.relay, request.sequence);
+                metrics::inc_counter(
+                    "transport_duplicate_requests_total",
+                    &[("relay", router.relay_label(&request.relay))],
+                );
+                RelayResponse::Queued
+            }
+        }
+        (Ok(_), None) => RelayResponse::Rejected {
+            reason: "forwarding is not enabled on this node".to_string(),
+        },
+        (Err(e), _) => RelayResponse::Rejected {
+            reason: format!("invalid relay request: {}", e),
+        },
+    };
+    if let RelayResponse::Rejected { reason } = &response {
+        debug!("Rejected relay request: {}", reason);
+    }
+
+    serde_json::to_vec(&response).context("Failed to encode relay response")
+}
+
+/// Compares hashes so the check takes the same time however much of the token matches
+fn peer_token_matches(given: &str, token: &str) -> bool {
+    !given.is_empty() && blake3::hash(given.as_bytes()) == blake3::hash(token.as_bytes())
+}
@@ -1,19 +1,71 @@
 #[cfg(feature = "encryption-proof")]
 mod zk_impl {
-    use anyhow::{anyhow, Result};
+    use anyhow::{anyhow, bail, Context, Result};
     use blake3::hash;
     use halo2_proofs::halo2curves::bn256::{Bn256, Fr, G1Affine};
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner, Value},
-        plonk::{Circuit, ConstraintSystem, Error as PlonkError, create_proof, keygen_pk, keygen_vk},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem, Error as PlonkError, ProvingKey, create_proof, keygen_pk, keygen_vk, verify_proof},
+        poly::commitment::{Params, ParamsProver},
+        poly::ipa::{
+            commitment::{IPACommitmentScheme, ParamsIPA},
+            multiopen::{ProverIPA, VerifierIPA},
+            strategy::SingleStrategy as IpaSingleStrategy,
+        },
         poly::kzg::{
             commitment::{KZGCommitmentScheme, ParamsKZG},
-            multiopen::ProverGWC,
+            multiopen::{ProverGWC, VerifierGWC},
+            strategy::SingleStrategy,
         },
-        transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer},
+        transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer},
+        SerdeFormat,
     };
-    use rand_core::OsRng;
-    use tracing::info;
+    use rand_core::{CryptoRng, OsRng, RngCore};
+    use std::fs::File;
+    use std::io::{BufReader, BufWriter, Write};
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::sync::{Mutex, OnceLock};
+    use tracing::{info, warn};
+
+    use crate::config::{CommitmentScheme, DEFAULT_ZK_K, MAX_ZK_K};
+
+    /// KZG parameters are persisted so keys (and the exported VK) stay stable across restarts.
+    /// The default circuit size keeps the original file name.
+    fn params_path(k: u32) -> String {
+        if k == DEFAULT_ZK_K {
+            "zk_params.bin".to_string()
+        } else {
+            format!("zk_params_k{}.bin", k)
+        }
+    }
+
+    /// Magic header of the exported verifying key file (KZG)
+    const VK_FILE_MAGIC: &[u8; 8] = b"AEROVK01";
+    /// Magic header of the exported verifying key file (IPA)
+    const VK_FILE_MAGIC_IPA: &[u8; 8] = b"AEROVKI1";
+
+    /// First byte of every proof: the commitment scheme that produced it
+    const PROOF_TAG_KZG: u8 = 0x01;
+    const PROOF_TAG_IPA: u8 = 0x02;
+
+    fn proof_tag(scheme: CommitmentScheme) -> u8 {
+        match scheme {
+            CommitmentScheme::Kzg => PROOF_TAG_KZG,
+            CommitmentScheme::Ipa => PROOF_TAG_IPA,
+        }
+    }
+
+    /// Scheme a tagged proof was produced with, and the proof transcript
+    pub fn split_proof(proof: &[u8]) -> Result<(CommitmentScheme, &[u8])> {
+        match proof.split_first() {
+            Some((&PROOF_TAG_KZG, transcript)) => Ok((CommitmentScheme::Kzg, transcript)),
+            Some((&PROOF_TAG_IPA, transcript)) => Ok((CommitmentScheme::Ipa, transcript)),
+            Some((tag, _)) => bail!("Unknown proof scheme tag 0x{:02x}", tag),
+            None => bail!("Empty proof"),
+        }
+    }
 
     // Allow dead code since this is a WIP circuit
     #[allow(dead_code)]
@@ -47,47 +99,268 @@ mod zk_impl {
         }
     }
 
-    /// Generates a ZK proof of packet commitment (WIP – currently proves nothing but compiles)
+    /// Commitment scheme params: KZG needs a (persisted) trusted setup, IPA is transparent
+    enum SchemeParams {
+        Kzg(ParamsKZG<Bn256>),
+        Ipa(ParamsIPA<G1Affine>),
+    }
+
+    /// Params and proving key. The production setup is generated once per process;
+    /// `keygen_with_rng` builds independent ones (e.g. from a seeded RNG for test vectors).
+    pub struct ProvingSetup {
+        params: SchemeParams,
+        pk: ProvingKey<G1Affine>,
+    }
+
+    impl ProvingSetup {
+        pub fn scheme(&self) -> CommitmentScheme {
+            match self.params {
+                SchemeParams::Kzg(_) => CommitmentScheme::Kzg,
+                SchemeParams::Ipa(_) => CommitmentScheme::Ipa,
+            }
+        }
+    }
+
+    /// Cached setups by commitment scheme and circuit size `k`
+    static SETUPS: OnceLock<Mutex<HashMap<(CommitmentScheme, u32), &'static ProvingSetup>>> = OnceLock::new();
+
+    /// Derives the proving key (and with it the verifying key) for the packet circuit from `params`
+    fn keygen_for<'params, P: Params<'params, G1Affine>>(params: &P) -> Result<ProvingKey<G1Affine>> {
+        let empty = PacketCommitmentCircuit {
+            preimage: Vec::new(),
+            public_commitment: Value::unknown(),
+        };
+        let vk = keygen_vk(params, &empty).map_err(|e| anyhow!("VK error: {:?}", e))?;
+        keygen_pk(params, vk, &empty).map_err(|e| anyhow!("PK error: {:?}", e))
+    }
+
+    fn keygen(params: SchemeParams) -> Result<ProvingSetup> {
+        let pk = match &params {
+            SchemeParams::Kzg(params) => keygen_for(params)?,
+            SchemeParams::Ipa(params) => keygen_for(params)?,
+        };
+        Ok(ProvingSetup { params, pk })
+    }
+
+    /// Runs the KZG setup for 2^k rows and keygen with the given RNG. Not persisted or
+    /// cached – pass a seeded RNG (e.g. `ChaChaRng::seed_from_u64`) for reproducible keys.
+    /// IPA needs no RNG: its params are derived deterministically from `k`.
+    pub fn keygen_with_rng<R: RngCore + CryptoRng>(k: u32, rng: R) -> Result<ProvingSetup> {
+        keygen(SchemeParams::Kzg(ParamsKZG::<Bn256>::setup(k, rng)))
+    }
+
+    /// Loads the KZG params for `k` from disk, or runs the setup and saves them
+    fn load_or_setup_params(k: u32) -> Result<ParamsKZG<Bn256>> {
+        let path = params_path(k);
+        if Path::new(&path).exists() {
+            let mut reader = BufReader::new(File::open(&path)?);
+            let params = ParamsKZG::<Bn256>::read(&mut reader)
+                .context(format!("Failed to read ZK params from {}", path))?;
+            if params.k() == k {
+                info!("ZK params loaded from {}", path);
+                return Ok(params);
+            }
+            warn!("{} holds params for k = {}, regenerating for k = {}", path, params.k(), k);
+        }
+
+        let params = ParamsKZG::<Bn256>::setup(k, OsRng);
+        let mut writer = BufWriter::new(File::create(&path)?);
+        params.write(&mut writer)?;
+        writer.flush()?;
+        info!("ZK params generated and saved to {}", path);
+        Ok(params)
+    }
+
+    /// Params of `scheme` for 2^k rows: KZG params are loaded or set up once and saved,
+    /// IPA params are transparent and simply regenerated
+    fn scheme_params(scheme: CommitmentScheme, k: u32) -> Result<SchemeParams> {
+        Ok(match scheme {
+            CommitmentScheme::Kzg => SchemeParams::Kzg(load_or_setup_params(k)?),
+            CommitmentScheme::Ipa => SchemeParams::Ipa(ParamsIPA::<G1Affine>::new(k)),
+        })
+    }
+
+    /// Whether starting `scheme` for `k` reuses saved params instead of running a setup
+    /// (IPA has no setup, so always true)
+    pub fn params_cached(scheme: CommitmentScheme, k: u32) -> bool {
+        match scheme {
+            CommitmentScheme::Kzg => Path::new(&params_path(k)).exists(),
+            CommitmentScheme::Ipa => true,
+        }
+    }
+
+    /// Smallest `k` above `k` the circuit fits in, if any up to `MAX_ZK_K`. Probed with
+    /// `MockProver`, which lays the circuit out without params, so no setup runs per candidate.
+    fn min_viable_k(k: u32) -> Option<u32> {
+        let empty = PacketCommitmentCircuit {
+            preimage: Vec::new(),
+            public_commitment: Value::unknown(),
+        };
+        (k + 1..=MAX_ZK_K).find(|&candidate| MockProver::<Fr>::run(candidate, &empty, Vec::new()).is_ok())
+    }
+
+    /// Returns the cached params and proving key of `scheme` for 2^k rows, generating them
+    /// on first use. A circuit that does not fit fails with the minimum viable `k`.
+    pub fn proving_setup(scheme: CommitmentScheme, k: u32) -> Result<&'static ProvingSetup> {
+        let mut setups = SETUPS.get_or_init(Default::default).lock().unwrap();
+        if let Some(setup) = setups.get(&(scheme, k)) {
+            return Ok(setup);
+        }
+
+        let setup = match keygen(scheme_params(scheme, k)?) {
+            Ok(setup) => setup,
+            Err(e) => match min_viable_k(k) {
+                Some(min_k) => bail!("ZK circuit does not fit in 2^{} rows; set [zk] k = {} or higher ({})", k, min_k, e),
+                None => bail!("ZK keygen failed for k = {}: {}", k, e),
+            },
+        };
+
+        let setup: &'static ProvingSetup = Box::leak(Box::new(setup));
+        setups.insert((scheme, k), setup);
+        Ok(setup)
+    }
+
+    /// Generates a ZK proof of packet commitment (WIP – currently proves nothing but compiles).
+    /// Uses the cached KZG setup for the default `k` and `OsRng`.
     pub fn generate_packet_proof(packet_data_hex: &str) -> Result<Vec<u8>> {
+        generate_packet_proof_with_rng(proving_setup(CommitmentScheme::Kzg, DEFAULT_ZK_K)?, packet_data_hex, OsRng)
+    }
+
+    /// Generates a proof with a caller-supplied setup and RNG. Production code should
+    /// use `generate_packet_proof` (`OsRng`); a seeded RNG makes proofs deterministic
+    /// for snapshot tests and benchmarks only.
+    pub fn generate_packet_proof_with_rng<R: RngCore + CryptoRng>(
+        setup: &ProvingSetup,
+        packet_data_hex: &str,
+        rng: R,
+    ) -> Result<Vec<u8>> {
         let preimage = hex::decode(packet_data_hex)?;
         let commitment: [u8; 32] = hash(&preimage).into();
 
-        let params = ParamsKZG::<Bn256>::setup(11, OsRng);
-
         let circuit = PacketCommitmentCircuit {
             preimage,
             public_commitment: Value::known(commitment),
         };
 
-        let vk = keygen_vk(&params, &circuit).map_err(|e| anyhow!("VK error: {:?}", e))?;
-        let pk = keygen_pk(&params, vk, &circuit).map_err(|e| anyhow!("PK error: {:?}", e))?;
-
         let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
 
         // Empty public instances – no public inputs yet
         let instances: &[Vec<Vec<Fr>>] = &[vec![]];
 
-        create_proof::<KZGCommitmentScheme<Bn256>, ProverGWC<_>, Challenge255<_>, _, _, _>(
-            &params,
-            &pk,
-            &[circuit],
-            instances,
-            OsRng,
-            &mut transcript,
-        )
+        match &setup.params {
+            SchemeParams::Kzg(params) => create_proof::<KZGCommitmentScheme<Bn256>, ProverGWC<_>, Challenge255<_>, _, _, _>(
+                params,
+                &setup.pk,
+                &[circuit],
+                instances,
+                rng,
+                &mut transcript,
+            ),
+            SchemeParams::Ipa(params) => create_proof::<IPACommitmentScheme<G1Affine>, ProverIPA<_>, Challenge255<_>, _, _, _>(
+                params,
+                &setup.pk,
+                &[circuit],
+                instances,
+                rng,
+                &mut transcript,
+            ),
+        }
         .map_err(|e| anyhow!("Proof error: {:?}", e))?;
 
-        let proof = transcript.finalize();
-        info!("ZK proof generated: {} bytes", proof.len());
+        // Tag the proof with its scheme so verifiers never check it against the wrong one
+        let mut proof = vec![proof_tag(setup.scheme())];
+        proof.extend(transcript.finalize());
+        info!("ZK proof generated: {} bytes ({:?})", proof.len(), setup.scheme());
 
         Ok(proof)
     }
+
+    /// Verifies a tagged proof against the cached setup of `scheme` for `k`. A proof
+    /// produced with another scheme is an error rather than a failed verification.
+    pub fn verify_packet_proof(scheme: CommitmentScheme, k: u32, proof: &[u8]) -> Result<bool> {
+        let (proof_scheme, proof) = split_proof(proof)?;
+        if proof_scheme != scheme {
+            bail!("Proof was produced with {:?}, expected {:?}", proof_scheme, scheme);
+        }
+        let setup = proving_setup(scheme, k)?;
+
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+        let instances: &[Vec<Vec<Fr>>] = &[vec![]];
+
+        let verified = match &setup.params {
+            SchemeParams::Kzg(params) => verify_proof::<KZGCommitmentScheme<Bn256>, VerifierGWC<_>, Challenge255<_>, _, _>(
+                params.verifier_params(),
+                setup.pk.get_vk(),
+                SingleStrategy::new(params),
+                instances,
+                &mut transcript,
+            )
+            .is_ok(),
+            SchemeParams::Ipa(params) => verify_proof::<IPACommitmentScheme<G1Affine>, VerifierIPA<_>, Challenge255<_>, _, _>(
+                params.verifier_params(),
+                setup.pk.get_vk(),
+                IpaSingleStrategy::new(params),
+                instances,
+                &mut transcript,
+            )
+            .is_ok(),
+        };
+
+        Ok(verified)
+    }
+
+    /// Exports the verifying key together with the scheme params for external verifiers.
+    ///
+    /// File layout (integers little-endian):
+    /// - 8 bytes  magic `AEROVK01` (KZG) or `AEROVKI1` (IPA)
+    /// - u32      circuit size `k` (2^k rows)
+    /// - u32 len + bytes: BN254 params – KZG: halo2 `ParamsKZG::write` (a verifier needs
+    ///   `g[0]`, `g2` and `s_g2` from it); IPA: halo2 `ParamsIPA::write`
+    /// - u32 len + bytes: verifying key (halo2 `VerifyingKey::write`, `SerdeFormat::RawBytes`)
+    ///
+    /// Proofs carry a one-byte scheme tag (0x01 KZG, 0x02 IPA) followed by the transcript:
+    /// GWC multi-open (KZG) or IPA multi-open, with a Blake2b transcript (`Challenge255`).
+    pub fn export_verifying_key(path: &str, scheme: CommitmentScheme, k: u32) -> Result<()> {
+        let setup = proving_setup(scheme, k)?;
+
+        let mut params_bytes = Vec::new();
+        let magic = match &setup.params {
+            SchemeParams::Kzg(params) => {
+                params.write(&mut params_bytes)?;
+                VK_FILE_MAGIC
+            }
+            SchemeParams::Ipa(params) => {
+                params.write(&mut params_bytes)?;
+                VK_FILE_MAGIC_IPA
+            }
+        };
+
+        let mut vk_bytes = Vec::new();
+        setup.pk.get_vk().write(&mut vk_bytes, SerdeFormat::RawBytes)?;
+
+        let mut writer = BufWriter::new(
+            File::create(path).context(format!("Failed to create {}", path))?,
+        );
+        writer.write_all(magic)?;
+        writer.write_all(&k.to_le_bytes())?;
+        writer.write_all(&(params_bytes.len() as u32).to_le_bytes())?;
+        writer.write_all(&params_bytes)?;
+        writer.write_all(&(vk_bytes.len() as u32).to_le_bytes())?;
+        writer.write_all(&vk_bytes)?;
+        writer.flush()?;
+
+        info!("Verifying key exported to {} ({} bytes VK)", path, vk_bytes.len());
+        Ok(())
+    }
 }
 
 #[cfg(feature = "encryption-proof")]
-pub use zk_impl::generate_packet_proof;
+pub use zk_impl::{
+    export_verifying_key, generate_packet_proof, generate_packet_proof_with_rng, keygen_with_rng, params_cached,
+    proving_setup, split_proof, verify_packet_proof, ProvingSetup,
+};
 
 #[cfg(not(feature = "encryption-proof"))]
 pub fn generate_packet_proof(_packet_data_hex: &str) -> anyhow::Result<Vec<u8>> {
     Ok(vec![])
-}
\ No newline at end of file
+}